@@ -0,0 +1,514 @@
+use crate::HttpRequest;
+use crate::url_encoded;
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use chrono::{DateTime, Local, Utc};
+use eframe::egui;
+use egui_json_tree::JsonTree;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+/// Bodies larger than this are not searched for tokens.
+const MAX_SCANNED_BODY: usize = 1024 * 1024;
+
+/// Keys JWT signatures are verified against, configured in Settings.
+#[derive(Default)]
+pub struct JwtKeys {
+    pub hmac_secret: String,
+    /// Treat the HMAC secret as base64 rather than raw bytes.
+    pub secret_is_base64: bool,
+    pub jwks: Option<JwkSet>,
+    /// File the JWKS was loaded from, for display.
+    pub jwks_source: Option<String>,
+    pub error: Option<String>,
+}
+
+impl JwtKeys {
+    pub fn clear_jwks(&mut self) {
+        self.jwks = None;
+        self.jwks_source = None;
+        self.error = None;
+    }
+}
+
+/// Asks for a JWKS file and uses it for verifying asymmetric signatures.
+pub fn load_jwks(ctx: egui::Context, keys: Arc<Mutex<JwtKeys>>) {
+    std::thread::spawn(move || {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON Web Key Set", &["json", "jwks"])
+            .add_filter("All files", &["*"])
+            .pick_file()
+        else {
+            return;
+        };
+
+        let loaded = std::fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| serde_json::from_slice::<JwkSet>(&bytes).map_err(|e| e.to_string()));
+        let mut keys = keys.lock().unwrap();
+        match loaded {
+            Ok(jwks) => {
+                keys.jwks = Some(jwks);
+                keys.jwks_source = Some(path.display().to_string());
+                keys.error = None;
+            }
+            Err(e) => keys.error = Some(format!("{}: {}", path.display(), e)),
+        }
+        ctx.request_repaint();
+    });
+}
+
+/// Credentials found in a request.
+pub enum Credential {
+    Jwt {
+        token: String,
+        header: Value,
+        payload: Value,
+    },
+    Basic {
+        username: String,
+        password: String,
+    },
+}
+
+/// A credential together with where it was found, e.g. `Header Authorization`.
+pub struct Found {
+    pub source: String,
+    pub credential: Credential,
+}
+
+/// Searches headers, cookies, query parameters and JSON or form body fields
+/// for JWTs and Basic credentials.
+pub fn find_credentials(req: &HttpRequest) -> Vec<Found> {
+    let mut found = Vec::new();
+    let mut push_jwts = |source: String, value: &str| {
+        for token in value.split([' ', ',', ';']) {
+            if let Some(credential) = decode_jwt(token.trim()) {
+                found.push(Found {
+                    source: source.clone(),
+                    credential,
+                });
+            }
+        }
+    };
+
+    let mut basic = Vec::new();
+    for (name, value) in &req.headers {
+        if name.eq_ignore_ascii_case("cookie") {
+            for cookie in value.split(';') {
+                if let Some((cookie_name, cookie_value)) = cookie.split_once('=') {
+                    push_jwts(format!("Cookie {}", cookie_name.trim()), cookie_value);
+                }
+            }
+        } else if (name.eq_ignore_ascii_case("authorization")
+            || name.eq_ignore_ascii_case("proxy-authorization"))
+            && let Some(credential) = decode_basic(value)
+        {
+            basic.push(Found {
+                source: format!("Header {}", name),
+                credential,
+            });
+        } else {
+            push_jwts(format!("Header {}", name), value);
+        }
+    }
+
+    for (name, value) in &req.query_params {
+        push_jwts(format!("Query {}", name), value);
+    }
+
+    let body = req.display_body();
+    if !body.is_empty() && body.len() <= MAX_SCANNED_BODY {
+        if let Ok(json) = serde_json::from_slice::<Value>(body) {
+            let mut strings = Vec::new();
+            collect_strings(&json, "$".to_string(), &mut strings);
+            for (path, value) in strings {
+                push_jwts(format!("Body {}", path), &value);
+            }
+        } else if let Ok(text) = std::str::from_utf8(body)
+            && req.headers.iter().any(|(name, value)| {
+                name.eq_ignore_ascii_case("content-type")
+                    && value
+                        .to_ascii_lowercase()
+                        .starts_with("application/x-www-form-urlencoded")
+            })
+        {
+            for (name, value) in url_encoded::parse_pairs(text, true) {
+                push_jwts(format!("Body {}", name), &value);
+            }
+        }
+    }
+
+    basic.append(&mut found);
+    basic
+}
+
+fn collect_strings(value: &Value, path: String, out: &mut Vec<(String, String)>) {
+    match value {
+        Value::String(s) => out.push((path, s.clone())),
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                collect_strings(item, format!("{}[{}]", path, i), out);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map {
+                collect_strings(item, format!("{}.{}", path, key), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Decodes a compact JWS. The header must be a JSON object naming an `alg`,
+/// which keeps ordinary dotted strings from matching.
+fn decode_jwt(token: &str) -> Option<Credential> {
+    let mut segments = token.split('.');
+    let (header, payload, signature) = (segments.next()?, segments.next()?, segments.next()?);
+    if segments.next().is_some() || header.is_empty() || payload.is_empty() {
+        return None;
+    }
+    let is_base64url = |s: &str| {
+        s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+    };
+    if !is_base64url(header) || !is_base64url(payload) || !is_base64url(signature) {
+        return None;
+    }
+
+    let header: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header).ok()?).ok()?;
+    if !header.get("alg").is_some_and(Value::is_string) {
+        return None;
+    }
+    let payload = URL_SAFE_NO_PAD.decode(payload).ok()?;
+    let payload = serde_json::from_slice(&payload)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&payload).into_owned()));
+    Some(Credential::Jwt {
+        token: token.to_string(),
+        header,
+        payload,
+    })
+}
+
+fn decode_basic(value: &str) -> Option<Credential> {
+    let (scheme, encoded) = value.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let decoded = STANDARD.decode(encoded.trim()).ok()?;
+    let decoded = String::from_utf8_lossy(&decoded);
+    let (username, password) = decoded.split_once(':').unwrap_or((&decoded, ""));
+    Some(Credential::Basic {
+        username: username.to_string(),
+        password: password.to_string(),
+    })
+}
+
+/// Outcome of checking a JWT signature.
+enum Verification {
+    Valid(String),
+    Invalid(String),
+    /// No key is configured that could check this token.
+    NoKey(String),
+}
+
+fn verify(token: &str, keys: &JwtKeys) -> Verification {
+    let header = match jsonwebtoken::decode_header(token) {
+        Ok(header) => header,
+        Err(e) => return Verification::Invalid(format!("unsupported header: {}", e)),
+    };
+
+    let (key, key_name) = match header.alg {
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+            if keys.hmac_secret.is_empty() {
+                return Verification::NoKey("configure an HMAC secret in Settings".to_string());
+            }
+            let key = if keys.secret_is_base64 {
+                match DecodingKey::from_base64_secret(keys.hmac_secret.trim()) {
+                    Ok(key) => key,
+                    Err(e) => return Verification::Invalid(format!("HMAC secret: {}", e)),
+                }
+            } else {
+                DecodingKey::from_secret(keys.hmac_secret.as_bytes())
+            };
+            (key, "HMAC secret".to_string())
+        }
+        _ => {
+            let Some(jwks) = &keys.jwks else {
+                return Verification::NoKey("load a JWKS file in Settings".to_string());
+            };
+            let jwk = match &header.kid {
+                Some(kid) => jwks.find(kid),
+                None if jwks.keys.len() == 1 => jwks.keys.first(),
+                None => None,
+            };
+            let Some(jwk) = jwk else {
+                return Verification::NoKey(match &header.kid {
+                    Some(kid) => format!("no key with kid \"{}\" in the JWKS", kid),
+                    None => "token has no kid and the JWKS holds several keys".to_string(),
+                });
+            };
+            match DecodingKey::from_jwk(jwk) {
+                Ok(key) => (
+                    key,
+                    match &jwk.common.key_id {
+                        Some(kid) => format!("JWKS key {}", kid),
+                        None => "JWKS key".to_string(),
+                    },
+                ),
+                Err(e) => return Verification::Invalid(format!("JWKS key: {}", e)),
+            }
+        }
+    };
+
+    // Only the signature is checked here; time claims are shown separately
+    let mut validation = Validation::new(header.alg);
+    validation.validate_exp = false;
+    validation.validate_nbf = false;
+    validation.validate_aud = false;
+    validation.required_spec_claims.clear();
+    match jsonwebtoken::decode::<Value>(token, &key, &validation) {
+        Ok(_) => Verification::Valid(key_name),
+        Err(e) => Verification::Invalid(format!("{} ({})", e, key_name)),
+    }
+}
+
+/// Renders the credentials found in a request.
+pub fn render_credentials(ui: &mut egui::Ui, req_id: u64, found: &[Found], keys: &JwtKeys) {
+    for (i, found) in found.iter().enumerate() {
+        let id = egui::Id::new(("credential", req_id, i));
+        let title = match &found.credential {
+            Credential::Jwt { header, .. } => format!(
+                "JWT ({}) · {}",
+                header.get("alg").and_then(Value::as_str).unwrap_or("?"),
+                found.source
+            ),
+            Credential::Basic { .. } => format!("Basic · {}", found.source),
+        };
+        egui::CollapsingHeader::new(title)
+            .id_salt(id)
+            .default_open(true)
+            .show(ui, |ui| match &found.credential {
+                Credential::Jwt {
+                    token,
+                    header,
+                    payload,
+                } => render_jwt(ui, id, token, header, payload, keys),
+                Credential::Basic { username, password } => {
+                    egui::Grid::new(id.with("basic"))
+                        .striped(true)
+                        .spacing([10.0, 5.0])
+                        .show(ui, |ui| {
+                            ui.label(egui::RichText::new("Username").strong());
+                            ui.label(egui::RichText::new(username).monospace());
+                            ui.end_row();
+                            ui.label(egui::RichText::new("Password").strong());
+                            ui.label(egui::RichText::new(password).monospace());
+                            ui.end_row();
+                        });
+                }
+            });
+    }
+}
+
+fn render_jwt(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    token: &str,
+    header: &Value,
+    payload: &Value,
+    keys: &JwtKeys,
+) {
+    let now = Utc::now().timestamp();
+    egui::Grid::new(id.with("claims"))
+        .striped(true)
+        .spacing([10.0, 5.0])
+        .show(ui, |ui| {
+            ui.label(egui::RichText::new("Signature").strong());
+            match verify(token, keys) {
+                Verification::Valid(key) => ui.label(
+                    egui::RichText::new(format!("✔ Valid ({})", key)).color(egui::Color32::GREEN),
+                ),
+                Verification::Invalid(e) => ui.label(
+                    egui::RichText::new(format!("✖ Invalid: {}", e)).color(egui::Color32::RED),
+                ),
+                Verification::NoKey(hint) => ui.label(
+                    egui::RichText::new(format!("Not verified: {}", hint))
+                        .italics()
+                        .weak(),
+                ),
+            };
+            ui.end_row();
+
+            for (claim, label) in [
+                ("exp", "Expires"),
+                ("nbf", "Not before"),
+                ("iat", "Issued at"),
+            ] {
+                let Some(seconds) = payload.get(claim).and_then(Value::as_i64) else {
+                    continue;
+                };
+                ui.label(egui::RichText::new(format!("{} ({})", label, claim)).strong());
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(format_time(seconds, now)).monospace());
+                    if claim == "exp" && seconds <= now {
+                        ui.label(
+                            egui::RichText::new("EXPIRED")
+                                .strong()
+                                .color(egui::Color32::RED),
+                        );
+                    }
+                    if claim == "nbf" && seconds > now {
+                        ui.label(
+                            egui::RichText::new("NOT YET VALID")
+                                .strong()
+                                .color(egui::Color32::YELLOW),
+                        );
+                    }
+                });
+                ui.end_row();
+            }
+        });
+    ui.add_space(5.0);
+
+    for (label, value) in [("Header", header), ("Payload", payload)] {
+        ui.label(egui::RichText::new(label).strong());
+        egui::Frame::new()
+            .fill(egui::Color32::from_gray(30))
+            .inner_margin(10.0)
+            .show(ui, |ui| {
+                JsonTree::new(id.with(label), value).show(ui);
+            });
+        ui.add_space(5.0);
+    }
+}
+
+/// Local time of a NumericDate claim with its distance from now.
+fn format_time(seconds: i64, now: i64) -> String {
+    let Some(time) = DateTime::from_timestamp(seconds, 0) else {
+        return seconds.to_string();
+    };
+    let local = time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S %:z");
+    let delta = seconds - now;
+    let span = format_span(delta.unsigned_abs());
+    if delta >= 0 {
+        format!("{} (in {})", local, span)
+    } else {
+        format!("{} ({} ago)", local, span)
+    }
+}
+
+fn format_span(seconds: u64) -> String {
+    match seconds {
+        0..60 => format!("{}s", seconds),
+        60..3600 => format!("{}m {}s", seconds / 60, seconds % 60),
+        3600..86400 => format!("{}h {}m", seconds / 3600, seconds / 60 % 60),
+        _ => format!("{}d {}h", seconds / 86400, seconds / 3600 % 24),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(header: &str, payload: &str) -> String {
+        format!(
+            "{}.{}.sig",
+            URL_SAFE_NO_PAD.encode(header),
+            URL_SAFE_NO_PAD.encode(payload)
+        )
+    }
+
+    fn sources(found: &[Found]) -> Vec<&str> {
+        found.iter().map(|f| f.source.as_str()).collect()
+    }
+
+    #[test]
+    fn tokens_are_found_in_headers_cookies_query_and_body() {
+        let jwt = token(r#"{"alg":"HS256"}"#, r#"{"sub":"42"}"#);
+        let req = HttpRequest {
+            headers: vec![
+                (String::from("Authorization"), format!("Bearer {}", jwt)),
+                (
+                    String::from("Cookie"),
+                    format!("theme=dark; session={}", jwt),
+                ),
+                (
+                    String::from("Content-Type"),
+                    String::from("application/json"),
+                ),
+            ],
+            query_params: vec![(String::from("access_token"), jwt.clone())],
+            body: format!(r#"{{"auth": {{"tokens": ["x", "{}"]}}}}"#, jwt).into_bytes(),
+            ..Default::default()
+        };
+        let found = find_credentials(&req);
+        assert_eq!(
+            sources(&found),
+            [
+                "Header Authorization",
+                "Cookie session",
+                "Query access_token",
+                "Body $.auth.tokens[1]"
+            ]
+        );
+        let Credential::Jwt { payload, .. } = &found[0].credential else {
+            panic!("expected a JWT");
+        };
+        assert_eq!(payload["sub"], "42");
+    }
+
+    #[test]
+    fn dotted_strings_without_an_algorithm_are_not_tokens() {
+        assert!(decode_jwt("www.example.com").is_none());
+        assert!(decode_jwt(&token(r#"{"typ":"JWT"}"#, "{}")).is_none());
+        assert!(decode_jwt(&token(r#"{"alg":"none"}"#, "{}")).is_some());
+        // A payload that is not JSON is kept as text
+        let Some(Credential::Jwt { payload, .. }) = decode_jwt(&token(r#"{"alg":"none"}"#, "hi"))
+        else {
+            panic!("expected a JWT");
+        };
+        assert_eq!(payload, "hi");
+    }
+
+    #[test]
+    fn basic_credentials_are_decoded_and_listed_first() {
+        let req = HttpRequest {
+            headers: vec![
+                (String::from("X-Token"), token(r#"{"alg":"none"}"#, "{}")),
+                (
+                    String::from("Proxy-Authorization"),
+                    format!("basic {}", STANDARD.encode("alice:p:w")),
+                ),
+            ],
+            ..Default::default()
+        };
+        let found = find_credentials(&req);
+        assert_eq!(
+            sources(&found),
+            ["Header Proxy-Authorization", "Header X-Token"]
+        );
+        let Credential::Basic { username, password } = &found[0].credential else {
+            panic!("expected Basic credentials");
+        };
+        assert_eq!((username.as_str(), password.as_str()), ("alice", "p:w"));
+        assert!(decode_basic("Bearer abc").is_none());
+        assert!(decode_basic("Basic !!!").is_none());
+    }
+
+    #[test]
+    fn hmac_signatures_are_verified_with_the_configured_secret() {
+        let claims = serde_json::json!({ "sub": "42" });
+        let key = jsonwebtoken::EncodingKey::from_secret(b"secret");
+        let jwt = jsonwebtoken::encode(&jsonwebtoken::Header::default(), &claims, &key).unwrap();
+
+        let mut keys = JwtKeys::default();
+        assert!(matches!(verify(&jwt, &keys), Verification::NoKey(_)));
+        keys.hmac_secret = String::from("secret");
+        assert!(matches!(verify(&jwt, &keys), Verification::Valid(_)));
+        keys.hmac_secret = String::from("other");
+        assert!(matches!(verify(&jwt, &keys), Verification::Invalid(_)));
+    }
+}
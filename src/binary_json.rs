@@ -0,0 +1,242 @@
+use serde_json::{Map, Value};
+use std::io::Cursor;
+
+/// Binary encodings of JSON-like data that are decoded into a JSON tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryFormat {
+    MessagePack,
+    Cbor,
+    Bson,
+}
+
+impl BinaryFormat {
+    pub const ALL: [BinaryFormat; 3] = [
+        BinaryFormat::MessagePack,
+        BinaryFormat::Cbor,
+        BinaryFormat::Bson,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BinaryFormat::MessagePack => "MessagePack",
+            BinaryFormat::Cbor => "CBOR",
+            BinaryFormat::Bson => "BSON",
+        }
+    }
+
+    /// Picks the format named by a content type such as `application/msgpack`.
+    pub fn detect(content_type: &str) -> Option<Self> {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+        let subtype = mime.rsplit(['/', '+']).next().unwrap_or("");
+
+        match subtype {
+            "msgpack" | "x-msgpack" | "vnd.msgpack" => Some(BinaryFormat::MessagePack),
+            "cbor" | "cbor-seq" => Some(BinaryFormat::Cbor),
+            "bson" | "x-bson" => Some(BinaryFormat::Bson),
+            _ => None,
+        }
+    }
+
+    /// Decodes `data` into a JSON value. Several concatenated values, as in
+    /// streams or CBOR sequences, become an array.
+    pub fn decode(self, data: &[u8]) -> Result<Value, String> {
+        let mut cursor = Cursor::new(data);
+        let mut values = Vec::new();
+        while (cursor.position() as usize) < data.len() {
+            let value = match self {
+                BinaryFormat::MessagePack => rmpv::decode::read_value(&mut cursor)
+                    .map(msgpack_to_json)
+                    .map_err(|e| e.to_string()),
+                BinaryFormat::Cbor => ciborium::from_reader::<ciborium::Value, _>(&mut cursor)
+                    .map(cbor_to_json)
+                    .map_err(|e| e.to_string()),
+                BinaryFormat::Bson => bson::Document::from_reader(&mut cursor)
+                    .map(|doc| bson::Bson::Document(doc).into_relaxed_extjson())
+                    .map_err(|e| e.to_string()),
+            };
+            match value {
+                Ok(value) => values.push(value),
+                Err(e) if values.is_empty() => return Err(e),
+                Err(e) => {
+                    return Err(format!(
+                        "value {} at byte {}: {}",
+                        values.len() + 1,
+                        cursor.position(),
+                        e
+                    ));
+                }
+            }
+        }
+
+        match values.len() {
+            0 => Err("empty body".to_string()),
+            1 => Ok(values.remove(0)),
+            _ => Ok(Value::Array(values)),
+        }
+    }
+}
+
+fn msgpack_to_json(value: rmpv::Value) -> Value {
+    match value {
+        rmpv::Value::Nil => Value::Null,
+        rmpv::Value::Boolean(b) => Value::Bool(b),
+        rmpv::Value::Integer(i) => match (i.as_u64(), i.as_i64()) {
+            (Some(u), _) => Value::from(u),
+            (None, Some(i)) => Value::from(i),
+            _ => Value::Null,
+        },
+        rmpv::Value::F32(f) => Value::from(f as f64),
+        rmpv::Value::F64(f) => Value::from(f),
+        rmpv::Value::String(s) => Value::String(msgpack_string(&s)),
+        rmpv::Value::Binary(bytes) => Value::String(hex(&bytes)),
+        rmpv::Value::Array(items) => Value::Array(items.into_iter().map(msgpack_to_json).collect()),
+        rmpv::Value::Map(entries) => {
+            let mut map = Map::new();
+            for (key, value) in entries {
+                let key = match key {
+                    rmpv::Value::String(s) => msgpack_string(&s),
+                    other => other.to_string(),
+                };
+                map.insert(key, msgpack_to_json(value));
+            }
+            Value::Object(map)
+        }
+        rmpv::Value::Ext(kind, data) => {
+            let mut map = Map::new();
+            map.insert("$ext".to_string(), Value::from(kind));
+            map.insert("data".to_string(), Value::String(hex(&data)));
+            Value::Object(map)
+        }
+    }
+}
+
+/// MessagePack strings may hold invalid UTF-8, which is shown lossily.
+fn msgpack_string(s: &rmpv::Utf8String) -> String {
+    String::from_utf8_lossy(s.as_bytes()).into_owned()
+}
+
+fn cbor_to_json(value: ciborium::Value) -> Value {
+    match value {
+        ciborium::Value::Null => Value::Null,
+        ciborium::Value::Bool(b) => Value::Bool(b),
+        ciborium::Value::Integer(i) => {
+            let i = i128::from(i);
+            u64::try_from(i)
+                .map(Value::from)
+                .or_else(|_| i64::try_from(i).map(Value::from))
+                .unwrap_or_else(|_| Value::String(i.to_string()))
+        }
+        ciborium::Value::Float(f) => Value::from(f),
+        ciborium::Value::Text(s) => Value::String(s),
+        ciborium::Value::Bytes(bytes) => Value::String(hex(&bytes)),
+        ciborium::Value::Array(items) => {
+            Value::Array(items.into_iter().map(cbor_to_json).collect())
+        }
+        ciborium::Value::Map(entries) => {
+            let mut map = Map::new();
+            for (key, value) in entries {
+                let key = match cbor_to_json(key) {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                map.insert(key, cbor_to_json(value));
+            }
+            Value::Object(map)
+        }
+        ciborium::Value::Tag(tag, value) => {
+            let mut map = Map::new();
+            map.insert("$tag".to_string(), Value::from(tag));
+            map.insert("value".to_string(), cbor_to_json(*value));
+            Value::Object(map)
+        }
+        _ => Value::Null,
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    hex.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn formats_are_detected_from_the_content_type() {
+        let detect = BinaryFormat::detect;
+        assert_eq!(
+            detect("application/x-msgpack"),
+            Some(BinaryFormat::MessagePack)
+        );
+        assert_eq!(
+            detect("application/vnd.api+cbor; q=1"),
+            Some(BinaryFormat::Cbor)
+        );
+        assert_eq!(detect("Application/BSON"), Some(BinaryFormat::Bson));
+        assert_eq!(detect("application/json"), None);
+    }
+
+    #[test]
+    fn msgpack_maps_to_json() {
+        // {"a": 1, "b": [true, nil], "c": bin ff}, then {1: -1}
+        let data = [
+            0x83, 0xa1, b'a', 0x01, 0xa1, b'b', 0x92, 0xc3, 0xc0, 0xa1, b'c', 0xc4, 0x01, 0xff,
+            0x81, 0x01, 0xff,
+        ];
+        assert_eq!(
+            BinaryFormat::MessagePack.decode(&data).unwrap(),
+            json!([{"a": 1, "b": [true, null], "c": "ff"}, {"1": -1}])
+        );
+    }
+
+    #[test]
+    fn cbor_maps_to_json() {
+        // {"a": h'0102', "t": 1(1000), "n": -2^64}
+        let data = [
+            0xa3, 0x61, b'a', 0x42, 0x01, 0x02, 0x61, b't', 0xc1, 0x19, 0x03, 0xe8, 0x61, b'n',
+            0x3b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        ];
+        assert_eq!(
+            BinaryFormat::Cbor.decode(&data).unwrap(),
+            json!({
+                "a": "01 02",
+                "t": {"$tag": 1, "value": 1000},
+                "n": "-18446744073709551616",
+            })
+        );
+    }
+
+    #[test]
+    fn bson_documents_map_to_relaxed_extended_json() {
+        let mut data = Vec::new();
+        bson::doc! { "a": 1, "b": "x" }
+            .to_writer(&mut data)
+            .unwrap();
+        bson::doc! { "c": [true] }.to_writer(&mut data).unwrap();
+        assert_eq!(
+            BinaryFormat::Bson.decode(&data).unwrap(),
+            json!([{"a": 1, "b": "x"}, {"c": [true]}])
+        );
+    }
+
+    #[test]
+    fn errors_name_the_value_that_failed() {
+        assert_eq!(
+            BinaryFormat::Cbor.decode(&[]),
+            Err("empty body".to_string())
+        );
+        // The second value is an array of two with one item
+        let error = BinaryFormat::MessagePack
+            .decode(&[0x01, 0x92, 0x01])
+            .unwrap_err();
+        assert!(error.starts_with("value 2 at byte"), "{}", error);
+        assert!(BinaryFormat::Bson.decode(&[0x05, 0x00]).is_err());
+    }
+}
@@ -0,0 +1,457 @@
+use crate::HttpRequest;
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+
+/// Limits on how much captured data is kept.
+#[derive(Clone, Debug)]
+pub struct CaptureLimits {
+    /// The oldest requests are dropped beyond this count.
+    pub max_requests: usize,
+    /// Bodies larger than this keep only a prefix, their length and hash.
+    pub max_body_size: usize,
+    /// The oldest requests are dropped while stored data exceeds this.
+    pub memory_budget: usize,
+    /// Write bodies larger than `max_body_size` to a temporary file in full.
+    pub spill_to_disk: bool,
+}
+
+impl Default for CaptureLimits {
+    fn default() -> Self {
+        Self {
+            max_requests: 10_000,
+            max_body_size: 10 * 1024 * 1024,
+            memory_budget: 512 * 1024 * 1024,
+            spill_to_disk: false,
+        }
+    }
+}
+
+/// How much of a request body has arrived.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum BodyState {
+    Receiving,
+    #[default]
+    Complete,
+    /// The client went away or sent an invalid body; holds the reason.
+    Aborted(String),
+}
+
+/// A piece of the body as it arrived.
+#[derive(Clone, Copy, Debug)]
+pub struct BodyChunk {
+    /// Time since the request head was received.
+    pub at: Duration,
+    pub len: usize,
+}
+
+/// What is known about a body beyond the stored prefix.
+#[derive(Clone, Debug)]
+pub struct TruncatedBody {
+    /// SHA-256 of the complete body, hex encoded.
+    pub sha256: String,
+    /// The complete body, if it was spilled to disk.
+    pub spill_path: Option<PathBuf>,
+    pub spill_error: Option<String>,
+}
+
+/// Captured requests, oldest first. Request ids are assigned on push and are
+/// contiguous, so a request is found by id without searching.
+pub struct CaptureStore {
+    requests: VecDeque<HttpRequest>,
+    next_id: u64,
+    memory_used: usize,
+    /// Requests dropped to stay within the limits since the last clear.
+    pub evicted: usize,
+    pub limits: CaptureLimits,
+}
+
+impl Default for CaptureStore {
+    fn default() -> Self {
+        Self {
+            requests: VecDeque::new(),
+            next_id: 1,
+            memory_used: 0,
+            evicted: 0,
+            limits: CaptureLimits::default(),
+        }
+    }
+}
+
+impl CaptureStore {
+    /// Stores a request under a new id, evicting the oldest requests if the
+    /// limits are exceeded.
+    pub fn push(&mut self, mut req: HttpRequest) -> u64 {
+        let id = self.next_id;
+        req.id = id;
+        self.next_id += 1;
+        self.memory_used += stored_size(&req);
+        self.requests.push_back(req);
+        self.enforce_limits();
+        id
+    }
+
+    /// Changes a stored request in place, e.g. as its body arrives. Returns
+    /// `None` if the request was evicted.
+    pub fn update<R>(&mut self, id: u64, f: impl FnOnce(&mut HttpRequest) -> R) -> Option<R> {
+        let index = self.index_of(id)?;
+        let req = self.requests.get_mut(index)?;
+        let before = stored_size(req);
+        let result = f(req);
+        self.memory_used = self.memory_used - before + stored_size(req);
+        self.enforce_limits();
+        Some(result)
+    }
+
+    /// Evicts the oldest requests until the limits hold. The newest request
+    /// is kept even if it alone exceeds the memory budget.
+    pub fn enforce_limits(&mut self) {
+        while self.requests.len() > self.limits.max_requests.max(1)
+            || (self.memory_used > self.limits.memory_budget && self.requests.len() > 1)
+        {
+            let Some(req) = self.requests.pop_front() else {
+                break;
+            };
+            self.memory_used -= stored_size(&req);
+            self.evicted += 1;
+            remove_spill_file(&req);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for req in self.requests.drain(..) {
+            remove_spill_file(&req);
+        }
+        self.memory_used = 0;
+        self.evicted = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &HttpRequest> {
+        self.requests.iter()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&HttpRequest> {
+        self.requests.get(self.index_of(id)?)
+    }
+
+    fn index_of(&self, id: u64) -> Option<usize> {
+        let first = self.requests.front()?.id;
+        usize::try_from(id.checked_sub(first)?).ok()
+    }
+
+    /// Stored requests with an id above `id`, oldest first.
+    pub fn newer_than(&self, id: u64) -> impl Iterator<Item = &HttpRequest> {
+        let skip = id.saturating_add(1).saturating_sub(self.first_id());
+        self.requests.range(
+            usize::try_from(skip)
+                .unwrap_or(usize::MAX)
+                .min(self.requests.len())..,
+        )
+    }
+
+    /// Id of the oldest stored request, or of the next one if none are stored.
+    pub fn first_id(&self) -> u64 {
+        self.requests.front().map_or(self.next_id, |req| req.id)
+    }
+
+    /// Id of the newest stored request, 0 if none were stored yet.
+    pub fn last_id(&self) -> u64 {
+        self.next_id - 1
+    }
+
+    /// Approximate bytes held by stored requests.
+    pub fn memory_used(&self) -> usize {
+        self.memory_used
+    }
+}
+
+impl Drop for CaptureStore {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Approximate heap size of a request, dominated by its body copies.
+fn stored_size(req: &HttpRequest) -> usize {
+    let fields: usize = req
+        .headers
+        .iter()
+        .chain(&req.trailers)
+        .chain(&req.query_params)
+        .map(|(name, value)| name.len() + value.len())
+        .sum();
+    req.body.len()
+        + req.decoded_body.as_ref().map_or(0, Vec::len)
+        + req.raw.len()
+        + req.path.len()
+        + req.chunks.len() * std::mem::size_of::<BodyChunk>()
+        + fields
+}
+
+fn remove_spill_file(req: &HttpRequest) {
+    if let Some(path) = req.truncated.as_ref().and_then(|t| t.spill_path.as_ref()) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Receives a body chunk by chunk, deciding how much of it is kept in memory
+/// and optionally writing the whole body to a temporary file. The kept prefix
+/// itself lives in the stored request.
+pub struct BodyCapture {
+    total: usize,
+    hasher: Sha256,
+    max_body_size: usize,
+    spill_to_disk: bool,
+    spill: Option<(PathBuf, BufWriter<File>)>,
+    spill_error: Option<String>,
+}
+
+impl BodyCapture {
+    pub fn new(limits: &CaptureLimits) -> Self {
+        Self {
+            total: 0,
+            hasher: Sha256::new(),
+            max_body_size: limits.max_body_size,
+            spill_to_disk: limits.spill_to_disk,
+            spill: None,
+            spill_error: None,
+        }
+    }
+
+    /// Hashes `chunk` and splits it into the part to keep in memory and the
+    /// part beyond the body size limit.
+    pub fn push<'a>(&mut self, chunk: &'a [u8]) -> (&'a [u8], &'a [u8]) {
+        let room = self.max_body_size.saturating_sub(self.total);
+        self.total += chunk.len();
+        self.hasher.update(chunk);
+        chunk.split_at(room.min(chunk.len()))
+    }
+
+    /// Whether spilling `rest` needs the kept prefix, because the spill file
+    /// does not exist yet.
+    pub fn needs_prefix(&self, rest: &[u8]) -> bool {
+        !rest.is_empty() && self.spill_to_disk && self.spill.is_none() && self.spill_error.is_none()
+    }
+
+    /// Writes the part of a chunk beyond the limit to the spill file, creating
+    /// it from `prefix` first if needed.
+    pub async fn spill(&mut self, prefix: Option<Vec<u8>>, rest: &[u8]) {
+        if rest.is_empty() || !self.spill_to_disk || self.spill_error.is_some() {
+            return;
+        }
+        let result = match (&mut self.spill, prefix) {
+            (Some((_, file)), _) => file.write_all(rest).await,
+            // The request was evicted, so there is nothing to spill for
+            (None, None) => return,
+            (spill @ None, Some(prefix)) => match create_spill_file().await {
+                Ok(created) => {
+                    let (_, file) = spill.insert(created);
+                    // The file starts with everything received so far
+                    match file.write_all(&prefix).await {
+                        Ok(()) => file.write_all(rest).await,
+                        Err(e) => Err(e),
+                    }
+                }
+                Err(e) => Err(e),
+            },
+        };
+        if let Err(e) = result {
+            self.spill_error = Some(e.to_string());
+        }
+    }
+
+    /// Bytes received so far.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// What is known about the body beyond the kept prefix, `None` if the
+    /// whole body fit.
+    pub async fn finish(mut self) -> Option<TruncatedBody> {
+        if self.total <= self.max_body_size {
+            return None;
+        }
+        let mut spill_error = self.spill_error.take();
+        let spill_path = match self.spill.take() {
+            Some((path, mut file)) => match file.flush().await {
+                Ok(()) if spill_error.is_none() => Some(path),
+                result => {
+                    if let Err(e) = result {
+                        spill_error = Some(e.to_string());
+                    }
+                    drop(file);
+                    let _ = tokio::fs::remove_file(&path).await;
+                    None
+                }
+            },
+            None => None,
+        };
+        let sha256 = std::mem::take(&mut self.hasher)
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        Some(TruncatedBody {
+            sha256,
+            spill_path,
+            spill_error,
+        })
+    }
+}
+
+/// A body that was not finished, e.g. an aborted upload, does not keep its
+/// spill file.
+impl Drop for BodyCapture {
+    fn drop(&mut self) {
+        if let Some((path, file)) = self.spill.take() {
+            drop(file);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+async fn create_spill_file() -> std::io::Result<(PathBuf, BufWriter<File>)> {
+    static NEXT_FILE: AtomicU64 = AtomicU64::new(0);
+    let dir = std::env::temp_dir().join("inspector-http");
+    tokio::fs::create_dir_all(&dir).await?;
+    let path = dir.join(format!(
+        "{}-{}.body",
+        std::process::id(),
+        NEXT_FILE.fetch_add(1, Ordering::SeqCst)
+    ));
+    let file = File::create(&path).await?;
+    Ok((path, BufWriter::new(file)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(body_len: usize) -> HttpRequest {
+        HttpRequest {
+            body: vec![b'x'; body_len],
+            ..Default::default()
+        }
+    }
+
+    fn ids(store: &CaptureStore) -> Vec<u64> {
+        store.iter().map(|req| req.id).collect()
+    }
+
+    #[test]
+    fn oldest_requests_are_evicted_beyond_the_count() {
+        let mut store = CaptureStore::default();
+        store.limits.max_requests = 3;
+        for _ in 0..5 {
+            store.push(request(0));
+        }
+        assert_eq!(ids(&store), [3, 4, 5]);
+        assert_eq!(store.evicted, 2);
+        assert_eq!((store.first_id(), store.last_id()), (3, 5));
+    }
+
+    #[test]
+    fn memory_budget_keeps_at_least_the_newest_request() {
+        let mut store = CaptureStore::default();
+        store.limits.memory_budget = 250;
+        store.push(request(100));
+        store.push(request(100));
+        assert_eq!(store.len(), 2);
+        store.push(request(100));
+        assert_eq!(ids(&store), [2, 3]);
+        assert!(store.memory_used() <= 250);
+
+        store.push(request(1000));
+        assert_eq!(ids(&store), [4]);
+
+        // Growing a body also evicts
+        let mut store = CaptureStore::default();
+        store.limits.memory_budget = 250;
+        store.push(request(100));
+        let id = store.push(request(100));
+        store.update(id, |req| req.body.extend_from_slice(&[0; 100]));
+        assert_eq!(ids(&store), [id]);
+    }
+
+    #[test]
+    fn ids_map_to_ring_buffer_positions() {
+        let mut store = CaptureStore::default();
+        store.limits.max_requests = 2;
+        for _ in 0..4 {
+            store.push(request(0));
+        }
+        assert_eq!(store.index_of(3), Some(0));
+        assert_eq!(store.index_of(4), Some(1));
+        assert_eq!(store.index_of(2), None);
+        assert!(store.get(2).is_none());
+        assert_eq!(store.get(4).map(|req| req.id), Some(4));
+        assert!(store.get(5).is_none());
+        assert!(store.update(1, |_| ()).is_none());
+
+        let newer: Vec<u64> = store.newer_than(0).map(|req| req.id).collect();
+        assert_eq!(newer, [3, 4]);
+        assert_eq!(store.newer_than(3).count(), 1);
+        assert_eq!(store.newer_than(4).count(), 0);
+    }
+
+    #[test]
+    fn clear_keeps_ids_increasing() {
+        let mut store = CaptureStore::default();
+        store.push(request(10));
+        store.clear();
+        assert!(store.is_empty());
+        assert_eq!(store.memory_used(), 0);
+        assert_eq!(store.first_id(), 2);
+        assert_eq!(store.push(request(0)), 2);
+    }
+
+    #[tokio::test]
+    async fn large_bodies_keep_a_prefix_and_spill_in_full() {
+        let limits = CaptureLimits {
+            max_body_size: 4,
+            spill_to_disk: true,
+            ..Default::default()
+        };
+        let mut capture = BodyCapture::new(&limits);
+        let mut body = Vec::new();
+        for chunk in [&b"abc"[..], b"defg", b"hij"] {
+            let (kept, rest) = capture.push(chunk);
+            let prefix = capture
+                .needs_prefix(rest)
+                .then(|| [&body[..], kept].concat());
+            body.extend_from_slice(kept);
+            capture.spill(prefix, rest).await;
+        }
+        assert_eq!(body, b"abcd");
+        assert_eq!(capture.total(), 10);
+
+        let truncated = capture.finish().await.unwrap();
+        assert_eq!(
+            truncated.sha256,
+            "72399361da6a7754fec986dca5b7cbaf1c810a28ded4abaf56b2106d06cb78b0"
+        );
+        let path = truncated.spill_path.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"abcdefghij");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn small_bodies_are_not_truncated() {
+        let mut capture = BodyCapture::new(&CaptureLimits::default());
+        let (kept, rest) = capture.push(b"hello");
+        assert_eq!((kept, rest), (&b"hello"[..], &b""[..]));
+        assert!(capture.finish().await.is_none());
+    }
+}
@@ -0,0 +1,784 @@
+use crate::xml;
+use eframe::egui;
+use egui::util::cache::{ComputerMut, FrameCache};
+use egui_extras::syntax_highlighting::{self, CodeTheme};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Text larger than this is shown without highlighting, one row at a time.
+const HIGHLIGHT_LIMIT: usize = 512 * 1024;
+
+/// Longer lines are split into several rows of large texts.
+const MAX_ROW_LENGTH: usize = 1000;
+
+const KEY_COLOR: egui::Color32 = egui::Color32::from_rgb(156, 220, 254);
+const STRING_COLOR: egui::Color32 = egui::Color32::from_rgb(206, 145, 120);
+const TABLE_COLOR: egui::Color32 = egui::Color32::from_rgb(78, 201, 176);
+const COMMENT_COLOR: egui::Color32 = egui::Color32::from_rgb(106, 153, 85);
+
+/// Languages text bodies are highlighted and pretty-printed as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    Json,
+    Xml,
+    Html,
+    Css,
+    JavaScript,
+    Yaml,
+    Toml,
+    Sql,
+}
+
+impl Language {
+    pub const ALL: [Language; 8] = [
+        Language::Json,
+        Language::Xml,
+        Language::Html,
+        Language::Css,
+        Language::JavaScript,
+        Language::Yaml,
+        Language::Toml,
+        Language::Sql,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Language::Json => "JSON",
+            Language::Xml => "XML",
+            Language::Html => "HTML",
+            Language::Css => "CSS",
+            Language::JavaScript => "JavaScript",
+            Language::Yaml => "YAML",
+            Language::Toml => "TOML",
+            Language::Sql => "SQL",
+        }
+    }
+
+    /// Extension syntect looks the syntax up by.
+    fn extension(self) -> &'static str {
+        match self {
+            Language::Json => "json",
+            Language::Xml => "xml",
+            Language::Html => "html",
+            Language::Css => "css",
+            Language::JavaScript => "js",
+            Language::Yaml => "yaml",
+            Language::Toml => "toml",
+            Language::Sql => "sql",
+        }
+    }
+
+    /// YAML and TOML are already laid out by their authors.
+    pub fn can_pretty_print(self) -> bool {
+        !matches!(self, Language::Yaml | Language::Toml)
+    }
+
+    /// Picks the language from the content type, falling back to sniffing
+    /// the start of the text.
+    pub fn detect(content_type: &str, text: &str) -> Option<Self> {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+        let subtype = mime.rsplit(['/', '+']).next().unwrap_or("");
+
+        match subtype {
+            "json" | "x-ndjson" => return Some(Language::Json),
+            "xml" => return Some(Language::Xml),
+            "html" | "xhtml" => return Some(Language::Html),
+            "css" => return Some(Language::Css),
+            "javascript" | "x-javascript" | "ecmascript" => return Some(Language::JavaScript),
+            "yaml" | "x-yaml" => return Some(Language::Yaml),
+            "toml" | "x-toml" => return Some(Language::Toml),
+            "sql" | "x-sql" => return Some(Language::Sql),
+            _ => {}
+        }
+
+        let start = text.trim_start_matches('\u{feff}').trim_start();
+        let lower: String = start
+            .chars()
+            .take(64)
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+            Some(Language::Html)
+        } else if xml::is_xml("", start.as_bytes()) {
+            Some(Language::Xml)
+        } else if start.starts_with('{') || start.starts_with('[') {
+            // Large bodies are not highlighted, so skip parsing them every frame
+            (start.len() > HIGHLIGHT_LIMIT
+                || serde_json::from_str::<serde_json::Value>(start).is_ok())
+            .then_some(Language::Json)
+        } else if start.starts_with("---") {
+            Some(Language::Yaml)
+        } else if [
+            "select ", "insert ", "update ", "delete ", "create ", "with ",
+        ]
+        .iter()
+        .any(|keyword| lower.starts_with(keyword))
+        {
+            Some(Language::Sql)
+        } else {
+            None
+        }
+    }
+}
+
+/// Reformats `text`. Errors leave the text as received.
+pub fn pretty_print(language: Language, text: &str) -> Result<String, String> {
+    match language {
+        Language::Json => serde_json::from_str::<serde_json::Value>(text)
+            .and_then(|value| serde_json::to_string_pretty(&value))
+            .map_err(|e| e.to_string()),
+        Language::Xml => xml::parse(text)
+            .map(|doc| xml::pretty_print(text, &doc))
+            .map_err(|e| e.to_string()),
+        Language::Html => Ok(format_html(text)),
+        Language::Css | Language::JavaScript => Ok(format_braces(text, language)),
+        Language::Sql => Ok(format_sql(text)),
+        Language::Yaml | Language::Toml => Ok(text.to_string()),
+    }
+}
+
+/// Identifies the text shown under `id` for `FrameCache`. Bodies only grow
+/// while they arrive, so the length tells their versions apart without
+/// hashing the text every frame.
+#[derive(Clone, Copy)]
+struct TextKey<'a> {
+    id: egui::Id,
+    text: &'a str,
+}
+
+impl Hash for TextKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        self.text.len().hash(state);
+    }
+}
+
+#[derive(Default)]
+struct PrettyPrinter;
+
+impl ComputerMut<(TextKey<'_>, Language), Result<Arc<str>, String>> for PrettyPrinter {
+    fn compute(&mut self, (key, language): (TextKey<'_>, Language)) -> Result<Arc<str>, String> {
+        pretty_print(language, key.text).map(Arc::from)
+    }
+}
+
+/// A visible row of a large text: a line, or part of a very long line.
+struct Row {
+    start: usize,
+    end: usize,
+    /// Line number on the first row of each line.
+    line: Option<usize>,
+}
+
+#[derive(Default)]
+struct RowSplitter;
+
+impl ComputerMut<TextKey<'_>, Arc<Vec<Row>>> for RowSplitter {
+    fn compute(&mut self, TextKey { text, .. }: TextKey<'_>) -> Arc<Vec<Row>> {
+        let mut rows = Vec::new();
+        let mut offset = 0;
+        for (number, raw_line) in text.split('\n').enumerate() {
+            let line = raw_line.strip_suffix('\r').unwrap_or(raw_line);
+            let mut start = 0;
+            loop {
+                let mut end = (start + MAX_ROW_LENGTH).min(line.len());
+                while !line.is_char_boundary(end) {
+                    end -= 1;
+                }
+                rows.push(Row {
+                    start: offset + start,
+                    end: offset + end,
+                    line: (start == 0).then_some(number + 1),
+                });
+                if end >= line.len() {
+                    break;
+                }
+                start = end;
+            }
+            offset += raw_line.len() + 1;
+        }
+        Arc::new(rows)
+    }
+}
+
+/// Renders text with syntax highlighting and optional line numbers. With
+/// `pretty`, the text is reformatted first; the result is cached while the
+/// text is shown, so `id` must change along with the text.
+pub fn render_code(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    text: &str,
+    language: Option<Language>,
+    pretty: bool,
+    line_numbers: bool,
+) {
+    let formatted = match language {
+        Some(language) if pretty && language.can_pretty_print() => {
+            Some(ui.ctx().memory_mut(|mem| {
+                mem.caches
+                    .cache::<FrameCache<Result<Arc<str>, String>, PrettyPrinter>>()
+                    .get((TextKey { id, text }, language))
+            }))
+        }
+        _ => None,
+    };
+    let text = match &formatted {
+        Some(Ok(formatted)) => formatted,
+        None => text,
+        Some(Err(e)) => {
+            ui.label(
+                egui::RichText::new(format!("⚠ Could not pretty-print: {}", e))
+                    .small()
+                    .color(egui::Color32::YELLOW),
+            );
+            text
+        }
+    };
+
+    if text.len() > HIGHLIGHT_LIMIT {
+        // Reformatted text is split into rows separately from the original
+        let id = match &formatted {
+            Some(Ok(_)) => id.with("pretty"),
+            _ => id,
+        };
+        render_large_text(ui, id, text, line_numbers);
+        return;
+    }
+
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let job = match language {
+        Some(Language::Toml) => highlight_toml(text, font_id.clone()),
+        Some(language) => {
+            let theme = CodeTheme::from_style(ui.style());
+            syntax_highlighting::highlight(ui.ctx(), ui.style(), &theme, text, language.extension())
+        }
+        None => plain_job(text, font_id.clone()),
+    };
+
+    egui::Frame::new()
+        .fill(egui::Color32::from_gray(30))
+        .inner_margin(10.0)
+        .show(ui, |ui| {
+            egui::ScrollArea::both()
+                .id_salt(id.with("code"))
+                .max_height(400.0)
+                .show(ui, |ui| {
+                    ui.horizontal_top(|ui| {
+                        if line_numbers {
+                            let lines = text.lines().count().max(1);
+                            let width = lines.to_string().len();
+                            let numbers: Vec<String> =
+                                (1..=lines).map(|n| format!("{:>width$}", n)).collect();
+                            ui.label(
+                                egui::RichText::new(numbers.join("\n"))
+                                    .font(font_id.clone())
+                                    .color(egui::Color32::from_gray(100)),
+                            );
+                            ui.separator();
+                        }
+                        ui.add(egui::Label::new(job).selectable(true).extend());
+                    });
+                });
+        });
+}
+
+/// Plain text laid out one row at a time, so only visible rows cost anything.
+fn render_large_text(ui: &mut egui::Ui, id: egui::Id, text: &str, line_numbers: bool) {
+    let rows = ui.ctx().memory_mut(|mem| {
+        mem.caches
+            .cache::<FrameCache<Arc<Vec<Row>>, RowSplitter>>()
+            .get(TextKey { id, text })
+    });
+    let width = rows
+        .last()
+        .and_then(|row| row.line)
+        .unwrap_or(1)
+        .to_string()
+        .len();
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+
+    ui.label(
+        egui::RichText::new(format!(
+            "Large body ({:.1} KB) - shown without highlighting",
+            text.len() as f32 / 1024.0
+        ))
+        .small()
+        .weak(),
+    );
+    egui::Frame::new()
+        .fill(egui::Color32::from_gray(30))
+        .inner_margin(10.0)
+        .show(ui, |ui| {
+            egui::ScrollArea::both()
+                .id_salt(id.with("code"))
+                .max_height(400.0)
+                .show_rows(ui, row_height, rows.len(), |ui, range| {
+                    for row in &rows[range] {
+                        ui.horizontal(|ui| {
+                            if line_numbers {
+                                let number = row.line.map(|n| n.to_string()).unwrap_or_default();
+                                ui.label(
+                                    egui::RichText::new(format!("{:>width$}", number))
+                                        .font(font_id.clone())
+                                        .color(egui::Color32::from_gray(100)),
+                                );
+                                ui.separator();
+                            }
+                            ui.add(
+                                egui::Label::new(
+                                    egui::RichText::new(&text[row.start..row.end])
+                                        .font(font_id.clone())
+                                        .color(egui::Color32::LIGHT_GRAY),
+                                )
+                                .extend(),
+                            );
+                        });
+                    }
+                });
+        });
+}
+
+fn plain_job(text: &str, font_id: egui::FontId) -> egui::text::LayoutJob {
+    egui::text::LayoutJob::simple(
+        text.to_string(),
+        font_id,
+        egui::Color32::LIGHT_GRAY,
+        f32::INFINITY,
+    )
+}
+
+/// Colors TOML tables, keys, strings and comments line by line.
+fn highlight_toml(text: &str, font_id: egui::FontId) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    let format = |color| egui::TextFormat::simple(font_id.clone(), color);
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('#') {
+            job.append(line, 0.0, format(COMMENT_COLOR));
+        } else if trimmed.starts_with('[') {
+            job.append(line, 0.0, format(TABLE_COLOR));
+        } else if let Some(eq) = line.find('=') {
+            job.append(&line[..eq], 0.0, format(KEY_COLOR));
+            let value = &line[eq..];
+            let color = if value[1..].trim_start().starts_with(['"', '\'']) {
+                STRING_COLOR
+            } else {
+                egui::Color32::LIGHT_GRAY
+            };
+            job.append(value, 0.0, format(color));
+        } else {
+            job.append(line, 0.0, format(egui::Color32::LIGHT_GRAY));
+        }
+    }
+    job
+}
+
+/// HTML elements that never have content.
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements whose content is kept exactly as received.
+const RAW_TEXT_ELEMENTS: [&str; 4] = ["script", "style", "pre", "textarea"];
+
+enum HtmlToken<'a> {
+    Open(&'a str),
+    Close(&'a str),
+    /// Comments, doctypes and self-closing tags.
+    Standalone(&'a str),
+    Text(&'a str),
+}
+
+fn tokenize_html(text: &str) -> Vec<HtmlToken<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            tokens.push(HtmlToken::Text(rest));
+            break;
+        };
+        if start > 0 {
+            tokens.push(HtmlToken::Text(&rest[..start]));
+            rest = &rest[start..];
+        }
+
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->").map(|e| e + 3)
+        } else {
+            // Find the closing '>' outside of quoted attribute values
+            let mut quote = None;
+            rest.char_indices()
+                .skip(1)
+                .find_map(|(i, c)| match (quote, c) {
+                    (None, '"' | '\'') => {
+                        quote = Some(c);
+                        None
+                    }
+                    (Some(q), c) if c == q => {
+                        quote = None;
+                        None
+                    }
+                    (None, '>') => Some(i + 1),
+                    _ => None,
+                })
+        }
+        .unwrap_or(rest.len());
+
+        let tag = &rest[..end];
+        rest = &rest[end..];
+        let name_of = |tag: &str| {
+            tag.trim_start_matches(['<', '/'])
+                .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                .next()
+                .unwrap_or("")
+                .to_ascii_lowercase()
+        };
+
+        if tag.starts_with("</") {
+            tokens.push(HtmlToken::Close(tag));
+        } else if tag.starts_with("<!") || tag.starts_with("<?") || tag.ends_with("/>") {
+            tokens.push(HtmlToken::Standalone(tag));
+        } else {
+            let name = name_of(tag);
+            if VOID_ELEMENTS.contains(&name.as_str()) {
+                tokens.push(HtmlToken::Standalone(tag));
+            } else if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+                // Everything up to the matching end tag is content
+                let close = format!("</{}", name);
+                let content_end = rest.to_ascii_lowercase().find(&close).unwrap_or(rest.len());
+                tokens.push(HtmlToken::Open(tag));
+                if content_end > 0 {
+                    tokens.push(HtmlToken::Standalone(&rest[..content_end]));
+                }
+                rest = &rest[content_end..];
+            } else {
+                tokens.push(HtmlToken::Open(tag));
+            }
+        }
+    }
+    tokens
+}
+
+/// Puts each tag on its own line, indented by nesting depth. Elements that
+/// only hold a short text stay on one line.
+fn format_html(text: &str) -> String {
+    let tokens = tokenize_html(text);
+    let mut out = String::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < tokens.len() {
+        let indent = "  ".repeat(depth);
+        match &tokens[i] {
+            HtmlToken::Open(tag) => {
+                // <p>short text</p> on one line
+                if let (Some(HtmlToken::Text(content)), Some(HtmlToken::Close(close))) =
+                    (tokens.get(i + 1), tokens.get(i + 2))
+                    && !content.trim().contains('\n')
+                    && content.trim().len() <= 80
+                {
+                    out.push_str(&format!("{}{}{}{}\n", indent, tag, content.trim(), close));
+                    i += 3;
+                    continue;
+                }
+                out.push_str(&format!("{}{}\n", indent, tag));
+                depth += 1;
+            }
+            HtmlToken::Close(tag) => {
+                depth = depth.saturating_sub(1);
+                out.push_str(&format!("{}{}\n", "  ".repeat(depth), tag));
+            }
+            HtmlToken::Standalone(content) => {
+                for line in content.trim_matches('\n').lines() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    out.push_str(&format!("{}{}\n", indent, line.trim_end()));
+                }
+            }
+            HtmlToken::Text(content) => {
+                let words: Vec<&str> = content.split_whitespace().collect();
+                if !words.is_empty() {
+                    out.push_str(&format!("{}{}\n", indent, words.join(" ")));
+                }
+            }
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Lays out CSS and JavaScript with one statement or declaration per line,
+/// indenting blocks. Strings, comments and parenthesized expressions such as
+/// `for (;;)` headers are kept intact.
+fn format_braces(text: &str, language: Language) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    let mut depth = 0usize;
+    let mut parens = 0usize;
+    let mut i = 0;
+
+    let newline = |out: &mut String, depth: usize| {
+        while out.ends_with(' ') {
+            out.pop();
+        }
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(&"  ".repeat(depth));
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '"' | '\'' | '`' => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && chars[i] != c {
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(chars.len());
+                out.extend(&chars[start..i]);
+                continue;
+            }
+            '/' if next == Some('*') => {
+                let start = i;
+                i += 2;
+                while i + 1 < chars.len() && !(chars[i] == '*' && chars[i + 1] == '/') {
+                    i += 1;
+                }
+                i = (i + 2).min(chars.len());
+                out.extend(&chars[start..i]);
+                continue;
+            }
+            '/' if next == Some('/') && language == Language::JavaScript => {
+                let start = i;
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                out.extend(&chars[start..i]);
+                newline(&mut out, depth);
+                continue;
+            }
+            c if c.is_whitespace() => {
+                if !out.is_empty() && !out.ends_with([' ', '\n', '(', '[']) {
+                    out.push(' ');
+                }
+            }
+            '(' | '[' => {
+                parens += 1;
+                out.push(c);
+            }
+            ')' | ']' => {
+                parens = parens.saturating_sub(1);
+                while out.ends_with(' ') {
+                    out.pop();
+                }
+                out.push(c);
+            }
+            '{' if parens == 0 => {
+                if !out.is_empty() && !out.ends_with([' ', '\n']) {
+                    out.push(' ');
+                }
+                out.push('{');
+                depth += 1;
+                newline(&mut out, depth);
+            }
+            '}' if parens == 0 => {
+                depth = depth.saturating_sub(1);
+                newline(&mut out, depth);
+                out.push('}');
+                // Keep `} else`, `});` and `},` together
+                let rest: String = chars[i + 1..]
+                    .iter()
+                    .skip_while(|c| c.is_whitespace())
+                    .take(8)
+                    .collect();
+                let continues = rest.starts_with([')', ',', ';', ']'])
+                    || ["else", "catch", "finally", "while"]
+                        .iter()
+                        .any(|keyword| rest.starts_with(keyword));
+                if !continues {
+                    newline(&mut out, depth);
+                } else if rest.starts_with(char::is_alphabetic) {
+                    out.push(' ');
+                }
+            }
+            ';' if parens == 0 => {
+                while out.ends_with(' ') {
+                    out.pop();
+                }
+                out.push(';');
+                newline(&mut out, depth);
+            }
+            c => out.push(c),
+        }
+        i += 1;
+    }
+    out.trim_end().to_string() + "\n"
+}
+
+/// Keywords that start a clause on a new line.
+const SQL_CLAUSES: [&str; 22] = [
+    "select",
+    "from",
+    "where",
+    "group",
+    "order",
+    "having",
+    "limit",
+    "offset",
+    "union",
+    "except",
+    "intersect",
+    "insert",
+    "values",
+    "update",
+    "set",
+    "delete",
+    "returning",
+    "with",
+    "join",
+    "left",
+    "right",
+    "inner",
+];
+
+/// Starts each clause on a new line, puts each selected column and each
+/// `AND`/`OR` condition on its own indented line.
+fn format_sql(text: &str) -> String {
+    let mut out = String::new();
+    let mut parens = 0usize;
+    let mut clause = String::new();
+    let mut prev_word = String::new();
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\'' | '"' | '`' => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && chars[i] != c {
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                if !out.is_empty() && !out.ends_with([' ', '\n', '(', '.']) {
+                    out.push(' ');
+                }
+                out.extend(&chars[start..i]);
+                prev_word.clear();
+                continue;
+            }
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                let start = i;
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                out.extend(&chars[start..i]);
+                out.push('\n');
+                continue;
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                let lower = word.to_ascii_lowercase();
+                let joins_previous = matches!(
+                    prev_word.as_str(),
+                    "left"
+                        | "right"
+                        | "inner"
+                        | "outer"
+                        | "full"
+                        | "cross"
+                        | "natural"
+                        | "union"
+                        | "insert"
+                        | "delete"
+                );
+                if parens == 0 && !joins_previous && SQL_CLAUSES.contains(&lower.as_str())
+                    || parens == 0 && matches!(lower.as_str(), "full" | "cross")
+                {
+                    trim_end_spaces(&mut out);
+                    if !out.is_empty() && !out.ends_with('\n') {
+                        out.push('\n');
+                    }
+                    clause = lower.clone();
+                } else if parens == 0 && matches!(lower.as_str(), "and" | "or") && clause == "where"
+                {
+                    trim_end_spaces(&mut out);
+                    out.push_str("\n  ");
+                } else if !out.is_empty() && !out.ends_with([' ', '\n', '(', '.']) {
+                    out.push(' ');
+                }
+                out.push_str(&word);
+                prev_word = lower;
+                continue;
+            }
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ',' => {
+                out.push(',');
+                if parens == 0 && clause == "select" {
+                    out.push_str("\n ");
+                }
+            }
+            '(' => {
+                // `count(*)` stays together, `IN (...)` and `VALUES (...)` do not
+                let after_keyword = SQL_CLAUSES.contains(&prev_word.as_str())
+                    || matches!(
+                        prev_word.as_str(),
+                        "in" | "as" | "on" | "exists" | "and" | "or" | "not"
+                    );
+                if !out.is_empty()
+                    && !out.ends_with([' ', '\n', '('])
+                    && (after_keyword || !out.ends_with(|c: char| c.is_alphanumeric() || c == '_'))
+                {
+                    out.push(' ');
+                }
+                out.push('(');
+                parens += 1;
+            }
+            ')' => {
+                parens = parens.saturating_sub(1);
+                out.push(')');
+            }
+            ';' => {
+                out.push_str(";\n\n");
+                clause.clear();
+            }
+            '.' => out.push('.'),
+            c => {
+                // Operators like `>=` and `<>` stay together
+                let joins_operator =
+                    out.ends_with(['<', '>', '=', '!', '|', ':']) && "<>=|:".contains(c);
+                if !out.is_empty() && !out.ends_with([' ', '\n', '(', '.']) && !joins_operator {
+                    out.push(' ');
+                }
+                out.push(c);
+            }
+        }
+        prev_word.clear();
+        i += 1;
+    }
+    out.trim_end().to_string() + "\n"
+}
+
+fn trim_end_spaces(out: &mut String) {
+    while out.ends_with(' ') {
+        out.pop();
+    }
+}
@@ -0,0 +1,342 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Byte counters for a single connection, updated as data crosses the socket.
+#[derive(Debug, Default)]
+pub struct ConnectionCounters {
+    pub bytes_in: AtomicU64,
+    pub bytes_out: AtomicU64,
+}
+
+/// Maximum number of raw bytes kept per request for the wire view.
+pub const RAW_CAPTURE_LIMIT: usize = 64 * 1024;
+
+/// Sent first by HTTP/2 clients with prior knowledge (h2c), e.g. gRPC.
+const HTTP2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Bytes read from the socket that have not been attributed to a request yet.
+#[derive(Debug, Default)]
+pub struct RawCapture {
+    buf: Vec<u8>,
+    /// Bytes read while the buffer was full.
+    dropped: usize,
+    /// Set for HTTP/2 connections, whose frames interleave requests.
+    disabled: bool,
+    /// When the first byte of the request at the front of the buffer arrived.
+    first_byte: Option<Instant>,
+    last_read: Option<Instant>,
+}
+
+impl RawCapture {
+    fn append(&mut self, data: &[u8]) {
+        if self.disabled {
+            return;
+        }
+        let now = Instant::now();
+        if self.first_byte.is_none() {
+            self.first_byte = Some(now);
+        }
+        self.last_read = Some(now);
+        let room = RAW_CAPTURE_LIMIT.saturating_sub(self.buf.len());
+        let keep = data.len().min(room);
+        self.buf.extend_from_slice(&data[..keep]);
+        self.dropped += data.len() - keep;
+    }
+
+    /// Stops capturing and discards what was buffered.
+    pub fn disable(&mut self) {
+        self.disabled = true;
+        self.buf = Vec::new();
+        self.dropped = 0;
+        self.first_byte = None;
+    }
+
+    pub fn first_byte(&self) -> Option<Instant> {
+        self.first_byte
+    }
+
+    /// Removes everything buffered, e.g. the bytes of a rejected request.
+    pub fn take_all(&mut self) -> (Vec<u8>, usize) {
+        let total = self.buf.len() + self.dropped;
+        self.dropped = 0;
+        self.first_byte = None;
+        (std::mem::take(&mut self.buf), total)
+    }
+
+    /// Removes the bytes of the request at the front of the buffer, leaving any
+    /// pipelined data behind. Returns the captured bytes and the total wire size.
+    pub fn take_request(
+        &mut self,
+        chunked: bool,
+        content_length: Option<usize>,
+    ) -> (Vec<u8>, usize) {
+        let wire_len = if self.dropped == 0 {
+            request_wire_len(&self.buf, chunked, content_length)
+        } else {
+            None
+        };
+
+        match wire_len {
+            Some(len) if len <= self.buf.len() => {
+                let rest = self.buf.split_off(len);
+                // Pipelined bytes arrived with the last read at the latest
+                self.first_byte = if rest.is_empty() {
+                    None
+                } else {
+                    self.last_read
+                };
+                (std::mem::replace(&mut self.buf, rest), len)
+            }
+            // Framing unknown or truncated, attribute everything to this request
+            _ => self.take_all(),
+        }
+    }
+}
+
+/// Length of the first HTTP/1 message in `buf`, if it is complete.
+fn request_wire_len(buf: &[u8], chunked: bool, content_length: Option<usize>) -> Option<usize> {
+    let head_len = find_head_end(buf)?;
+    if chunked {
+        chunked_body_len(&buf[head_len..]).map(|len| head_len + len)
+    } else {
+        Some(head_len + content_length.unwrap_or(0))
+    }
+}
+
+/// Position just past the blank line ending the request head. Bare `\n` line
+/// endings are accepted like hyper does.
+fn find_head_end(buf: &[u8]) -> Option<usize> {
+    let mut i = 0;
+    while let Some(pos) = buf[i..].iter().position(|&b| b == b'\n') {
+        let next = i + pos + 1;
+        match buf.get(next) {
+            Some(b'\n') => return Some(next + 1),
+            Some(b'\r') if buf.get(next + 1) == Some(&b'\n') => return Some(next + 2),
+            _ => i = next,
+        }
+    }
+    None
+}
+
+/// Length of a complete chunked body including the final chunk and trailers.
+fn chunked_body_len(buf: &[u8]) -> Option<usize> {
+    let mut pos = 0;
+    loop {
+        let line_end = pos + buf[pos..].iter().position(|&b| b == b'\n')? + 1;
+        let line = std::str::from_utf8(&buf[pos..line_end]).ok()?;
+        let size_str = line.split(';').next()?.trim();
+        let size = usize::from_str_radix(size_str, 16).ok()?;
+        pos = line_end;
+
+        if size == 0 {
+            // Trailer section ends with an empty line
+            loop {
+                let line_end = pos + buf[pos..].iter().position(|&b| b == b'\n')? + 1;
+                let empty = buf[pos..line_end].iter().all(|&b| b == b'\r' || b == b'\n');
+                pos = line_end;
+                if empty {
+                    return Some(pos);
+                }
+            }
+        }
+
+        pos += size;
+        // Skip the CRLF after the chunk data
+        let line_end = pos + buf.get(pos..)?.iter().position(|&b| b == b'\n')? + 1;
+        pos = line_end;
+    }
+}
+
+/// Wraps an accepted stream, counting the bytes read from and written to it and
+/// keeping the raw bytes read for the wire view.
+pub struct ConnectionIo<S> {
+    inner: S,
+    counters: Arc<ConnectionCounters>,
+    raw: Arc<Mutex<RawCapture>>,
+    /// Bytes read while detecting the protocol, handed out again before
+    /// reading from `inner`.
+    replay: Vec<u8>,
+}
+
+impl<S> ConnectionIo<S> {
+    pub fn new(inner: S, counters: Arc<ConnectionCounters>, raw: Arc<Mutex<RawCapture>>) -> Self {
+        Self {
+            inner,
+            counters,
+            raw,
+            replay: Vec::new(),
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> ConnectionIo<S> {
+    /// Reads until the first bytes tell the HTTP/2 connection preface apart
+    /// from an HTTP/1 request line. The bytes read are replayed afterwards.
+    pub async fn detect_http2(&mut self) -> io::Result<bool> {
+        while self.replay.len() < HTTP2_PREFACE.len() && HTTP2_PREFACE.starts_with(&self.replay) {
+            let mut data = [0u8; 24];
+            let wanted = HTTP2_PREFACE.len() - self.replay.len();
+            let mut buf = ReadBuf::new(&mut data[..wanted]);
+            std::future::poll_fn(|cx| self.poll_read_counted(cx, &mut buf)).await?;
+            if buf.filled().is_empty() {
+                return Ok(false);
+            }
+            self.replay.extend_from_slice(buf.filled());
+        }
+        Ok(self.replay == HTTP2_PREFACE)
+    }
+
+    fn poll_read_counted(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let read = &buf.filled()[before..];
+        if !read.is_empty() {
+            self.counters
+                .bytes_in
+                .fetch_add(read.len() as u64, Ordering::Relaxed);
+            self.raw.lock().unwrap().append(read);
+        }
+        result
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for ConnectionIo<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if !self.replay.is_empty() {
+            let len = self.replay.len().min(buf.remaining());
+            buf.put_slice(&self.replay[..len]);
+            self.replay.drain(..len);
+            return Poll::Ready(Ok(()));
+        }
+        self.poll_read_counted(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for ConnectionIo<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = result {
+            self.counters
+                .bytes_out
+                .fetch_add(written as u64, Ordering::Relaxed);
+        }
+        result
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_write_vectored(cx, bufs);
+        if let Poll::Ready(Ok(written)) = result {
+            self.counters
+                .bytes_out
+                .fetch_add(written as u64, Ordering::Relaxed);
+        }
+        result
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHUNKED: &[u8] = b"POST /upload HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+        5;name=value\r\nhello\r\n6\r\n world\r\n0\r\nX-Checksum: abc\r\n\r\n";
+
+    #[test]
+    fn chunked_bodies_end_after_the_trailers() {
+        let head_len = find_head_end(CHUNKED).unwrap();
+        assert_eq!(
+            chunked_body_len(&CHUNKED[head_len..]),
+            Some(CHUNKED.len() - head_len)
+        );
+        assert_eq!(request_wire_len(CHUNKED, true, None), Some(CHUNKED.len()));
+    }
+
+    #[test]
+    fn incomplete_messages_have_no_length() {
+        for split in 0..CHUNKED.len() {
+            assert_eq!(
+                request_wire_len(&CHUNKED[..split], true, None),
+                None,
+                "{}",
+                split
+            );
+        }
+        assert_eq!(
+            request_wire_len(b"GET / HTTP/1.1\r\nHost: a\r\n", false, None),
+            None
+        );
+    }
+
+    #[test]
+    fn bare_line_feeds_end_the_head() {
+        let request = b"GET / HTTP/1.1\nHost: a\n\nrest";
+        assert_eq!(find_head_end(request), Some(request.len() - 4));
+        assert_eq!(
+            request_wire_len(request, false, Some(4)),
+            Some(request.len())
+        );
+    }
+
+    #[test]
+    fn pipelined_requests_split_across_reads() {
+        let second = b"GET /next HTTP/1.1\r\n\r\n";
+        let mut wire = CHUNKED.to_vec();
+        wire.extend_from_slice(second);
+
+        let mut raw = RawCapture::default();
+        for piece in wire.chunks(7) {
+            raw.append(piece);
+        }
+        let (first, len) = raw.take_request(true, None);
+        assert_eq!(first, CHUNKED);
+        assert_eq!(len, CHUNKED.len());
+        assert!(raw.first_byte().is_some());
+
+        let (next, len) = raw.take_request(false, None);
+        assert_eq!(next, second);
+        assert_eq!(len, second.len());
+        assert!(raw.first_byte().is_none());
+    }
+
+    #[test]
+    fn truncated_captures_are_taken_whole() {
+        let mut raw = RawCapture::default();
+        raw.append(&vec![b'a'; RAW_CAPTURE_LIMIT + 10]);
+        let (captured, len) = raw.take_request(false, Some(0));
+        assert_eq!(captured.len(), RAW_CAPTURE_LIMIT);
+        assert_eq!(len, RAW_CAPTURE_LIMIT + 10);
+    }
+}
@@ -0,0 +1,221 @@
+use crate::connection_io::ConnectionCounters;
+use crate::timestamps::TimestampDisplay;
+use chrono::{DateTime, Utc};
+use eframe::egui;
+use egui_extras::{Column, TableBuilder};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CloseReason {
+    ClientClosed,
+    Error(String),
+    ServerShutdown,
+}
+
+impl CloseReason {
+    pub fn label(&self) -> String {
+        match self {
+            CloseReason::ClientClosed => "Client closed".to_string(),
+            CloseReason::Error(e) => format!("Error: {}", e),
+            CloseReason::ServerShutdown => "Server shutdown".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ConnectionInfo {
+    pub id: u64,
+    pub remote_addr: String,
+    pub transport: &'static str,
+    /// HTTP version of the first request, empty until one arrives.
+    pub protocol: String,
+    pub opened_at: DateTime<Utc>,
+    pub opened: Instant,
+    pub closed: Option<Instant>,
+    pub close_reason: Option<CloseReason>,
+    pub request_count: usize,
+    pub counters: Arc<ConnectionCounters>,
+}
+
+impl ConnectionInfo {
+    pub fn is_open(&self) -> bool {
+        self.closed.is_none()
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.closed.unwrap_or_else(Instant::now) - self.opened
+    }
+}
+
+/// Drops the oldest closed connections while there are more than `max`,
+/// the same count requests are limited to. Open connections are kept.
+pub fn enforce_limit(connections: &mut Vec<ConnectionInfo>, max: usize) {
+    let mut excess = connections.len().saturating_sub(max.max(1));
+    connections.retain(|conn| {
+        let evict = excess > 0 && !conn.is_open();
+        if evict {
+            excess -= 1;
+        }
+        !evict
+    });
+}
+
+pub fn render_connections(
+    ui: &mut egui::Ui,
+    connections: &mut Vec<ConnectionInfo>,
+    timestamps: &TimestampDisplay,
+) {
+    let open_count = connections.iter().filter(|c| c.is_open()).count();
+
+    ui.horizontal(|ui| {
+        ui.heading("Connections");
+        ui.label(format!(
+            "{} open, {} closed",
+            open_count,
+            connections.len() - open_count
+        ));
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if open_count < connections.len() && ui.button("🗑 Clear Closed").clicked() {
+                connections.retain(|c| c.is_open());
+            }
+        });
+    });
+    ui.separator();
+
+    if connections.is_empty() {
+        ui.centered_and_justified(|ui| {
+            ui.label(egui::RichText::new("No connections yet").weak());
+        });
+        return;
+    }
+
+    // Durations and byte counts of open connections keep changing
+    if open_count > 0 {
+        ui.ctx().request_repaint_after(Duration::from_millis(500));
+    }
+    timestamps.request_repaint(ui.ctx());
+    // Dates are shown only when they tell connections apart
+    let show_date = match (connections.first(), connections.last()) {
+        (Some(first), Some(last)) => {
+            timestamps.date(first.opened_at) != timestamps.date(last.opened_at)
+        }
+        _ => false,
+    };
+
+    let titles = [
+        "#", "Opened", "Remote", "Protocol", "Requests", "In", "Out", "Duration", "State",
+    ];
+    let row_height = ui.text_style_height(&egui::TextStyle::Body);
+    let mut table = TableBuilder::new(ui)
+        .striped(true)
+        .resizable(true)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center));
+    for _ in titles {
+        table = table.column(Column::auto().at_least(40.0));
+    }
+
+    // Only the visible rows are laid out
+    table
+        .header(20.0, |mut header| {
+            for title in titles {
+                header.col(|ui| {
+                    ui.label(egui::RichText::new(title).strong());
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(row_height, connections.len(), |mut row| {
+                let conn = &connections[connections.len() - 1 - row.index()];
+                row.col(|ui| {
+                    ui.label(egui::RichText::new(format!("#{}", conn.id)).monospace());
+                });
+                row.col(|ui| {
+                    ui.label(
+                        egui::RichText::new(timestamps.format(conn.opened_at, show_date))
+                            .monospace(),
+                    );
+                });
+                row.col(|ui| {
+                    ui.label(egui::RichText::new(&conn.remote_addr).monospace());
+                });
+                row.col(|ui| {
+                    let protocol = if conn.protocol.is_empty() {
+                        conn.transport.to_string()
+                    } else {
+                        format!("{} / {}", conn.protocol, conn.transport)
+                    };
+                    ui.label(protocol);
+                });
+                row.col(|ui| {
+                    ui.label(conn.request_count.to_string());
+                });
+                row.col(|ui| {
+                    ui.label(format!(
+                        "{} bytes",
+                        conn.counters.bytes_in.load(Ordering::Relaxed)
+                    ));
+                });
+                row.col(|ui| {
+                    ui.label(format!(
+                        "{} bytes",
+                        conn.counters.bytes_out.load(Ordering::Relaxed)
+                    ));
+                });
+                row.col(|ui| {
+                    ui.label(format!("{:.1}s", conn.duration().as_secs_f32()));
+                });
+                row.col(|ui| match &conn.close_reason {
+                    None => {
+                        ui.label(egui::RichText::new("Open").color(egui::Color32::GREEN));
+                    }
+                    Some(reason) => {
+                        let color = match reason {
+                            CloseReason::Error(_) => egui::Color32::RED,
+                            _ => egui::Color32::GRAY,
+                        };
+                        ui.label(egui::RichText::new(reason.label()).color(color));
+                    }
+                });
+            });
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection(id: u64, open: bool) -> ConnectionInfo {
+        ConnectionInfo {
+            id,
+            remote_addr: String::new(),
+            transport: "TCP",
+            protocol: String::new(),
+            opened_at: Utc::now(),
+            opened: Instant::now(),
+            closed: (!open).then(Instant::now),
+            close_reason: (!open).then_some(CloseReason::ClientClosed),
+            request_count: 0,
+            counters: Arc::default(),
+        }
+    }
+
+    #[test]
+    fn oldest_closed_connections_are_dropped_first() {
+        let mut connections = vec![
+            connection(1, true),
+            connection(2, false),
+            connection(3, false),
+            connection(4, false),
+        ];
+        enforce_limit(&mut connections, 2);
+        let ids: Vec<u64> = connections.iter().map(|c| c.id).collect();
+        assert_eq!(ids, [1, 4]);
+
+        // Open connections stay even beyond the limit
+        let mut connections = vec![connection(1, true), connection(2, true)];
+        enforce_limit(&mut connections, 1);
+        assert_eq!(connections.len(), 2);
+    }
+}
@@ -0,0 +1,63 @@
+use std::io::Read;
+
+/// Upper bound for decompressed bodies, guarding against decompression bombs.
+const MAX_DECODED_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Returns the content codings applied to a body, in the order they were
+/// applied, ignoring `identity`.
+pub fn encodings(headers: &[(String, String)]) -> Vec<String> {
+    headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("content-encoding"))
+        .flat_map(|(_, value)| value.split(','))
+        .map(|coding| coding.trim().to_ascii_lowercase())
+        .filter(|coding| !coding.is_empty() && coding != "identity")
+        .collect()
+}
+
+/// Undoes the content codings in `headers`. Returns `None` when the body is
+/// not encoded.
+pub fn decode(headers: &[(String, String)], body: &[u8]) -> Option<Result<Vec<u8>, String>> {
+    let encodings = encodings(headers);
+    if encodings.is_empty() || body.is_empty() {
+        return None;
+    }
+
+    let mut data = body.to_vec();
+    // Codings are listed in the order applied, so undo them in reverse
+    for coding in encodings.iter().rev() {
+        data = match decode_one(coding, &data) {
+            Ok(decoded) => decoded,
+            Err(e) => return Some(Err(format!("{}: {}", coding, e))),
+        };
+    }
+    Some(Ok(data))
+}
+
+/// Undoes a single content coding such as `gzip`.
+pub fn decode_one(coding: &str, data: &[u8]) -> Result<Vec<u8>, String> {
+    match coding {
+        "gzip" | "x-gzip" => read_limited(flate2::read::MultiGzDecoder::new(data)),
+        // "deflate" is meant to be zlib-wrapped, but some clients send raw deflate
+        "deflate" => read_limited(flate2::read::ZlibDecoder::new(data))
+            .or_else(|_| read_limited(flate2::read::DeflateDecoder::new(data))),
+        "br" => read_limited(brotli::Decompressor::new(data, 4096)),
+        "zstd" => read_limited(zstd::stream::read::Decoder::new(data).map_err(|e| e.to_string())?),
+        other => Err(format!("unsupported content encoding '{}'", other)),
+    }
+}
+
+fn read_limited(reader: impl Read) -> Result<Vec<u8>, String> {
+    let mut decoded = Vec::new();
+    reader
+        .take(MAX_DECODED_SIZE + 1)
+        .read_to_end(&mut decoded)
+        .map_err(|e| e.to_string())?;
+    if decoded.len() as u64 > MAX_DECODED_SIZE {
+        return Err(format!(
+            "decoded body exceeds {} MB",
+            MAX_DECODED_SIZE / (1024 * 1024)
+        ));
+    }
+    Ok(decoded)
+}
@@ -0,0 +1,292 @@
+use crate::code_view;
+use crate::media::{self, MediaKind};
+use crate::xml;
+use eframe::egui;
+use egui::util::cache::{ComputerMut, FrameCache};
+use egui_json_tree::JsonTree;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Number of bytes shown in hex dumps before cutting off.
+const HEX_DUMP_LIMIT: usize = 64 * 1024;
+
+/// How a piece of content is rendered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContentView {
+    Text,
+    Json,
+    Xml,
+    Media,
+    Hex,
+}
+
+impl ContentView {
+    pub const ALL: [ContentView; 5] = [
+        ContentView::Text,
+        ContentView::Json,
+        ContentView::Xml,
+        ContentView::Media,
+        ContentView::Hex,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ContentView::Text => "Text",
+            ContentView::Json => "JSON",
+            ContentView::Xml => "XML",
+            ContentView::Media => "Media",
+            ContentView::Hex => "Hex",
+        }
+    }
+
+    /// Picks a view from the content type, falling back to sniffing the data.
+    pub fn detect(content_type: &str, data: &[u8]) -> Self {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+
+        if mime.contains("json") {
+            ContentView::Json
+        } else if media::detect(&mime, data).is_some() {
+            ContentView::Media
+        } else if mime.ends_with("+xml") || mime.ends_with("/xml") {
+            ContentView::Xml
+        } else if mime.starts_with("text/") {
+            ContentView::Text
+        } else if std::str::from_utf8(data).is_ok() {
+            let trimmed = String::from_utf8_lossy(data);
+            let trimmed = trimmed.trim_start();
+            if trimmed.starts_with('{') || trimmed.starts_with('[') {
+                ContentView::Json
+            } else if xml::is_xml("", data) {
+                ContentView::Xml
+            } else {
+                ContentView::Text
+            }
+        } else {
+            ContentView::Hex
+        }
+    }
+}
+
+/// Renders `data` with the given view, falling back to text when it does not
+/// parse as JSON or XML.
+pub fn render_content(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    view: ContentView,
+    content_type: &str,
+    data: &[u8],
+) {
+    match view {
+        ContentView::Json => match serde_json::from_slice::<serde_json::Value>(data) {
+            Ok(json_value) => {
+                egui::Frame::new()
+                    .fill(egui::Color32::from_gray(30))
+                    .inner_margin(10.0)
+                    .show(ui, |ui| {
+                        JsonTree::new(id.with("json"), &json_value).show(ui);
+                    });
+            }
+            Err(_) => render_text(ui, id, &String::from_utf8_lossy(data)),
+        },
+        ContentView::Xml => match cached::<XmlParser, _, _>(ui, ContentKey { id, data }).as_ref() {
+            Some(tree) => {
+                egui::Frame::new()
+                    .fill(egui::Color32::from_gray(30))
+                    .inner_margin(10.0)
+                    .show(ui, |ui| {
+                        xml::render_tree(ui, id.with("xml"), tree, false);
+                    });
+            }
+            None => render_text(ui, id, &String::from_utf8_lossy(data)),
+        },
+        ContentView::Text => render_text(ui, id, &String::from_utf8_lossy(data)),
+        ContentView::Media => {
+            let kind = media::detect(content_type, data).unwrap_or(MediaKind::Image);
+            let metadata = cached::<MediaReader, _, _>(ui, (ContentKey { id, data }, kind));
+            media::render_media(ui, id, kind, data, &metadata);
+        }
+        ContentView::Hex => render_hex(ui, data),
+    }
+}
+
+/// Identifies the content shown under `id` for `FrameCache`. Hashing the
+/// whole content every frame would cost about as much as parsing it.
+#[derive(Clone, Copy)]
+struct ContentKey<'a> {
+    id: egui::Id,
+    data: &'a [u8],
+}
+
+impl Hash for ContentKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        self.data.len().hash(state);
+    }
+}
+
+#[derive(Default)]
+struct XmlParser;
+
+impl ComputerMut<ContentKey<'_>, Arc<Option<xml::Tree>>> for XmlParser {
+    fn compute(&mut self, key: ContentKey<'_>) -> Arc<Option<xml::Tree>> {
+        let text = String::from_utf8_lossy(key.data);
+        Arc::new(xml::parse(&text).ok().map(|doc| xml::Tree::new(&doc)))
+    }
+}
+
+#[derive(Default)]
+struct MediaReader;
+
+impl ComputerMut<(ContentKey<'_>, MediaKind), Arc<Result<Vec<(String, String)>, String>>>
+    for MediaReader
+{
+    fn compute(
+        &mut self,
+        (key, kind): (ContentKey<'_>, MediaKind),
+    ) -> Arc<Result<Vec<(String, String)>, String>> {
+        Arc::new(media::metadata(kind, key.data))
+    }
+}
+
+fn cached<C, K, V>(ui: &egui::Ui, key: K) -> V
+where
+    C: ComputerMut<K, V> + Default + Send + Sync + 'static,
+    K: Copy + Hash,
+    V: Clone + Send + Sync + 'static,
+{
+    ui.ctx()
+        .memory_mut(|mem| mem.caches.cache::<FrameCache<V, C>>().get(key))
+}
+
+/// Plain text in a scrollable frame. Large texts lay out only their visible
+/// rows.
+pub fn render_text(ui: &mut egui::Ui, id: egui::Id, text: &str) {
+    code_view::render_code(ui, id.with("text"), text, None, false, false);
+}
+
+pub fn render_hex(ui: &mut egui::Ui, data: &[u8]) {
+    let shown = &data[..data.len().min(HEX_DUMP_LIMIT)];
+    if shown.len() < data.len() {
+        ui.label(
+            egui::RichText::new(format!(
+                "⚠ Showing the first {} of {} bytes",
+                shown.len(),
+                data.len()
+            ))
+            .small()
+            .color(egui::Color32::YELLOW),
+        );
+    }
+
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+    let rows = shown.len().div_ceil(16);
+    egui::Frame::new()
+        .fill(egui::Color32::from_gray(30))
+        .inner_margin(10.0)
+        .show(ui, |ui| {
+            egui::ScrollArea::vertical()
+                .id_salt(ui.next_auto_id())
+                .max_height(400.0)
+                .show_rows(ui, row_height, rows, |ui, range| {
+                    for row in range {
+                        let line = &shown[row * 16..(row * 16 + 16).min(shown.len())];
+                        ui.label(egui::RichText::new(hex_line(row * 16, line)).monospace());
+                    }
+                });
+        });
+}
+
+fn hex_line(offset: usize, line: &[u8]) -> String {
+    let mut hex = String::with_capacity(48);
+    for (i, byte) in line.iter().enumerate() {
+        if i == 8 {
+            hex.push(' ');
+        }
+        hex.push_str(&format!("{:02x} ", byte));
+    }
+    let ascii: String = line
+        .iter()
+        .map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        })
+        .collect();
+    format!("{:08x}  {:<49} |{}|", offset, hex, ascii)
+}
+
+/// File extension for a content type, `None` if the type is unknown.
+pub fn file_extension(content_type: &str) -> Option<&'static str> {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    Some(match mime.as_str() {
+        "application/json" => "json",
+        "application/xml" | "text/xml" | "application/soap+xml" => "xml",
+        "text/html" => "html",
+        "text/css" => "css",
+        "text/javascript" | "application/javascript" => "js",
+        "application/x-www-form-urlencoded" => "txt",
+        "text/csv" => "csv",
+        "application/pdf" => "pdf",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/svg+xml" => "svg",
+        "image/webp" => "webp",
+        "image/bmp" => "bmp",
+        "audio/mpeg" => "mp3",
+        "audio/wav" | "audio/x-wav" | "audio/wave" => "wav",
+        "audio/flac" => "flac",
+        "audio/ogg" => "ogg",
+        "audio/mp4" => "m4a",
+        "video/mp4" => "mp4",
+        "video/quicktime" => "mov",
+        "video/webm" => "webm",
+        "video/x-matroska" => "mkv",
+        "application/zip" => "zip",
+        "application/octet-stream" => "bin",
+        "application/x-protobuf" | "application/protobuf" => "pb",
+        "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => "msgpack",
+        "application/cbor" => "cbor",
+        "application/bson" => "bson",
+        mime if mime.starts_with("multipart/") => "multipart",
+        _ => return None,
+    })
+}
+
+/// Asks for a destination on a background thread and writes `data` there.
+/// Like `save_to_file`, for data already in a file.
+pub fn save_file_copy(filename: String, source: std::path::PathBuf) {
+    std::thread::spawn(move || {
+        if let Some(path) = rfd::FileDialog::new().set_file_name(&filename).save_file() {
+            if let Err(e) = std::fs::copy(&source, &path) {
+                eprintln!("Failed to save file: {}", e);
+            } else {
+                println!("Saved to: {:?}", path);
+            }
+        }
+    });
+}
+
+pub fn save_to_file(filename: String, data: Vec<u8>) {
+    std::thread::spawn(move || {
+        if let Some(path) = rfd::FileDialog::new().set_file_name(&filename).save_file() {
+            if let Err(e) = std::fs::write(&path, data) {
+                eprintln!("Failed to save file: {}", e);
+            } else {
+                println!("Saved to: {:?}", path);
+            }
+        }
+    });
+}
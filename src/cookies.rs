@@ -0,0 +1,348 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use eframe::egui;
+
+/// Value of the `SameSite` attribute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SameSite {
+    Unset,
+    Strict,
+    Lax,
+    None,
+}
+
+impl SameSite {
+    pub const ALL: [SameSite; 4] = [
+        SameSite::Unset,
+        SameSite::Strict,
+        SameSite::Lax,
+        SameSite::None,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SameSite::Unset => "(not set)",
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+/// A cookie sent with `Set-Cookie` on every response. Attributes left empty
+/// are omitted.
+#[derive(Clone, Debug)]
+pub struct ResponseCookie {
+    pub enabled: bool,
+    pub name: String,
+    pub value: String,
+    pub path: String,
+    pub domain: String,
+    /// A date such as `2030-01-01`, `2030-01-01T12:00:00Z` or an HTTP date.
+    pub expires: String,
+    /// Seconds; `0` or a negative value deletes the cookie.
+    pub max_age: String,
+    pub same_site: SameSite,
+    pub secure: bool,
+    pub http_only: bool,
+}
+
+impl Default for ResponseCookie {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            name: "session".to_string(),
+            value: String::new(),
+            path: "/".to_string(),
+            domain: String::new(),
+            expires: String::new(),
+            max_age: String::new(),
+            same_site: SameSite::Unset,
+            secure: false,
+            http_only: false,
+        }
+    }
+}
+
+impl ResponseCookie {
+    /// The `Set-Cookie` header value, or why the cookie cannot be sent.
+    pub fn to_header(&self) -> Result<String, String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("name is empty".to_string());
+        }
+        if let Some(c) = name.chars().find(|&c| !is_token_char(c)) {
+            return Err(format!("name contains {:?}", c));
+        }
+        if let Some(c) = self.value.chars().find(|&c| !is_cookie_octet(c)) {
+            return Err(format!("value contains {:?}", c));
+        }
+
+        let mut header = format!("{}={}", name, self.value);
+        if !self.path.trim().is_empty() {
+            header.push_str(&format!("; Path={}", self.path.trim()));
+        }
+        if !self.domain.trim().is_empty() {
+            header.push_str(&format!("; Domain={}", self.domain.trim()));
+        }
+        if !self.expires.trim().is_empty() {
+            let expires = parse_date(self.expires.trim())
+                .ok_or_else(|| format!("Expires \"{}\" is not a date", self.expires.trim()))?;
+            header.push_str(&format!(
+                "; Expires={}",
+                expires.format("%a, %d %b %Y %H:%M:%S GMT")
+            ));
+        }
+        if !self.max_age.trim().is_empty() {
+            let max_age: i64 = self
+                .max_age
+                .trim()
+                .parse()
+                .map_err(|_| format!("Max-Age \"{}\" is not a number", self.max_age.trim()))?;
+            header.push_str(&format!("; Max-Age={}", max_age));
+        }
+        if self.same_site != SameSite::Unset {
+            header.push_str(&format!("; SameSite={}", self.same_site.label()));
+        }
+        if self.secure {
+            header.push_str("; Secure");
+        }
+        if self.http_only {
+            header.push_str("; HttpOnly");
+        }
+        Ok(header)
+    }
+}
+
+/// Accepts RFC 3339, HTTP dates (RFC 2822 with `GMT`) and plain dates.
+fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(text) {
+        return Some(date.with_timezone(&Utc));
+    }
+    if let Ok(date) = DateTime::parse_from_rfc2822(text) {
+        return Some(date.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc())
+}
+
+/// RFC 6265 token characters allowed in cookie names.
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_graphic() && !"()<>@,;:\\\"/[]?={}".contains(c)
+}
+
+/// RFC 6265 cookie-octets allowed in unquoted cookie values.
+fn is_cookie_octet(c: char) -> bool {
+    c.is_ascii_graphic() && !"\",;\\".contains(c)
+}
+
+/// Splits all `Cookie` headers into name/value pairs. HTTP/2 clients may
+/// send one header per cookie.
+pub fn request_cookies(headers: &[(String, String)]) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("cookie"))
+        .flat_map(|(_, value)| value.split(';'))
+        .filter(|pair| !pair.trim().is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) => (name.trim().to_string(), value.trim().to_string()),
+            None => (String::new(), pair.trim().to_string()),
+        })
+        .collect()
+}
+
+/// Editor for the cookies sent with every response.
+pub fn render_cookie_jar(ui: &mut egui::Ui, cookies: &mut Vec<ResponseCookie>) {
+    let mut remove = None;
+    for (i, cookie) in cookies.iter_mut().enumerate() {
+        let id = egui::Id::new(("set_cookie", i));
+        egui::Frame::new()
+            .fill(egui::Color32::from_gray(30))
+            .inner_margin(10.0)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut cookie.enabled, "");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut cookie.name)
+                            .desired_width(120.0)
+                            .hint_text("name"),
+                    );
+                    ui.label("=");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut cookie.value)
+                            .desired_width(250.0)
+                            .hint_text("value"),
+                    );
+                    if ui.button("🗑").on_hover_text("Remove cookie").clicked() {
+                        remove = Some(i);
+                    }
+                });
+                egui::Grid::new(id.with("attributes"))
+                    .spacing([10.0, 5.0])
+                    .show(ui, |ui| {
+                        ui.label("Path:");
+                        ui.add(egui::TextEdit::singleline(&mut cookie.path).desired_width(120.0));
+                        ui.label("Domain:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut cookie.domain)
+                                .desired_width(150.0)
+                                .hint_text("host only"),
+                        );
+                        ui.end_row();
+
+                        ui.label("Expires:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut cookie.expires)
+                                .desired_width(120.0)
+                                .hint_text("2030-01-01"),
+                        );
+                        ui.label("Max-Age:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut cookie.max_age)
+                                .desired_width(150.0)
+                                .hint_text("seconds"),
+                        );
+                        ui.end_row();
+
+                        ui.label("SameSite:");
+                        egui::ComboBox::from_id_salt(id.with("same_site"))
+                            .selected_text(cookie.same_site.label())
+                            .show_ui(ui, |ui| {
+                                for option in SameSite::ALL {
+                                    ui.selectable_value(
+                                        &mut cookie.same_site,
+                                        option,
+                                        option.label(),
+                                    );
+                                }
+                            });
+                        ui.checkbox(&mut cookie.secure, "Secure");
+                        ui.checkbox(&mut cookie.http_only, "HttpOnly");
+                        ui.end_row();
+                    });
+
+                match cookie.to_header() {
+                    Ok(header) => {
+                        ui.label(
+                            egui::RichText::new(format!("Set-Cookie: {}", header))
+                                .monospace()
+                                .small()
+                                .weak(),
+                        );
+                        if cookie.same_site == SameSite::None && !cookie.secure {
+                            ui.label(
+                                egui::RichText::new(
+                                    "⚠ Browsers reject SameSite=None cookies without Secure",
+                                )
+                                .small()
+                                .color(egui::Color32::YELLOW),
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        ui.label(
+                            egui::RichText::new(format!("⚠ Not sent: {}", e))
+                                .small()
+                                .color(egui::Color32::YELLOW),
+                        );
+                    }
+                }
+            });
+        ui.add_space(5.0);
+    }
+    if let Some(i) = remove {
+        cookies.remove(i);
+    }
+
+    ui.horizontal(|ui| {
+        if ui.button("➕ Add Cookie").clicked() {
+            cookies.push(ResponseCookie::default());
+        }
+        if !cookies.is_empty()
+            && ui
+                .button("Expire All")
+                .on_hover_text("Send every cookie with Max-Age=0 so clients delete it")
+                .clicked()
+        {
+            for cookie in cookies.iter_mut() {
+                cookie.expires.clear();
+                cookie.max_age = "0".to_string();
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(name: &str, value: &str) -> (String, String) {
+        (name.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn cookie_headers_are_split_into_pairs() {
+        let headers = [
+            header("Cookie", "a=1; b = two ;; flag"),
+            header("Host", "example.com"),
+            header("cookie", "c=x=y"),
+        ];
+        assert_eq!(
+            request_cookies(&headers),
+            [
+                header("a", "1"),
+                header("b", "two"),
+                header("", "flag"),
+                header("c", "x=y"),
+            ]
+        );
+    }
+
+    #[test]
+    fn set_cookie_headers_include_the_set_attributes() {
+        let cookie = ResponseCookie {
+            value: String::from("abc"),
+            domain: String::from(" example.com "),
+            expires: String::from("2030-01-02"),
+            max_age: String::from("3600"),
+            same_site: SameSite::Lax,
+            secure: true,
+            http_only: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            cookie.to_header().unwrap(),
+            "session=abc; Path=/; Domain=example.com; Expires=Wed, 02 Jan 2030 00:00:00 GMT; \
+             Max-Age=3600; SameSite=Lax; Secure; HttpOnly"
+        );
+    }
+
+    #[test]
+    fn invalid_cookies_are_not_sent() {
+        let cookie = |name: &str, value: &str| ResponseCookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            ..Default::default()
+        };
+        assert!(cookie(" ", "x").to_header().is_err());
+        assert!(cookie("a b", "x").to_header().is_err());
+        assert!(cookie("a", "x;y").to_header().is_err());
+        let expires = ResponseCookie {
+            expires: String::from("tomorrow"),
+            ..Default::default()
+        };
+        assert!(expires.to_header().is_err());
+    }
+
+    #[test]
+    fn expiry_dates_accept_several_formats() {
+        let expected = NaiveDate::from_ymd_opt(2030, 1, 2)
+            .and_then(|date| date.and_hms_opt(12, 0, 0))
+            .map(|date| date.and_utc());
+        assert_eq!(parse_date("2030-01-02T12:00:00Z"), expected);
+        assert_eq!(parse_date("2030-01-02T14:00:00+02:00"), expected);
+        assert_eq!(parse_date("Wed, 02 Jan 2030 12:00:00 GMT"), expected);
+        assert!(parse_date("2030-13-01").is_none());
+    }
+}
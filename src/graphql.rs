@@ -0,0 +1,593 @@
+use crate::HttpRequest;
+use eframe::egui;
+use egui_json_tree::JsonTree;
+use serde_json::Value;
+
+const KEYWORD_COLOR: egui::Color32 = egui::Color32::from_rgb(197, 134, 192);
+const FIELD_COLOR: egui::Color32 = egui::Color32::from_rgb(156, 220, 254);
+const TYPE_COLOR: egui::Color32 = egui::Color32::from_rgb(78, 201, 176);
+const VARIABLE_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 220, 170);
+const STRING_COLOR: egui::Color32 = egui::Color32::from_rgb(206, 145, 120);
+const NUMBER_COLOR: egui::Color32 = egui::Color32::from_rgb(181, 206, 168);
+const DIRECTIVE_COLOR: egui::Color32 = egui::Color32::from_rgb(86, 156, 214);
+const COMMENT_COLOR: egui::Color32 = egui::Color32::from_rgb(106, 153, 85);
+
+/// One GraphQL operation as sent over HTTP.
+pub struct Operation {
+    pub query: String,
+    pub operation_name: Option<String>,
+    pub variables: Option<Value>,
+    pub extensions: Option<Value>,
+}
+
+impl Operation {
+    fn from_json(value: &Value) -> Option<Self> {
+        let query = value.get("query")?.as_str()?.to_string();
+        Some(Operation {
+            query,
+            operation_name: value
+                .get("operationName")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            variables: value.get("variables").filter(|v| !v.is_null()).cloned(),
+            extensions: value.get("extensions").filter(|v| !v.is_null()).cloned(),
+        })
+    }
+
+    /// Operation type and name, e.g. `query GetUser` or `mutation`.
+    pub fn summary(&self) -> String {
+        let tokens = tokenize(&self.query);
+        let (kind, name) = find_operation(&tokens, self.operation_name.as_deref());
+        match name {
+            Some(name) => format!("{} {}", kind, name),
+            None => kind.to_string(),
+        }
+    }
+}
+
+/// Extracts the operations of a GraphQL request: `application/graphql`
+/// bodies, JSON bodies with a `query` string (or a batch of them), and GET
+/// requests with a `query` parameter.
+pub fn operations(req: &HttpRequest) -> Vec<Operation> {
+    let param = |name: &str| {
+        req.query_params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+    };
+    let content_type = req
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        .map(|(_, value)| value.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    if content_type.starts_with("application/graphql") && !content_type.contains("json") {
+        return vec![Operation {
+            query: String::from_utf8_lossy(req.display_body()).into_owned(),
+            operation_name: param("operationName"),
+            variables: param("variables").and_then(|v| serde_json::from_str(&v).ok()),
+            extensions: None,
+        }];
+    }
+
+    if req.method == "GET" {
+        return param("query")
+            .map(|query| Operation {
+                query,
+                operation_name: param("operationName"),
+                variables: param("variables").and_then(|v| serde_json::from_str(&v).ok()),
+                extensions: param("extensions").and_then(|v| serde_json::from_str(&v).ok()),
+            })
+            .into_iter()
+            .collect();
+    }
+
+    if content_type.contains("json") {
+        let body = req.display_body();
+        // Cheap check before parsing every JSON body
+        if !body.windows(7).any(|w| w == b"\"query\"") {
+            return Vec::new();
+        }
+        return match serde_json::from_slice::<Value>(body) {
+            Ok(Value::Array(batch)) => batch.iter().filter_map(Operation::from_json).collect(),
+            Ok(value) => Operation::from_json(&value).into_iter().collect(),
+            Err(_) => Vec::new(),
+        };
+    }
+    Vec::new()
+}
+
+/// Summary shown in the request list, e.g. `query GetUser (+2)` for batches.
+pub fn summary(req: &HttpRequest) -> Option<String> {
+    let operations = operations(req);
+    let first = operations.first()?.summary();
+    Some(match operations.len() {
+        1 => first,
+        n => format!("{} (+{})", first, n - 1),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    Name,
+    Variable,
+    Directive,
+    Number,
+    String,
+    Punctuator,
+    Spread,
+    Comment,
+}
+
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    /// Byte offset in the source.
+    start: usize,
+}
+
+/// Splits a GraphQL document into tokens. Commas and whitespace are
+/// insignificant and dropped; unknown characters become punctuators.
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut at = 0;
+    while at < bytes.len() {
+        let start = at;
+        let c = bytes[at];
+        let kind = match c {
+            b' ' | b'\t' | b'\r' | b'\n' | b',' => {
+                at += 1;
+                continue;
+            }
+            b'#' => {
+                while at < bytes.len() && bytes[at] != b'\n' {
+                    at += 1;
+                }
+                TokenKind::Comment
+            }
+            b'"' if bytes[at..].starts_with(b"\"\"\"") => {
+                at += 3;
+                while at < bytes.len() && !bytes[at..].starts_with(b"\"\"\"") {
+                    at += if bytes[at] == b'\\' { 2 } else { 1 };
+                }
+                at = (at + 3).min(bytes.len());
+                TokenKind::String
+            }
+            b'"' => {
+                at += 1;
+                while at < bytes.len() && bytes[at] != b'"' && bytes[at] != b'\n' {
+                    at += if bytes[at] == b'\\' { 2 } else { 1 };
+                }
+                at = (at + 1).min(bytes.len());
+                TokenKind::String
+            }
+            b'.' if bytes[at..].starts_with(b"...") => {
+                at += 3;
+                TokenKind::Spread
+            }
+            b'$' | b'@' => {
+                at += 1;
+                while at < bytes.len() && is_name_byte(bytes[at]) {
+                    at += 1;
+                }
+                if c == b'$' {
+                    TokenKind::Variable
+                } else {
+                    TokenKind::Directive
+                }
+            }
+            b'-' | b'0'..=b'9' => {
+                at += 1;
+                while at < bytes.len()
+                    && (bytes[at].is_ascii_alphanumeric()
+                        || matches!(bytes[at], b'.' | b'+' | b'-'))
+                {
+                    at += 1;
+                }
+                TokenKind::Number
+            }
+            c if is_name_byte(c) => {
+                while at < bytes.len() && is_name_byte(bytes[at]) {
+                    at += 1;
+                }
+                TokenKind::Name
+            }
+            _ => {
+                // Advance by a whole character to stay on a UTF-8 boundary
+                at += source[at..].chars().next().map_or(1, char::len_utf8);
+                TokenKind::Punctuator
+            }
+        };
+        tokens.push(Token {
+            kind,
+            text: &source[start..at.min(bytes.len())],
+            start,
+        });
+    }
+    tokens
+}
+
+fn is_name_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Type and name of the operation to run: the one named `operation_name`,
+/// otherwise the first. A bare selection set is an anonymous query.
+fn find_operation<'a>(
+    tokens: &'a [Token<'a>],
+    operation_name: Option<&str>,
+) -> (&'static str, Option<&'a str>) {
+    let mut depth = 0usize;
+    let mut first = None;
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        match (token.kind, token.text) {
+            (TokenKind::Punctuator, "{") => {
+                if depth == 0 && first.is_none() {
+                    first = Some(("query", None));
+                }
+                depth += 1;
+            }
+            (TokenKind::Punctuator, "}") => depth = depth.saturating_sub(1),
+            (TokenKind::Name, kind @ ("query" | "mutation" | "subscription")) if depth == 0 => {
+                let kind = match kind {
+                    "mutation" => "mutation",
+                    "subscription" => "subscription",
+                    _ => "query",
+                };
+                let name = tokens
+                    .get(i + 1)
+                    .filter(|t| t.kind == TokenKind::Name)
+                    .map(|t| t.text);
+                if operation_name.is_some() && name == operation_name {
+                    return (kind, name);
+                }
+                if first.is_none() {
+                    first = Some((kind, name));
+                }
+                // Skip to the selection set so it is not counted as shorthand
+                while i < tokens.len() && tokens[i].text != "{" {
+                    i += 1;
+                }
+                continue;
+            }
+            (TokenKind::Name, "fragment") if depth == 0 => {
+                // Step into the fragment's selection set so it is not taken
+                // for a shorthand query
+                while i < tokens.len() && tokens[i].text != "{" {
+                    i += 1;
+                }
+                depth += 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    first.unwrap_or(("query", None))
+}
+
+/// Re-indents a query with two spaces per selection set level. Arguments,
+/// variable definitions and input values stay on one line.
+pub fn format_query(query: &str) -> String {
+    let tokens = tokenize(query);
+    let mut out = String::new();
+    // Open brackets; `true` for selection sets, which are laid out on lines
+    let mut stack: Vec<bool> = Vec::new();
+    let mut prev: Option<&Token> = None;
+
+    let newline = |out: &mut String, level: usize| {
+        while out.ends_with(' ') {
+            out.pop();
+        }
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(&"  ".repeat(level));
+    };
+
+    for (i, token) in tokens.iter().enumerate() {
+        let in_selection = stack.last().copied().unwrap_or(true);
+        let level = stack.iter().filter(|s| **s).count();
+
+        if token.kind == TokenKind::Comment {
+            newline(&mut out, level);
+            out.push_str(token.text);
+            out.push('\n');
+            out.push_str(&"  ".repeat(level));
+            prev = None;
+            continue;
+        }
+
+        match token.text {
+            "{" if token.kind == TokenKind::Punctuator => {
+                // Braces inside arguments are input objects, not selections
+                let selection = in_selection;
+                if !out.is_empty() && !out.ends_with([' ', '\n', '(', '[']) {
+                    out.push(' ');
+                }
+                out.push('{');
+                stack.push(selection);
+                if selection {
+                    newline(&mut out, level + 1);
+                } else {
+                    out.push(' ');
+                }
+            }
+            "}" if token.kind == TokenKind::Punctuator => {
+                let selection = stack.pop().unwrap_or(true);
+                if selection {
+                    newline(&mut out, level.saturating_sub(1));
+                    out.push('}');
+                    if stack.is_empty() {
+                        out.push_str("\n\n");
+                    }
+                } else {
+                    out.push_str(" }");
+                }
+            }
+            "(" | "[" if token.kind == TokenKind::Punctuator => {
+                out.push_str(token.text);
+                stack.push(false);
+            }
+            ")" | "]" if token.kind == TokenKind::Punctuator => {
+                stack.pop();
+                out.push_str(token.text);
+            }
+            ":" | "!" if token.kind == TokenKind::Punctuator => {
+                out.push_str(token.text);
+                if token.text == ":" {
+                    out.push(' ');
+                }
+            }
+            "=" | "|" | "&" if token.kind == TokenKind::Punctuator => {
+                out.push(' ');
+                out.push_str(token.text);
+                out.push(' ');
+            }
+            _ => {
+                let prev_kind = prev.map(|p| p.kind);
+                let prev_text = prev.map_or("", |p| p.text);
+                let ends_value = matches!(
+                    prev_kind,
+                    Some(
+                        TokenKind::Name
+                            | TokenKind::Variable
+                            | TokenKind::Number
+                            | TokenKind::String
+                            | TokenKind::Directive
+                    )
+                ) || matches!(prev_text, ")" | "]" | "}" | "!");
+
+                if in_selection && !stack.is_empty() {
+                    // A new selection starts after a completed field, unless
+                    // this continues `... on Type` or adds a directive
+                    let continues = token.kind == TokenKind::Directive
+                        || prev_kind == Some(TokenKind::Spread)
+                        || (prev_text == "on" && i >= 2 && tokens[i - 2].kind == TokenKind::Spread);
+                    if ends_value && !continues {
+                        newline(&mut out, level);
+                    } else if (prev_kind == Some(TokenKind::Spread) && token.text == "on")
+                        || (!out.ends_with([' ', '\n']) && prev_kind != Some(TokenKind::Spread))
+                    {
+                        // `...Fragment` is written without a space, `... on Type` with one
+                        out.push(' ');
+                    }
+                } else if !stack.is_empty() && ends_value && token.kind != TokenKind::Directive {
+                    // Next argument, list item or object field
+                    out.push_str(", ");
+                } else if !out.is_empty() && !out.ends_with([' ', '\n', '(', '[']) {
+                    out.push(' ');
+                }
+                out.push_str(token.text);
+            }
+        }
+        prev = Some(token);
+    }
+    out.trim_end().to_string() + "\n"
+}
+
+/// Colors a query for display in a monospace label.
+pub fn highlight(query: &str, font_id: egui::FontId) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    let default_format = egui::TextFormat::simple(font_id.clone(), egui::Color32::LIGHT_GRAY);
+    let tokens = tokenize(query);
+    let mut at = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        if token.start > at {
+            job.append(&query[at..token.start], 0.0, default_format.clone());
+        }
+        let color = match token.kind {
+            TokenKind::Name => match token.text {
+                "query" | "mutation" | "subscription" | "fragment" | "on" | "true" | "false"
+                | "null" => KEYWORD_COLOR,
+                _ if is_type_name(&tokens, i) => TYPE_COLOR,
+                _ => FIELD_COLOR,
+            },
+            TokenKind::Variable => VARIABLE_COLOR,
+            TokenKind::Directive => DIRECTIVE_COLOR,
+            TokenKind::Number => NUMBER_COLOR,
+            TokenKind::String => STRING_COLOR,
+            TokenKind::Comment => COMMENT_COLOR,
+            TokenKind::Punctuator | TokenKind::Spread => egui::Color32::GRAY,
+        };
+        job.append(
+            token.text,
+            0.0,
+            egui::TextFormat::simple(font_id.clone(), color),
+        );
+        at = token.start + token.text.len();
+    }
+    if at < query.len() {
+        job.append(&query[at..], 0.0, default_format);
+    }
+    job
+}
+
+/// Type conditions, operation and fragment names, and variable types such
+/// as the `[ID!]` in `$ids: [ID!]`.
+fn is_type_name(tokens: &[Token], i: usize) -> bool {
+    let mut j = i;
+    while j > 0 && tokens[j - 1].text == "[" {
+        j -= 1;
+    }
+    match j.checked_sub(1).map(|p| tokens[p].text) {
+        Some("on" | "fragment" | "query" | "mutation" | "subscription") => true,
+        Some(":") => j >= 2 && tokens[j - 2].kind == TokenKind::Variable,
+        _ => false,
+    }
+}
+
+/// Renders each operation with its formatted query and variables tree.
+pub fn render_operations(ui: &mut egui::Ui, req_id: u64, operations: &[Operation]) {
+    for (i, operation) in operations.iter().enumerate() {
+        let id = egui::Id::new(("graphql", req_id, i));
+        let title = if operations.len() > 1 {
+            format!("Operation {}: {}", i + 1, operation.summary())
+        } else {
+            operation.summary()
+        };
+        egui::CollapsingHeader::new(egui::RichText::new(title).strong())
+            .id_salt(id)
+            .default_open(true)
+            .show(ui, |ui| {
+                let formatted = format_query(&operation.query);
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("Query").strong());
+                    if ui
+                        .small_button("📋")
+                        .on_hover_text("Copy formatted query")
+                        .clicked()
+                    {
+                        ui.ctx().copy_text(formatted.clone());
+                    }
+                });
+                let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+                egui::Frame::new()
+                    .fill(egui::Color32::from_gray(30))
+                    .inner_margin(10.0)
+                    .show(ui, |ui| {
+                        egui::ScrollArea::vertical()
+                            .id_salt(id.with("query"))
+                            .max_height(400.0)
+                            .show(ui, |ui| {
+                                ui.label(highlight(&formatted, font_id));
+                            });
+                    });
+                ui.add_space(5.0);
+
+                for (label, value) in [
+                    ("Variables", &operation.variables),
+                    ("Extensions", &operation.extensions),
+                ] {
+                    let Some(value) = value else {
+                        continue;
+                    };
+                    ui.label(egui::RichText::new(label).strong());
+                    egui::Frame::new()
+                        .fill(egui::Color32::from_gray(30))
+                        .inner_margin(10.0)
+                        .show(ui, |ui| {
+                            JsonTree::new(id.with(label), value).show(ui);
+                        });
+                    ui.add_space(5.0);
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(content_type: &str, body: &str) -> HttpRequest {
+        HttpRequest {
+            method: String::from("POST"),
+            headers: vec![(String::from("Content-Type"), content_type.to_string())],
+            body: body.as_bytes().to_vec(),
+            ..Default::default()
+        }
+    }
+
+    fn operation(query: &str, operation_name: Option<&str>) -> String {
+        Operation {
+            query: query.to_string(),
+            operation_name: operation_name.map(str::to_string),
+            variables: None,
+            extensions: None,
+        }
+        .summary()
+    }
+
+    #[test]
+    fn operations_are_named_by_type_and_name() {
+        assert_eq!(operation("{ user { id } }", None), "query");
+        assert_eq!(
+            operation("mutation AddUser($n: String) { add }", None),
+            "mutation AddUser"
+        );
+        assert_eq!(
+            operation(
+                "fragment F on User { id } subscription OnEvent { event }",
+                None
+            ),
+            "subscription OnEvent"
+        );
+        // A field named like a keyword is not an operation
+        assert_eq!(
+            operation("query A { mutation } query B { b }", Some("B")),
+            "query B"
+        );
+        assert_eq!(operation("# mutation X\nquery { a }", None), "query");
+    }
+
+    #[test]
+    fn json_bodies_and_batches_are_summarised() {
+        let req = post(
+            "application/json",
+            r#"{"query": "query GetUser { user { id } }", "variables": {"id": 1}}"#,
+        );
+        assert_eq!(summary(&req).as_deref(), Some("query GetUser"));
+        let operations = operations(&req);
+        assert_eq!(operations[0].variables, Some(serde_json::json!({"id": 1})));
+
+        let batch = post(
+            "application/json",
+            r#"[{"query": "mutation A { a }"}, {"query": "{ b }"}]"#,
+        );
+        assert_eq!(summary(&batch).as_deref(), Some("mutation A (+1)"));
+
+        assert_eq!(summary(&post("application/json", r#"{"id": 1}"#)), None);
+        assert_eq!(summary(&post("application/json", r#"{"query": 1}"#)), None);
+    }
+
+    #[test]
+    fn graphql_bodies_and_get_parameters_are_operations() {
+        let req = post("application/graphql", "query Feed { posts }");
+        assert_eq!(summary(&req).as_deref(), Some("query Feed"));
+
+        let req = HttpRequest {
+            method: String::from("GET"),
+            query_params: vec![
+                (
+                    String::from("query"),
+                    String::from("query A { a } query B { b }"),
+                ),
+                (String::from("operationName"), String::from("B")),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(summary(&req).as_deref(), Some("query B"));
+        assert_eq!(summary(&HttpRequest::default()), None);
+    }
+
+    #[test]
+    fn queries_are_indented_by_selection_set() {
+        assert_eq!(
+            format_query("query Q($id: ID!) { user(id: $id) { id, name } }"),
+            "query Q($id: ID!) {\n  user(id: $id) {\n    id\n    name\n  }\n}\n"
+        );
+    }
+}
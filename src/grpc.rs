@@ -0,0 +1,386 @@
+use crate::HttpRequest;
+use crate::content_encoding;
+use crate::protobuf::{self, ProtoSchemas};
+use eframe::egui;
+
+/// gRPC status codes and their canonical names.
+pub const STATUS_CODES: [(u32, &str); 17] = [
+    (0, "OK"),
+    (1, "CANCELLED"),
+    (2, "UNKNOWN"),
+    (3, "INVALID_ARGUMENT"),
+    (4, "DEADLINE_EXCEEDED"),
+    (5, "NOT_FOUND"),
+    (6, "ALREADY_EXISTS"),
+    (7, "PERMISSION_DENIED"),
+    (8, "RESOURCE_EXHAUSTED"),
+    (9, "FAILED_PRECONDITION"),
+    (10, "ABORTED"),
+    (11, "OUT_OF_RANGE"),
+    (12, "UNIMPLEMENTED"),
+    (13, "INTERNAL"),
+    (14, "UNAVAILABLE"),
+    (15, "DATA_LOSS"),
+    (16, "UNAUTHENTICATED"),
+];
+
+/// Headers defined by the gRPC protocol itself rather than by the call.
+const PROTOCOL_HEADERS: [&str; 9] = [
+    "content-type",
+    "te",
+    "host",
+    "user-agent",
+    "content-length",
+    "accept-encoding",
+    "grpc-timeout",
+    "grpc-encoding",
+    "grpc-accept-encoding",
+];
+
+/// A length-prefixed message in a gRPC body.
+pub struct Frame<'a> {
+    pub compressed: bool,
+    /// gRPC-Web sends trailers as a final frame with the high bit set.
+    pub trailers: bool,
+    pub data: &'a [u8],
+}
+
+pub fn status_name(code: u32) -> &'static str {
+    STATUS_CODES
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, name)| *name)
+        .unwrap_or("UNKNOWN")
+}
+
+/// Returns true for `application/grpc` and its `+proto`, `+json` and
+/// gRPC-Web variants.
+pub fn is_grpc(content_type: &str) -> bool {
+    content_type
+        .trim()
+        .to_ascii_lowercase()
+        .starts_with("application/grpc")
+}
+
+/// Splits `/package.Service/Method` into service and method.
+pub fn service_method(path: &str) -> Option<(&str, &str)> {
+    let path = path.split('?').next()?;
+    let (service, method) = path.strip_prefix('/')?.split_once('/')?;
+    if service.is_empty() || method.is_empty() || method.contains('/') {
+        return None;
+    }
+    Some((service, method))
+}
+
+/// Splits a body into its length-prefixed messages. Trailing bytes that do
+/// not form a complete frame are reported as an error.
+pub fn split_frames(body: &[u8]) -> (Vec<Frame<'_>>, Option<String>) {
+    let mut frames = Vec::new();
+    let mut rest = body;
+    while !rest.is_empty() {
+        if rest.len() < 5 {
+            return (frames, Some(format!("{} trailing bytes", rest.len())));
+        }
+        let flags = rest[0];
+        let len = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]) as usize;
+        let Some(data) = rest.get(5..5 + len) else {
+            let error = format!(
+                "message {} is truncated: {} of {} bytes",
+                frames.len() + 1,
+                rest.len() - 5,
+                len
+            );
+            return (frames, Some(error));
+        };
+        frames.push(Frame {
+            compressed: flags & 0x01 != 0,
+            trailers: flags & 0x80 != 0,
+            data,
+        });
+        rest = &rest[5 + len..];
+    }
+    (frames, None)
+}
+
+/// Percent-encodes a status message for the `grpc-message` trailer.
+pub fn encode_message(message: &str) -> String {
+    let mut encoded = String::with_capacity(message.len());
+    for byte in message.bytes() {
+        if (0x20..=0x7e).contains(&byte) && byte != b'%' {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn header<'a>(req: &'a HttpRequest, name: &str) -> Option<&'a str> {
+    req.headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Request message type of the called method, if its service is loaded.
+fn input_type(req: &HttpRequest, schemas: &ProtoSchemas) -> Option<String> {
+    let (service, method) = service_method(&req.path)?;
+    let service = schemas.pool.get_service_by_name(service)?;
+    let method = service.methods().find(|m| m.name() == method)?;
+    Some(method.input().full_name().to_string())
+}
+
+/// Shows the called service and method and the custom metadata.
+pub fn render_call(ui: &mut egui::Ui, req: &HttpRequest, schemas: &ProtoSchemas) {
+    egui::Grid::new("grpc_call_grid")
+        .striped(true)
+        .spacing([10.0, 5.0])
+        .show(ui, |ui| {
+            let (service, method) = service_method(&req.path).unwrap_or(("?", "?"));
+            let mut row = |label: &str, value: &str| {
+                ui.label(egui::RichText::new(label).strong());
+                ui.label(egui::RichText::new(value).monospace());
+                ui.end_row();
+            };
+            row("Service", service);
+            row("Method", method);
+            if let Some(input) = input_type(req, schemas) {
+                row("Request type", &input);
+            }
+            if let Some(timeout) = header(req, "grpc-timeout") {
+                row("Timeout", timeout);
+            }
+            if let Some(encoding) = header(req, "grpc-encoding") {
+                row("Encoding", encoding);
+            }
+        });
+    ui.add_space(5.0);
+
+    let metadata: Vec<(String, String)> = req
+        .headers
+        .iter()
+        .filter(|(name, _)| {
+            !name.starts_with(':')
+                && !PROTOCOL_HEADERS
+                    .iter()
+                    .any(|reserved| name.eq_ignore_ascii_case(reserved))
+        })
+        .cloned()
+        .collect();
+    ui.label(egui::RichText::new(format!("Metadata ({})", metadata.len())).strong());
+    if metadata.is_empty() {
+        ui.label(egui::RichText::new("(none)").italics().weak());
+    } else {
+        egui::Grid::new("grpc_metadata_grid")
+            .striped(true)
+            .spacing([10.0, 5.0])
+            .show(ui, |ui| {
+                for (name, value) in &metadata {
+                    ui.label(egui::RichText::new(name).strong());
+                    ui.label(egui::RichText::new(value).monospace());
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+/// A gRPC body split into its messages, each decompressed and decoded.
+pub struct Messages {
+    pub messages: Vec<Message>,
+    /// Why the body ended early, if it did.
+    pub error: Option<String>,
+}
+
+pub struct Message {
+    pub compressed: bool,
+    pub trailers: bool,
+    /// Size as sent, before decompression.
+    pub len: usize,
+    /// The decompressed message, or the message as sent if decompressing
+    /// failed.
+    pub data: Vec<u8>,
+    pub decompress_error: Option<String>,
+    /// `None` for trailers.
+    pub decoded: Option<protobuf::Decoded>,
+}
+
+/// Decodes each message of a gRPC body with the method's request type when
+/// its service is loaded, otherwise with `selected` or schema-less.
+pub fn decode_messages(
+    req: &HttpRequest,
+    schemas: &ProtoSchemas,
+    selected: Option<&str>,
+) -> Messages {
+    let input_type = input_type(req, schemas);
+    let message_type = input_type.as_deref().or(selected);
+    let encoding = header(req, "grpc-encoding");
+
+    let (frames, error) = split_frames(req.display_body());
+    let messages = frames
+        .into_iter()
+        .map(|frame| {
+            let (data, decompress_error) = match encoding
+                .filter(|_| frame.compressed && !frame.trailers)
+                .map(|coding| content_encoding::decode_one(coding, frame.data))
+            {
+                Some(Ok(decoded)) => (decoded, None),
+                Some(Err(e)) => (frame.data.to_vec(), Some(e)),
+                None => (frame.data.to_vec(), None),
+            };
+            let decoded = (!frame.trailers).then(|| protobuf::decode(schemas, message_type, &data));
+            Message {
+                compressed: frame.compressed,
+                trailers: frame.trailers,
+                len: frame.data.len(),
+                data,
+                decompress_error,
+                decoded,
+            }
+        })
+        .collect();
+    Messages { messages, error }
+}
+
+/// Lets the message type be chosen when the called method's service is not
+/// loaded.
+pub fn render_message_type_selector(
+    ui: &mut egui::Ui,
+    req: &HttpRequest,
+    schemas: &ProtoSchemas,
+    message_type: &mut Option<String>,
+) {
+    if input_type(req, schemas).is_none() {
+        let id = egui::Id::new(("grpc-body", req.id));
+        protobuf::render_message_type_selector(ui, id, schemas, message_type);
+        ui.add_space(5.0);
+    }
+}
+
+/// Renders each message of a gRPC body, as decoded by [`decode_messages`].
+pub fn render_messages(ui: &mut egui::Ui, request_id: u64, messages: &Messages) {
+    let id = egui::Id::new(("grpc-body", request_id));
+    ui.label(egui::RichText::new(format!("Messages ({})", messages.messages.len())).strong());
+    for (i, message) in messages.messages.iter().enumerate() {
+        let mut title = format!("Message {} ({} bytes", i + 1, message.len);
+        if message.compressed {
+            title.push_str(", compressed");
+        }
+        if message.trailers {
+            title.push_str(", trailers");
+        }
+        title.push(')');
+
+        egui::CollapsingHeader::new(title)
+            .id_salt(id.with(i))
+            .default_open(true)
+            .show(ui, |ui| {
+                if let Some(e) = &message.decompress_error {
+                    ui.label(
+                        egui::RichText::new(format!("⚠ Could not decompress: {}", e))
+                            .small()
+                            .color(egui::Color32::YELLOW),
+                    );
+                }
+                match &message.decoded {
+                    Some(decoded) => {
+                        protobuf::render_message(ui, id.with(i), &message.data, decoded)
+                    }
+                    None => {
+                        ui.label(
+                            egui::RichText::new(String::from_utf8_lossy(&message.data)).monospace(),
+                        );
+                    }
+                }
+            });
+    }
+    if let Some(error) = &messages.error {
+        ui.label(
+            egui::RichText::new(format!("⚠ Incomplete gRPC body: {}", error))
+                .small()
+                .color(egui::Color32::YELLOW),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn frame(flags: u8, data: &[u8]) -> Vec<u8> {
+        let mut frame = vec![flags];
+        frame.extend((data.len() as u32).to_be_bytes());
+        frame.extend(data);
+        frame
+    }
+
+    #[test]
+    fn frames_are_split_with_their_flags() {
+        let mut body = frame(0, b"one");
+        body.extend(frame(1, b"two"));
+        body.extend(frame(0x80, b"grpc-status:0\r\n"));
+        let (frames, error) = split_frames(&body);
+        assert_eq!(error, None);
+        let flags: Vec<_> = frames.iter().map(|f| (f.compressed, f.trailers)).collect();
+        assert_eq!(flags, [(false, false), (true, false), (false, true)]);
+        assert_eq!(frames[1].data, b"two");
+    }
+
+    #[test]
+    fn partial_frames_are_reported() {
+        let mut body = frame(0, b"one");
+        body.extend(&frame(0, b"second")[..8]);
+        let (frames, error) = split_frames(&body);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(
+            error.as_deref(),
+            Some("message 2 is truncated: 3 of 6 bytes")
+        );
+
+        let (frames, error) = split_frames(&[0, 0, 0]);
+        assert!(frames.is_empty());
+        assert_eq!(error.as_deref(), Some("3 trailing bytes"));
+    }
+
+    #[test]
+    fn compressed_messages_are_decompressed_before_decoding() {
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(&[0x08, 0x2A]).unwrap();
+        let mut body = frame(1, &gzip.finish().unwrap());
+        body.extend(frame(1, b"not gzip"));
+        let req = HttpRequest {
+            path: String::from("/test.Service/Call"),
+            headers: vec![(String::from("grpc-encoding"), String::from("gzip"))],
+            body,
+            ..Default::default()
+        };
+
+        let messages = decode_messages(&req, &ProtoSchemas::default(), None);
+        assert_eq!(messages.error, None);
+        let first = &messages.messages[0];
+        assert_eq!(first.data, [0x08, 0x2A]);
+        let decoded = first.decoded.as_ref().unwrap();
+        assert_eq!(decoded.value.as_ref().unwrap()["1 (varint)"], 42);
+        let second = &messages.messages[1];
+        assert!(second.decompress_error.is_some());
+        assert_eq!(second.data, b"not gzip");
+    }
+
+    #[test]
+    fn status_messages_are_percent_encoded() {
+        assert_eq!(encode_message("not found"), "not found");
+        assert_eq!(encode_message("100% done\n"), "100%25 done%0A");
+        assert_eq!(encode_message("ü"), "%C3%BC");
+    }
+
+    #[test]
+    fn paths_split_into_service_and_method() {
+        assert_eq!(
+            service_method("/pkg.Greeter/SayHello?x=1"),
+            Some(("pkg.Greeter", "SayHello"))
+        );
+        assert_eq!(service_method("/pkg.Greeter/"), None);
+        assert_eq!(service_method("/a/b/c"), None);
+        assert_eq!(service_method("pkg.Greeter/SayHello"), None);
+    }
+}
@@ -0,0 +1,556 @@
+use crate::HttpRequest;
+use crate::capture_store::BodyState;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// A JSONPath (`$.items[0].id`, `$..name`, `$.items[?(@.price > 10)]`) or
+/// jq-like (`.items[] | select(.id == 1) | .name`, `.tags | length`) query.
+#[derive(Debug, Clone)]
+pub struct Query {
+    stages: Vec<Stage>,
+}
+
+#[derive(Debug, Clone)]
+enum Stage {
+    Path(Vec<Segment>),
+    Length,
+    Keys,
+    Select(Condition),
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Child(String),
+    Index(i64),
+    Slice(Option<i64>, Option<i64>),
+    /// All children of arrays and objects.
+    Wildcard,
+    /// The value itself and everything below it.
+    Descendants,
+    /// Children matching the condition.
+    Filter(Condition),
+}
+
+#[derive(Debug, Clone)]
+struct Condition {
+    path: Vec<Segment>,
+    /// Without a comparison the path must exist and not be `null` or `false`.
+    comparison: Option<(Comparison, Value)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("query is empty".to_string());
+        }
+        let stages = split_top_level(text, '|')
+            .into_iter()
+            .map(|stage| parse_stage(stage.trim()))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { stages })
+    }
+
+    /// All values the query selects from `root`.
+    pub fn evaluate<'a>(&self, root: &'a Value) -> Vec<Cow<'a, Value>> {
+        let mut values = vec![Cow::Borrowed(root)];
+        for stage in &self.stages {
+            values = match stage {
+                Stage::Path(path) => values
+                    .into_iter()
+                    .flat_map(|value| select_owned(value, path))
+                    .collect(),
+                Stage::Length => values
+                    .into_iter()
+                    .map(|value| Cow::Owned(Value::from(length(&value))))
+                    .collect(),
+                Stage::Keys => values
+                    .into_iter()
+                    .filter_map(|value| keys(&value).map(Cow::Owned))
+                    .collect(),
+                Stage::Select(condition) => values
+                    .into_iter()
+                    .filter(|value| condition.matches(value))
+                    .collect(),
+            };
+        }
+        values
+    }
+}
+
+fn parse_stage(stage: &str) -> Result<Stage, String> {
+    match stage {
+        "length" => Ok(Stage::Length),
+        "keys" => Ok(Stage::Keys),
+        _ if stage.starts_with("select(") && stage.ends_with(')') => {
+            Ok(Stage::Select(parse_condition(&stage[7..stage.len() - 1])?))
+        }
+        _ if stage.starts_with(['$', '.', '[']) => Ok(Stage::Path(parse_path(stage)?)),
+        _ => Err(format!("unknown filter \"{}\"", stage)),
+    }
+}
+
+fn parse_path(text: &str) -> Result<Vec<Segment>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut segments = Vec::new();
+    let mut i = 0;
+    if chars.first().is_some_and(|&c| c == '$' || c == '@') {
+        i = 1;
+    }
+
+    while i < chars.len() {
+        match chars[i] {
+            '.' if chars.get(i + 1) == Some(&'.') => {
+                segments.push(Segment::Descendants);
+                i += 2;
+                // `..name` and `..*` select below every descendant
+                if chars.get(i).is_some_and(|&c| c != '[') {
+                    i -= 1;
+                }
+            }
+            '.' => {
+                i += 1;
+                if chars.get(i) == Some(&'*') {
+                    segments.push(Segment::Wildcard);
+                    i += 1;
+                    continue;
+                }
+                let start = i;
+                while i < chars.len() && is_name_char(chars[i]) {
+                    i += 1;
+                }
+                if i > start {
+                    segments.push(Segment::Child(chars[start..i].iter().collect()));
+                } else if i < chars.len() && chars[i] != '[' {
+                    return Err(format!("unexpected '{}' after '.'", chars[i]));
+                }
+            }
+            '[' => {
+                let end = find_closing_bracket(&chars, i)
+                    .ok_or_else(|| "missing closing ']'".to_string())?;
+                let inner: String = chars[i + 1..end].iter().collect();
+                segments.push(parse_bracket(inner.trim())?);
+                i = end + 1;
+                // jq's optional `[]?` never fails here anyway
+                if chars.get(i) == Some(&'?') {
+                    i += 1;
+                }
+            }
+            c => return Err(format!("unexpected '{}'", c)),
+        }
+    }
+    Ok(segments)
+}
+
+fn parse_bracket(inner: &str) -> Result<Segment, String> {
+    if inner.is_empty() || inner == "*" {
+        return Ok(Segment::Wildcard);
+    }
+    if let Some(filter) = inner.strip_prefix('?') {
+        let filter = filter.trim();
+        let filter = filter
+            .strip_prefix('(')
+            .and_then(|f| f.strip_suffix(')'))
+            .unwrap_or(filter);
+        return Ok(Segment::Filter(parse_condition(filter)?));
+    }
+    if let Some(name) = unquote(inner) {
+        return Ok(Segment::Child(name));
+    }
+    if let Some((start, end)) = inner.split_once(':') {
+        let bound = |text: &str| {
+            let text = text.trim();
+            if text.is_empty() {
+                Ok(None)
+            } else {
+                text.parse()
+                    .map(Some)
+                    .map_err(|_| format!("invalid slice bound \"{}\"", text))
+            }
+        };
+        return Ok(Segment::Slice(bound(start)?, bound(end)?));
+    }
+    inner
+        .parse()
+        .map(Segment::Index)
+        .map_err(|_| format!("invalid index \"{}\"", inner))
+}
+
+fn parse_condition(text: &str) -> Result<Condition, String> {
+    const OPERATORS: [(&str, Comparison); 6] = [
+        ("==", Comparison::Eq),
+        ("!=", Comparison::Ne),
+        ("<=", Comparison::Le),
+        (">=", Comparison::Ge),
+        ("<", Comparison::Lt),
+        (">", Comparison::Gt),
+    ];
+
+    let text = text.trim();
+    let found = OPERATORS.iter().find_map(|&(operator, comparison)| {
+        find_top_level(text, operator).map(|at| (at, operator.len(), comparison))
+    });
+    let Some((at, len, comparison)) = found else {
+        return Ok(Condition {
+            path: parse_path(text)?,
+            comparison: None,
+        });
+    };
+
+    let path = text[..at].trim();
+    let literal = text[at + len..].trim();
+    let value = match unquote(literal) {
+        Some(string) => Value::String(string),
+        None => {
+            serde_json::from_str(literal).map_err(|_| format!("invalid value \"{}\"", literal))?
+        }
+    };
+    Ok(Condition {
+        path: parse_path(path)?,
+        comparison: Some((comparison, value)),
+    })
+}
+
+impl Condition {
+    fn matches(&self, value: &Value) -> bool {
+        let selected = select(value, &self.path);
+        match &self.comparison {
+            None => selected
+                .iter()
+                .any(|v| !matches!(v, Value::Null | Value::Bool(false))),
+            Some((comparison, expected)) => selected
+                .iter()
+                .any(|actual| compare(actual, *comparison, expected)),
+        }
+    }
+}
+
+fn compare(actual: &Value, comparison: Comparison, expected: &Value) -> bool {
+    let ordering = match (actual, expected) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+    match comparison {
+        Comparison::Eq => ordering.map_or(actual == expected, |o| o.is_eq()),
+        Comparison::Ne => ordering.map_or(actual != expected, |o| o.is_ne()),
+        Comparison::Lt => ordering.is_some_and(|o| o.is_lt()),
+        Comparison::Le => ordering.is_some_and(|o| o.is_le()),
+        Comparison::Gt => ordering.is_some_and(|o| o.is_gt()),
+        Comparison::Ge => ordering.is_some_and(|o| o.is_ge()),
+    }
+}
+
+fn select<'a>(root: &'a Value, path: &[Segment]) -> Vec<&'a Value> {
+    let mut values = vec![root];
+    for segment in path {
+        values = values
+            .into_iter()
+            .flat_map(|value| apply(value, segment))
+            .collect();
+    }
+    values
+}
+
+/// Like `select`, for values computed by earlier stages.
+fn select_owned<'a>(value: Cow<'a, Value>, path: &[Segment]) -> Vec<Cow<'a, Value>> {
+    match value {
+        Cow::Borrowed(value) => select(value, path).into_iter().map(Cow::Borrowed).collect(),
+        Cow::Owned(value) => select(&value, path)
+            .into_iter()
+            .map(|v| Cow::Owned(v.clone()))
+            .collect(),
+    }
+}
+
+fn apply<'a>(value: &'a Value, segment: &Segment) -> Vec<&'a Value> {
+    match segment {
+        Segment::Child(name) => value.get(name).into_iter().collect(),
+        Segment::Index(index) => value
+            .as_array()
+            .and_then(|items| resolve_index(*index, items.len()).and_then(|i| items.get(i)))
+            .into_iter()
+            .collect(),
+        Segment::Slice(start, end) => match value.as_array() {
+            Some(items) => {
+                let len = items.len() as i64;
+                let clamp =
+                    |bound: i64| (if bound < 0 { len + bound } else { bound }).clamp(0, len);
+                let start = clamp(start.unwrap_or(0)) as usize;
+                let end = clamp(end.unwrap_or(len)) as usize;
+                items
+                    .get(start..end.max(start))
+                    .unwrap_or(&[])
+                    .iter()
+                    .collect()
+            }
+            None => Vec::new(),
+        },
+        Segment::Wildcard => children(value),
+        Segment::Descendants => {
+            let mut all = vec![value];
+            let mut i = 0;
+            while i < all.len() {
+                let below = children(all[i]);
+                all.extend(below);
+                i += 1;
+            }
+            all
+        }
+        Segment::Filter(condition) => children(value)
+            .into_iter()
+            .filter(|child| condition.matches(child))
+            .collect(),
+    }
+}
+
+fn children(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(map) => map.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn resolve_index(index: i64, len: usize) -> Option<usize> {
+    if index < 0 {
+        len.checked_sub(index.unsigned_abs() as usize)
+    } else {
+        Some(index as usize)
+    }
+}
+
+fn length(value: &Value) -> usize {
+    match value {
+        Value::Array(items) => items.len(),
+        Value::Object(map) => map.len(),
+        Value::String(s) => s.chars().count(),
+        _ => 0,
+    }
+}
+
+/// Object keys as jq lists them, sorted.
+fn keys(value: &Value) -> Option<Value> {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            Some(Value::from_iter(keys.into_iter().cloned()))
+        }
+        Value::Array(items) => Some(Value::from_iter(0..items.len())),
+        _ => None,
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '$'
+}
+
+fn unquote(text: &str) -> Option<String> {
+    let quote = text.chars().next().filter(|&c| c == '\'' || c == '"')?;
+    let inner = text.strip_prefix(quote)?.strip_suffix(quote)?;
+    if quote == '"' {
+        serde_json::from_str(text).ok()
+    } else {
+        Some(inner.replace("\\'", "'"))
+    }
+}
+
+fn find_closing_bracket(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, &c) in chars.iter().enumerate().skip(open) {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '[' | '(') => depth += 1,
+            (None, ']' | ')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Byte offset of `pattern` outside of quotes, brackets and parentheses.
+fn find_top_level(text: &str, pattern: &str) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '[' | '(') => depth += 1,
+            (None, ']' | ')') => depth -= 1,
+            _ if depth == 0 && text[i..].starts_with(pattern) => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(at) = find_top_level(rest, separator.encode_utf8(&mut [0; 4])) {
+        parts.push(&rest[..at]);
+        rest = &rest[at + separator.len_utf8()..];
+    }
+    parts.push(rest);
+    parts
+}
+
+/// Matches as shown in a single line: strings without quotes, several
+/// matches separated by commas.
+pub fn format_matches(matches: &[Cow<'_, Value>]) -> String {
+    matches
+        .iter()
+        .map(|value| match value.as_ref() {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A saved query shown as a column in the request list.
+pub struct QueryColumn {
+    query: Query,
+    /// Values by request id; bodies do not change once captured.
+    values: HashMap<u64, Option<String>>,
+}
+
+impl QueryColumn {
+    pub fn new(source: &str) -> Result<Self, String> {
+        Ok(Self {
+            query: Query::parse(source)?,
+            values: HashMap::new(),
+        })
+    }
+
+    /// The query result for a JSON request body, `None` for other bodies,
+    /// bodies still arriving and when nothing matches.
+    pub fn value(&mut self, req: &HttpRequest) -> Option<&str> {
+        if req.body_state == BodyState::Receiving {
+            return None;
+        }
+        self.values
+            .entry(req.id)
+            .or_insert_with(|| {
+                let json: Value = serde_json::from_slice(req.display_body()).ok()?;
+                let matches = self.query.evaluate(&json);
+                (!matches.is_empty()).then(|| format_matches(&matches))
+            })
+            .as_deref()
+    }
+
+    /// Drops values of requests that were evicted.
+    pub fn forget_before(&mut self, id: u64) {
+        self.values.retain(|&request_id, _| request_id >= id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn run(query: &str, root: &Value) -> String {
+        let query = Query::parse(query).unwrap();
+        format_matches(&query.evaluate(root))
+    }
+
+    fn order() -> Value {
+        json!({
+            "id": 7,
+            "customer": {"name": "Ada", "tags": ["vip", "beta"]},
+            "items": [
+                {"sku": "a", "price": 5, "name": "Pen"},
+                {"sku": "b", "price": 12.5, "name": "Book"},
+                {"sku": "c", "price": 30, "name": "Lamp"}
+            ]
+        })
+    }
+
+    #[test]
+    fn jsonpath_selects_children_indexes_and_slices() {
+        let root = order();
+        assert_eq!(run("$.customer.name", &root), "Ada");
+        assert_eq!(run("$.items[0].sku", &root), "a");
+        assert_eq!(run("$.items[-1].sku", &root), "c");
+        assert_eq!(run("$.items[1:].sku", &root), "b, c");
+        assert_eq!(run("$['customer']['tags'][*]", &root), "vip, beta");
+        assert_eq!(run("$.items[5]", &root), "");
+    }
+
+    #[test]
+    fn descendants_and_filters() {
+        let root = order();
+        assert_eq!(run("$..name", &root), "Ada, Pen, Book, Lamp");
+        assert_eq!(run("$.items[?(@.price > 10)].sku", &root), "b, c");
+        assert_eq!(run("$.items[?(@.name == 'Pen')].price", &root), "5");
+        assert_eq!(run("$.items[?(@.missing)]", &root), "");
+    }
+
+    #[test]
+    fn jq_pipes_select_length_and_keys() {
+        let root = order();
+        assert_eq!(
+            run(".items[] | select(.price <= 12.5) | .name", &root),
+            "Pen, Book"
+        );
+        assert_eq!(run(".items | length", &root), "3");
+        assert_eq!(run(".customer.name | length", &root), "3");
+        assert_eq!(run(".customer | keys", &root), r#"["name","tags"]"#);
+        assert_eq!(
+            run(".", &root.pointer("/customer/tags").unwrap().clone()),
+            r#"["vip","beta"]"#
+        );
+    }
+
+    #[test]
+    fn invalid_queries_are_rejected() {
+        for query in [
+            "",
+            "$.items[",
+            "$.items[x]",
+            "frobnicate",
+            "$.items[?(@.a == nope)]",
+        ] {
+            assert!(Query::parse(query).is_err(), "{}", query);
+        }
+    }
+
+    #[test]
+    fn columns_skip_bodies_still_arriving() {
+        let mut req = HttpRequest {
+            id: 3,
+            body: br#"{"user": "ada"}"#.to_vec(),
+            body_state: BodyState::Receiving,
+            ..Default::default()
+        };
+        let mut column = QueryColumn::new(".user").unwrap();
+        assert_eq!(column.value(&req), None);
+        req.body_state = BodyState::Complete;
+        assert_eq!(column.value(&req), Some("ada"));
+        column.forget_before(4);
+        assert!(column.values.is_empty());
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+
+/// Where the HTTP server accepts connections.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ListenAddress {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+/// Bind presets offered in the top panel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BindMode {
+    Loopback,
+    AllIpv4,
+    DualStack,
+    Interface,
+    UnixSocket,
+}
+
+impl BindMode {
+    pub const ALL: [BindMode; 5] = [
+        BindMode::Loopback,
+        BindMode::AllIpv4,
+        BindMode::DualStack,
+        BindMode::Interface,
+        BindMode::UnixSocket,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BindMode::Loopback => "Loopback (127.0.0.1)",
+            BindMode::AllIpv4 => "All IPv4 (0.0.0.0)",
+            BindMode::DualStack => "Dual-stack ([::])",
+            BindMode::Interface => "Specific interface",
+            BindMode::UnixSocket => "Unix socket",
+        }
+    }
+
+    /// Builds the listen address for this mode from the top panel inputs.
+    pub fn to_address(
+        self,
+        host: &str,
+        port: u16,
+        socket_path: &str,
+    ) -> Result<ListenAddress, String> {
+        let ip = match self {
+            BindMode::Loopback => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            BindMode::AllIpv4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            BindMode::DualStack => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            BindMode::Interface => host
+                .trim()
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .map_err(|_| format!("Invalid interface address: {}", host))?,
+            BindMode::UnixSocket => {
+                let path = socket_path.trim();
+                if path.is_empty() {
+                    return Err("Unix socket path must not be empty".to_string());
+                }
+                return Ok(ListenAddress::Unix(PathBuf::from(path)));
+            }
+        };
+        Ok(ListenAddress::Tcp(SocketAddr::new(ip, port)))
+    }
+}
+
+impl ListenAddress {
+    pub fn port(&self) -> Option<u16> {
+        match self {
+            ListenAddress::Tcp(addr) => Some(addr.port()),
+            ListenAddress::Unix(_) => None,
+        }
+    }
+
+    /// Returns the preset matching this address.
+    pub fn bind_mode(&self) -> BindMode {
+        match self {
+            ListenAddress::Tcp(addr) => match addr.ip() {
+                IpAddr::V4(ip) if ip == Ipv4Addr::LOCALHOST => BindMode::Loopback,
+                IpAddr::V4(ip) if ip.is_unspecified() => BindMode::AllIpv4,
+                IpAddr::V6(ip) if ip.is_unspecified() => BindMode::DualStack,
+                _ => BindMode::Interface,
+            },
+            ListenAddress::Unix(_) => BindMode::UnixSocket,
+        }
+    }
+
+    /// Arguments for curl that reach this address, e.g. `http://localhost:8080`
+    /// or `--unix-socket /tmp/inspector.sock http://localhost`.
+    pub fn curl_target(&self) -> String {
+        match self {
+            ListenAddress::Tcp(addr) => {
+                let ip = addr.ip();
+                if ip.is_unspecified() {
+                    format!("http://localhost:{}", addr.port())
+                } else {
+                    format!("http://{}", addr)
+                }
+            }
+            ListenAddress::Unix(path) => {
+                format!("--unix-socket {} http://localhost", path.display())
+            }
+        }
+    }
+}
+
+impl fmt::Display for ListenAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListenAddress::Tcp(addr) => write!(f, "http://{}", addr),
+            ListenAddress::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// Binds a TCP listener. Unspecified IPv6 addresses are bound dual-stack so
+/// `[::]` also accepts IPv4 clients regardless of the OS default.
+pub fn bind_tcp(addr: SocketAddr) -> std::io::Result<tokio::net::TcpListener> {
+    let domain = socket2::Domain::for_address(addr);
+    let socket = socket2::Socket::new(domain, socket2::Type::STREAM, Some(socket2::Protocol::TCP))?;
+    if addr.is_ipv6() && addr.ip().is_unspecified() {
+        socket.set_only_v6(false)?;
+    }
+    #[cfg(not(windows))]
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+    tokio::net::TcpListener::from_std(socket.into())
+}
@@ -1,88 +1,231 @@
+mod auth;
+mod binary_json;
+mod capture_store;
+mod code_view;
+mod connection_io;
+mod connections;
+mod content_encoding;
+mod content_view;
+mod cookies;
+mod graphql;
+mod grpc;
+mod json_query;
+mod listen_address;
+mod malformed;
+mod media;
+mod multipart;
+mod protobuf;
 mod request_detail;
 mod request_overview;
 mod response_config;
-
-use chrono::Local;
+mod server;
+mod settings;
+mod timestamps;
+mod timing;
+mod url_encoded;
+mod xml;
+
+use chrono::{DateTime, Utc};
 use eframe::egui;
-use http_body_util::{BodyExt, Full};
-use hyper::body::Bytes;
-use hyper::server::conn::http1;
-use hyper::service::service_fn;
-use hyper::{Request, Response, body::Incoming};
-use hyper_util::rt::TokioIo;
-use std::sync::mpsc::{Receiver, Sender, channel};
-use std::sync::{Arc, Mutex};
+use listen_address::{BindMode, ListenAddress};
+use server::{ServerHandle, ServerShared};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
-use tokio::net::TcpListener;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct HttpRequest {
-    pub timestamp: String,
+    /// Sequence number assigned by `CaptureStore::push`, unique for the
+    /// lifetime of the application.
+    pub id: u64,
+    /// When the request head arrived.
+    pub timestamp: DateTime<Utc>,
     pub method: String,
     pub path: String,
+    /// Decoded with `+` as a space; the raw query stays in `path`.
     pub query_params: Vec<(String, String)>,
     pub headers: Vec<(String, String)>,
+    /// Trailer fields sent after a chunked or HTTP/2 body.
+    pub trailers: Vec<(String, String)>,
     pub remote_addr: String,
-    pub body: String,
+    pub connection_id: u64,
+    /// Body as received, still content-encoded; only a prefix if `truncated`.
+    pub body: Vec<u8>,
+    /// Size of the complete body, or of what arrived so far.
     pub body_size: usize,
+    pub body_state: capture_store::BodyState,
+    /// Sizes and arrival times of the body pieces.
+    pub chunks: Vec<capture_store::BodyChunk>,
+    /// Set when the body exceeded the capture limit.
+    pub truncated: Option<capture_store::TruncatedBody>,
+    /// Body with its Content-Encoding undone, if it had one.
+    pub decoded_body: Option<Vec<u8>>,
+    pub decode_error: Option<String>,
+    /// Bytes as received on the wire, capped at `connection_io::RAW_CAPTURE_LIMIT`.
+    pub raw: Vec<u8>,
+    /// Total size of the request on the wire.
+    pub raw_size: usize,
+    /// Set for requests the server rejected; fields are then recovered leniently.
+    pub parse_error: Option<String>,
+    /// Operation type and name of a GraphQL request, e.g. `query GetUser`.
+    pub graphql: Option<String>,
+    /// Status code of the response sent, `None` for rejected requests.
+    pub response_status: Option<u16>,
+    pub timings: timing::RequestTimings,
+}
+
+impl HttpRequest {
+    /// The query string as received, without the `?`.
+    pub fn raw_query(&self) -> Option<&str> {
+        self.path.split_once('?').map(|(_, query)| query)
+    }
+
+    /// The body used for display: decoded if possible, otherwise as received.
+    pub fn display_body(&self) -> &[u8] {
+        self.decoded_body.as_deref().unwrap_or(&self.body)
+    }
 }
 
+/// How long error messages stay in the status bar.
+const ERROR_DISPLAY_TIME: Duration = Duration::from_secs(5);
+
 struct HttpServerApp {
-    requests: Arc<Mutex<Vec<HttpRequest>>>,
-    port: u16,
+    requests: Arc<Mutex<capture_store::CaptureStore>>,
+    listen_address: ListenAddress,
+    bind_mode: BindMode,
+    host_input: String,
     port_input: String,
-    selected_request: Option<usize>,
-    port_change_tx: Sender<u16>,
+    socket_path_input: String,
+    /// Id of the selected request.
+    selected_request: Option<u64>,
+    server: ServerHandle,
     server_status: Arc<Mutex<String>>,
-    last_working_port: Arc<Mutex<u16>>,
+    last_working_address: Arc<Mutex<ListenAddress>>,
+    active_connections: Arc<AtomicUsize>,
+    connections: Arc<Mutex<Vec<connections::ConnectionInfo>>>,
+    parser_options: Arc<Mutex<settings::ParserOptions>>,
     error_message: Option<String>,
     error_timestamp: Option<Instant>,
     response_config: Arc<Mutex<response_config::ResponseConfig>>,
     active_tab: AppTab,
+    detail_state: request_detail::RequestDetailState,
+    proto_schemas: Arc<Mutex<protobuf::ProtoSchemas>>,
+    jwt_keys: Arc<Mutex<auth::JwtKeys>>,
+    list_layout: request_overview::ListLayout,
+    timestamps: timestamps::TimestampDisplay,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum AppTab {
     RequestDetails,
     ResponseConfig,
+    Connections,
+    Settings,
 }
 
 impl HttpServerApp {
-    fn new(
-        port: u16,
-        port_change_tx: Sender<u16>,
-        server_status: Arc<Mutex<String>>,
-        last_working_port: Arc<Mutex<u16>>,
-    ) -> Self {
-        Self {
-            requests: Arc::new(Mutex::new(Vec::new())),
-            port,
-            port_input: port.to_string(),
+    fn new(listen_address: ListenAddress, server: ServerHandle, shared: &ServerShared) -> Self {
+        let mut app = Self {
+            requests: Arc::clone(&shared.requests),
+            listen_address: listen_address.clone(),
+            bind_mode: listen_address.bind_mode(),
+            host_input: String::new(),
+            port_input: String::new(),
+            socket_path_input: default_socket_path(),
             selected_request: None,
-            port_change_tx,
-            server_status,
-            last_working_port,
+            server,
+            server_status: Arc::clone(&shared.status),
+            last_working_address: Arc::clone(&shared.last_working_address),
+            active_connections: Arc::clone(&shared.active_connections),
+            connections: Arc::clone(&shared.connections),
+            parser_options: Arc::clone(&shared.parser_options),
             error_message: None,
             error_timestamp: None,
-            response_config: Arc::new(Mutex::new(response_config::ResponseConfig::default())),
+            response_config: Arc::clone(&shared.response_config),
             active_tab: AppTab::RequestDetails,
+            detail_state: request_detail::RequestDetailState::default(),
+            proto_schemas: Arc::new(Mutex::new(protobuf::ProtoSchemas::default())),
+            jwt_keys: Arc::new(Mutex::new(auth::JwtKeys::default())),
+            list_layout: request_overview::ListLayout::default(),
+            timestamps: timestamps::TimestampDisplay::default(),
+        };
+        app.reset_bind_inputs(&listen_address);
+        app
+    }
+
+    /// Resets the top panel inputs to reflect the given address.
+    fn reset_bind_inputs(&mut self, address: &ListenAddress) {
+        self.bind_mode = address.bind_mode();
+        match address {
+            ListenAddress::Tcp(addr) => {
+                self.host_input = addr.ip().to_string();
+                self.port_input = addr.port().to_string();
+            }
+            ListenAddress::Unix(path) => {
+                self.socket_path_input = path.display().to_string();
+            }
+        }
+    }
+
+    /// Builds an address from the top panel inputs and asks the server to rebind.
+    fn apply_bind_inputs(&mut self) {
+        let last_working = self.last_working_address.lock().unwrap().clone();
+
+        let port = match self.bind_mode {
+            BindMode::UnixSocket => last_working.port().unwrap_or(8080),
+            _ => match self.port_input.parse::<u16>() {
+                Ok(port) if port > 0 => port,
+                _ => {
+                    // Invalid or zero port, reset to last working port
+                    if let Some(port) = last_working.port() {
+                        self.port_input = port.to_string();
+                    }
+                    return;
+                }
+            },
+        };
+
+        match self
+            .bind_mode
+            .to_address(&self.host_input, port, &self.socket_path_input)
+        {
+            Ok(address) => {
+                if address != self.listen_address {
+                    self.server.rebind(address.clone());
+                    self.listen_address = address;
+                }
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Error: {}", e));
+                self.error_timestamp = Some(Instant::now());
+            }
         }
     }
 }
 
 impl eframe::App for HttpServerApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, request_overview::LAYOUT_KEY, &self.list_layout);
+        eframe::set_value(storage, timestamps::SETTINGS_KEY, &self.timestamps);
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        ctx.request_repaint();
+        media::forget_hidden_images(ctx);
 
         // Check if error message should be cleared (after 5 seconds)
-        if let Some(timestamp) = self.error_timestamp
-            && timestamp.elapsed() > Duration::from_secs(5)
-        {
-            self.error_message = None;
-            self.error_timestamp = None;
+        if let Some(timestamp) = self.error_timestamp {
+            match ERROR_DISPLAY_TIME.checked_sub(timestamp.elapsed()) {
+                Some(remaining) => ctx.request_repaint_after(remaining),
+                None => {
+                    self.error_message = None;
+                    self.error_timestamp = None;
+                }
+            }
         }
 
-        // Check if server is in error state and reset port input to last working port
+        // Check if server is in error state and reset bind inputs to last working address
+        let mut rolled_back = None;
         {
             let status = self.server_status.lock().unwrap();
             if status.contains("Error") {
@@ -92,15 +235,18 @@ impl eframe::App for HttpServerApp {
                     self.error_timestamp = Some(Instant::now());
                 }
 
-                let last_working = *self.last_working_port.lock().unwrap();
-                if self.port != last_working {
-                    self.port = last_working;
-                    self.port_input = last_working.to_string();
-                    // Tell server to rebind to the last working port
-                    let _ = self.port_change_tx.send(last_working);
+                let last_working = self.last_working_address.lock().unwrap().clone();
+                if self.listen_address != last_working {
+                    self.listen_address = last_working.clone();
+                    // Tell server to rebind to the last working address
+                    self.server.rebind(last_working.clone());
+                    rolled_back = Some(last_working);
                 }
             }
         }
+        if let Some(address) = rolled_back {
+            self.reset_bind_inputs(&address);
+        }
 
         // Top panel with title and controls
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
@@ -109,32 +255,76 @@ impl eframe::App for HttpServerApp {
                 ui.heading("Inspector HTTP");
                 ui.separator();
 
-                // Port configuration
-                ui.label("Port:");
-                let port_edit = egui::TextEdit::singleline(&mut self.port_input)
-                    .desired_width(60.0)
-                    .hint_text("8080");
-
-                if ui.add(port_edit).lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                    if let Ok(new_port) = self.port_input.parse::<u16>() {
-                        if new_port != self.port && new_port > 0 {
-                            let _ = self.port_change_tx.send(new_port);
-                            self.port = new_port;
-                        } else if new_port == 0 {
-                            // Port 0 is invalid, reset to last working port
-                            let last_working = *self.last_working_port.lock().unwrap();
-                            self.port_input = last_working.to_string();
+                // Bind address configuration
+                ui.label("Bind:");
+                let previous_mode = self.bind_mode;
+                egui::ComboBox::from_id_salt("bind_mode")
+                    .selected_text(self.bind_mode.label())
+                    .show_ui(ui, |ui| {
+                        for mode in BindMode::ALL {
+                            ui.selectable_value(&mut self.bind_mode, mode, mode.label());
                         }
-                    } else {
-                        // Invalid number, reset to last working port
-                        let last_working = *self.last_working_port.lock().unwrap();
-                        self.port_input = last_working.to_string();
+                    });
+
+                let mut submitted = false;
+                match self.bind_mode {
+                    BindMode::UnixSocket => {
+                        let path_edit = egui::TextEdit::singleline(&mut self.socket_path_input)
+                            .desired_width(200.0)
+                            .hint_text("/tmp/inspector-http.sock");
+                        submitted |= ui.add(path_edit).lost_focus()
+                            && ui.input(|i| i.key_pressed(egui::Key::Enter));
                     }
+                    mode => {
+                        if mode == BindMode::Interface {
+                            let host_edit = egui::TextEdit::singleline(&mut self.host_input)
+                                .desired_width(120.0)
+                                .hint_text("192.168.1.10");
+                            submitted |= ui.add(host_edit).lost_focus()
+                                && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                        }
+
+                        // Port configuration
+                        ui.label("Port:");
+                        let port_edit = egui::TextEdit::singleline(&mut self.port_input)
+                            .desired_width(60.0)
+                            .hint_text("8080");
+                        submitted |= ui.add(port_edit).lost_focus()
+                            && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    }
+                }
+
+                // Presets apply immediately, custom inputs apply on Enter
+                let preset_selected = self.bind_mode != previous_mode
+                    && !matches!(self.bind_mode, BindMode::Interface | BindMode::UnixSocket);
+                if submitted || preset_selected {
+                    self.apply_bind_inputs();
+                }
+
+                ui.separator();
+
+                // Server lifecycle controls
+                if self.server.target().running {
+                    if ui.button("⏹ Stop").clicked() {
+                        self.server.stop();
+                    }
+                } else if ui.button("▶ Start").clicked() {
+                    self.server.start();
+                }
+                if ui.button("🔄 Restart").clicked() {
+                    self.server.restart();
                 }
 
                 ui.separator();
                 let requests = self.requests.lock().unwrap();
                 ui.label(format!("Total Requests: {}", requests.len()));
+                if requests.evicted > 0 {
+                    ui.label(
+                        egui::RichText::new(format!("({} evicted)", requests.evicted))
+                            .color(egui::Color32::YELLOW),
+                    )
+                    .on_hover_text("The oldest requests were dropped to stay within the capture limits in Settings");
+                }
             });
             ui.add_space(5.0);
         });
@@ -164,6 +354,10 @@ impl eframe::App for HttpServerApp {
                     ui.label(egui::RichText::new("🔌").color(status_color).size(16.0));
                     ui.label(egui::RichText::new(&*status).color(status_color));
                 }
+
+                ui.separator();
+                let active_connections = self.active_connections.load(Ordering::SeqCst);
+                ui.label(format!("Active connections: {}", active_connections));
             });
             ui.add_space(3.0);
         });
@@ -178,7 +372,9 @@ impl eframe::App for HttpServerApp {
                     ui,
                     &requests,
                     &mut self.selected_request,
-                    self.port,
+                    &self.listen_address,
+                    &mut self.list_layout,
+                    &self.timestamps,
                 );
 
                 // Handle clear requests action
@@ -186,10 +382,11 @@ impl eframe::App for HttpServerApp {
                     drop(requests); // Release the lock before clearing
                     self.requests.lock().unwrap().clear();
                     self.selected_request = None;
+                    self.list_layout.clear_cache();
                 }
             });
 
-        // Right panel - Tabbed view (Request Details / Response Config)
+        // Right panel - Tabbed view (Request Details / Response Config / Connections / Settings)
         egui::CentralPanel::default().show(ctx, |ui| {
             // Tab bar
             ui.horizontal(|ui| {
@@ -203,6 +400,8 @@ impl eframe::App for HttpServerApp {
                     AppTab::ResponseConfig,
                     "📤 Response Config",
                 );
+                ui.selectable_value(&mut self.active_tab, AppTab::Connections, "🔗 Connections");
+                ui.selectable_value(&mut self.active_tab, AppTab::Settings, "⚙ Settings");
             });
             ui.separator();
 
@@ -210,188 +409,73 @@ impl eframe::App for HttpServerApp {
             match self.active_tab {
                 AppTab::RequestDetails => {
                     let requests = self.requests.lock().unwrap();
-                    let selected_request = self.selected_request.and_then(|idx| requests.get(idx));
-                    request_detail::render_request_detail(ui, selected_request);
+                    let selected_request = self.selected_request.and_then(|id| requests.get(id));
+                    let proto_schemas = self.proto_schemas.lock().unwrap();
+                    let jwt_keys = self.jwt_keys.lock().unwrap();
+                    request_detail::render_request_detail(
+                        ui,
+                        selected_request,
+                        &mut self.detail_state,
+                        &proto_schemas,
+                        &jwt_keys,
+                        &mut self.list_layout,
+                        &self.timestamps,
+                    );
                 }
                 AppTab::ResponseConfig => {
                     let mut config = self.response_config.lock().unwrap();
                     response_config::render_response_config(ui, &mut config);
                 }
+                AppTab::Connections => {
+                    let mut connections = self.connections.lock().unwrap();
+                    connections::render_connections(ui, &mut connections, &self.timestamps);
+                }
+                AppTab::Settings => {
+                    let mut parser_options = self.parser_options.lock().unwrap();
+                    let mut requests = self.requests.lock().unwrap();
+                    settings::render_settings(
+                        ui,
+                        &mut parser_options,
+                        &mut requests,
+                        &mut self.timestamps,
+                        &self.proto_schemas,
+                        &self.jwt_keys,
+                    );
+                }
             }
         });
     }
 }
 
-async fn handle_request(
-    req: Request<Incoming>,
-    remote_addr: String,
-    requests: Arc<Mutex<Vec<HttpRequest>>>,
-    response_config: Arc<Mutex<response_config::ResponseConfig>>,
-) -> Result<Response<Full<Bytes>>, hyper::Error> {
-    let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string();
-    let method = req.method().to_string();
-
-    // Capture full URI including query parameters
-    let path = if let Some(query) = req.uri().query() {
-        format!("{}?{}", req.uri().path(), query)
-    } else {
-        req.uri().path().to_string()
-    };
-
-    // Parse query parameters
-    let query_params: Vec<(String, String)> = req
-        .uri()
-        .query()
-        .map(|q| {
-            q.split('&')
-                .filter_map(|pair| {
-                    let mut split = pair.splitn(2, '=');
-                    match (split.next(), split.next()) {
-                        (Some(key), Some(value)) => Some((
-                            urlencoding::decode(key).unwrap_or_default().to_string(),
-                            urlencoding::decode(value).unwrap_or_default().to_string(),
-                        )),
-                        (Some(key), None) => Some((
-                            urlencoding::decode(key).unwrap_or_default().to_string(),
-                            String::new(),
-                        )),
-                        _ => None,
-                    }
-                })
-                .collect()
-        })
-        .unwrap_or_default();
-
-    let headers: Vec<(String, String)> = req
-        .headers()
-        .iter()
-        .map(|(name, value)| {
-            (
-                name.to_string(),
-                value.to_str().unwrap_or("<binary>").to_string(),
-            )
-        })
-        .collect();
-
-    // Read the request body
-    let body_bytes = req.collect().await?.to_bytes();
-    let body_size = body_bytes.len();
-    let body = String::from_utf8_lossy(&body_bytes).to_string();
-
-    let http_req = HttpRequest {
-        timestamp,
-        method,
-        path,
-        query_params,
-        headers,
-        remote_addr,
-        body,
-        body_size,
-    };
-
-    requests.lock().unwrap().push(http_req);
-
-    // Build response using configured status code and body
-    let config = response_config.lock().unwrap();
-    let response_body = config.response_body.clone();
-    let status_code = config.status_code;
-    drop(config); // Release lock early
-
-    // Build the response - this shouldn't fail with valid status codes
-    let response = Response::builder()
-        .status(status_code)
-        .body(Full::new(Bytes::from(response_body)))
-        .unwrap_or_else(|e| {
-            eprintln!("Error building response: {}", e);
-            // Fallback to a simple 200 OK response
-            Response::new(Full::new(Bytes::from("OK\n")))
-        });
-
-    Ok(response)
-}
-
-fn find_available_port(start_port: u16) -> u16 {
-    let rt = tokio::runtime::Runtime::new().unwrap();
-    rt.block_on(async {
-        for port in start_port..=65535 {
-            let addr = format!("0.0.0.0:{}", port);
-            if TcpListener::bind(&addr).await.is_ok() {
-                return port;
-            }
-        }
-        // Fallback to start_port if no port is available (unlikely)
-        start_port
-    })
+fn default_socket_path() -> String {
+    std::env::temp_dir()
+        .join("inspector-http.sock")
+        .display()
+        .to_string()
 }
 
 fn main() {
-    // Find first available port starting from 8080
-    let available_port = find_available_port(8080);
-
-    let (port_tx, port_rx): (Sender<u16>, Receiver<u16>) = channel();
-    let port_rx = Arc::new(Mutex::new(port_rx));
-    let server_status = Arc::new(Mutex::new(String::from("Starting...")));
-    let last_working_port = Arc::new(Mutex::new(available_port));
-
-    let app = HttpServerApp::new(
-        available_port,
-        port_tx,
-        Arc::clone(&server_status),
-        Arc::clone(&last_working_port),
-    );
-    let requests = Arc::clone(&app.requests);
-    let response_config = Arc::clone(&app.response_config);
-    let initial_port = app.port;
-
-    // Spawn server thread that can restart on port changes
-    let port_rx_clone = Arc::clone(&port_rx);
-    let last_working_clone = Arc::clone(&last_working_port);
-    std::thread::spawn(move || {
-        let rt = tokio::runtime::Runtime::new().unwrap();
-        let mut current_port = initial_port;
-
-        loop {
-            let requests_clone = Arc::clone(&requests);
-            let response_config_clone = Arc::clone(&response_config);
-            let status_clone = Arc::clone(&server_status);
-            let port_rx_clone2 = Arc::clone(&port_rx_clone);
-            let last_working_clone2 = Arc::clone(&last_working_clone);
-
-            // Update status
-            *status_clone.lock().unwrap() = format!("Listening on http://0.0.0.0:{}", current_port);
-
-            // Run server with cancellation support
-            rt.block_on(async {
-                match run_server_cancellable(
-                    current_port,
-                    requests_clone,
-                    response_config_clone,
-                    port_rx_clone2,
-                    status_clone,
-                    last_working_clone2,
-                )
-                .await
-                {
-                    Ok(new_port) => {
-                        current_port = new_port;
-                        println!("Restarting server on port {}", current_port);
-                    }
-                    Err(e) => {
-                        eprintln!("Server error: {}", e);
-                        let error_msg = format!("Error: {}", e);
-                        *server_status.lock().unwrap() = error_msg;
-
-                        // Wait for user to provide a new port instead of retrying same port
-                        if let Ok(new_port) = port_rx_clone.lock().unwrap().recv() {
-                            current_port = new_port;
-                            println!("New port received after error: {}", current_port);
-                        }
-                    }
-                }
-            });
-        }
-    });
+    // Find first available loopback port starting from 8080
+    let loopback = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    let available_port = server::find_available_port(loopback, 8080);
+    let initial_address = ListenAddress::Tcp(SocketAddr::new(loopback, available_port));
+
+    let shared = ServerShared {
+        requests: Arc::new(Mutex::new(capture_store::CaptureStore::default())),
+        response_config: Arc::new(Mutex::new(response_config::ResponseConfig::default())),
+        status: Arc::new(Mutex::new(String::from("Starting..."))),
+        last_working_address: Arc::new(Mutex::new(initial_address.clone())),
+        active_connections: Arc::new(AtomicUsize::new(0)),
+        connections: Arc::new(Mutex::new(Vec::new())),
+        next_connection_id: Arc::new(AtomicU64::new(0)),
+        parser_options: Arc::new(Mutex::new(settings::ParserOptions::default())),
+        gui: Arc::new(OnceLock::new()),
+    };
 
+    // Spawn server thread that follows start/stop/rebind requests from the GUI
+    let server = server::spawn(initial_address.clone(), shared.clone());
+    let mut app = HttpServerApp::new(initial_address, server, &shared);
+    let gui = Arc::clone(&shared.gui);
     // Load application icon
     let icon_data = include_bytes!("../assets/icon-256.png");
     let icon_image = image::load_from_memory(icon_data)
@@ -413,68 +497,21 @@ fn main() {
         ..Default::default()
     };
 
-    eframe::run_native("Inspector HTTP", options, Box::new(|_cc| Ok(Box::new(app)))).unwrap();
-}
-
-async fn run_server_cancellable(
-    port: u16,
-    requests: Arc<Mutex<Vec<HttpRequest>>>,
-    response_config: Arc<Mutex<response_config::ResponseConfig>>,
-    port_rx: Arc<Mutex<Receiver<u16>>>,
-    _server_status: Arc<Mutex<String>>,
-    last_working_port: Arc<Mutex<u16>>,
-) -> Result<u16, String> {
-    let addr = format!("0.0.0.0:{}", port);
-    let listener = match TcpListener::bind(&addr).await {
-        Ok(l) => l,
-        Err(e) => {
-            return Err(format!("Failed to bind to {}: {}", addr, e));
-        }
-    };
-
-    // Successfully bound - update last working port
-    *last_working_port.lock().unwrap() = port;
-    println!("HTTP Server listening on {}", addr);
-
-    loop {
-        // Check for port change request (non-blocking)
-        if let Ok(new_port) = port_rx.lock().unwrap().try_recv() {
-            println!("Port change requested: {} -> {}", port, new_port);
-            return Ok(new_port);
-        }
-
-        // Accept connections with timeout
-        let accept_result =
-            tokio::time::timeout(std::time::Duration::from_millis(100), listener.accept()).await;
-
-        match accept_result {
-            Ok(Ok((stream, remote_addr))) => {
-                let io = TokioIo::new(stream);
-                let requests = Arc::clone(&requests);
-                let response_config = Arc::clone(&response_config);
-                let remote_addr_str = remote_addr.to_string();
-
-                tokio::task::spawn(async move {
-                    let service = service_fn(move |req| {
-                        handle_request(
-                            req,
-                            remote_addr_str.clone(),
-                            Arc::clone(&requests),
-                            Arc::clone(&response_config),
-                        )
-                    });
-
-                    if let Err(err) = http1::Builder::new().serve_connection(io, service).await {
-                        eprintln!("Error serving connection: {:?}", err);
-                    }
-                });
-            }
-            Ok(Err(e)) => {
-                eprintln!("Error accepting connection: {}", e);
-            }
-            Err(_) => {
-                // Timeout - continue loop to check for port changes
+    eframe::run_native(
+        "Inspector HTTP",
+        options,
+        Box::new(|cc| {
+            egui_extras::install_image_loaders(&cc.egui_ctx);
+            // The server repaints the GUI when requests arrive
+            let _ = gui.set(cc.egui_ctx.clone());
+            if let Some(storage) = cc.storage {
+                app.list_layout =
+                    eframe::get_value(storage, request_overview::LAYOUT_KEY).unwrap_or_default();
+                app.timestamps =
+                    eframe::get_value(storage, timestamps::SETTINGS_KEY).unwrap_or_default();
             }
-        }
-    }
+            Ok(Box::new(app))
+        }),
+    )
+    .unwrap();
 }
@@ -0,0 +1,152 @@
+use crate::settings::ParserOptions;
+
+/// A request recovered from bytes hyper rejected.
+pub struct LenientRequest {
+    pub method: String,
+    pub target: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Describes errors caused by what the client sent, as opposed to I/O or
+/// shutdown errors.
+pub fn request_error_kind(err: &hyper::Error) -> Option<&'static str> {
+    if err.is_parse_too_large() {
+        Some("Message too large")
+    } else if err.is_parse() {
+        Some("Parse error")
+    } else if err.is_incomplete_message() {
+        Some("Incomplete message")
+    } else if err.is_timeout() {
+        Some("Header read timeout")
+    } else {
+        None
+    }
+}
+
+/// Parses a rejected request as far as the fallback options allow. The
+/// request line is always recovered if present; headers and body only when
+/// every header line is acceptable under `options`.
+pub fn parse_leniently(raw: &[u8], options: &ParserOptions) -> LenientRequest {
+    let (head_end, body_start) = find_head_end(raw).unwrap_or((raw.len(), raw.len()));
+    let head = String::from_utf8_lossy(&raw[..head_end]);
+
+    let mut lines = head
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .skip_while(|line| line.is_empty());
+
+    let mut request = LenientRequest {
+        method: String::new(),
+        target: String::new(),
+        headers: Vec::new(),
+        body: Vec::new(),
+    };
+
+    let mut request_line = lines.next().unwrap_or("").split_whitespace();
+    request.method = request_line.next().unwrap_or("").to_string();
+    request.target = request_line.next().unwrap_or("").to_string();
+
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in lines {
+        if line.starts_with([' ', '\t']) {
+            match headers.last_mut() {
+                Some((_, value)) if options.allow_obsolete_line_folding => {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                    continue;
+                }
+                _ => return request,
+            }
+        }
+
+        let Some((name, value)) = line.split_once(':') else {
+            return request;
+        };
+        if name.is_empty()
+            || (name.contains(char::is_whitespace) && !options.allow_spaces_in_header_names)
+        {
+            return request;
+        }
+        headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+    }
+    request.headers = headers;
+
+    let mut body = raw[body_start..].to_vec();
+    if let Some(len) = request
+        .headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+    {
+        body.truncate(len);
+    }
+    request.body = body;
+    request
+}
+
+/// Returns where the head ends and where the body starts, accepting both
+/// `\r\n\r\n` and `\n\n`.
+fn find_head_end(raw: &[u8]) -> Option<(usize, usize)> {
+    let crlf = raw.windows(4).position(|w| w == b"\r\n\r\n");
+    let lf = raw.windows(2).position(|w| w == b"\n\n");
+    match (crlf, lf) {
+        (Some(crlf), Some(lf)) if lf < crlf => Some((lf, lf + 2)),
+        (Some(crlf), _) => Some((crlf, crlf + 4)),
+        (None, Some(lf)) => Some((lf, lf + 2)),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOLDED: &[u8] = b"POST /form HTTP/1.1\r\nHost : example.com\r\nX-Long: first\r\n  second\r\nContent-Length: 3\r\n\r\nabcdef";
+
+    #[test]
+    fn lenient_options_recover_headers_and_body() {
+        let request = parse_leniently(FOLDED, &ParserOptions::default());
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.target, "/form");
+        assert_eq!(
+            request.headers,
+            [
+                ("host".to_string(), "example.com".to_string()),
+                ("x-long".to_string(), "first second".to_string()),
+                ("content-length".to_string(), "3".to_string()),
+            ]
+        );
+        assert_eq!(request.body, b"abc");
+    }
+
+    #[test]
+    fn strict_options_keep_only_the_request_line() {
+        let options = ParserOptions {
+            allow_spaces_in_header_names: false,
+            allow_obsolete_line_folding: false,
+            ..ParserOptions::default()
+        };
+        let request = parse_leniently(FOLDED, &options);
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.target, "/form");
+        assert!(request.headers.is_empty());
+        assert!(request.body.is_empty());
+    }
+
+    #[test]
+    fn bare_line_feeds_and_missing_heads_are_tolerated() {
+        let request = parse_leniently(
+            b"\nGET /a HTTP/1.1\nAccept: */*\n\nbody",
+            &ParserOptions::default(),
+        );
+        assert_eq!(request.target, "/a");
+        assert_eq!(request.headers, [("accept".to_string(), "*/*".to_string())]);
+        assert_eq!(request.body, b"body");
+
+        let request = parse_leniently(b"GET /partial", &ParserOptions::default());
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.target, "/partial");
+        assert!(request.body.is_empty());
+    }
+}
@@ -0,0 +1,990 @@
+use crate::content_view;
+use eframe::egui;
+use std::collections::HashMap;
+use std::io::Cursor;
+
+/// Kinds of uploaded media that get a preview instead of a text view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MediaKind {
+    Image,
+    Pdf,
+    Audio,
+    Video,
+}
+
+/// How an image is shown: scaled to fit the panel or at a fixed zoom.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ImageView {
+    fit: bool,
+    zoom: f32,
+}
+
+impl Default for ImageView {
+    fn default() -> Self {
+        ImageView {
+            fit: true,
+            zoom: 1.0,
+        }
+    }
+}
+
+/// Picks the media kind from the content type, falling back to sniffing
+/// the data. SVG is left to the XML view.
+pub fn detect(content_type: &str, data: &[u8]) -> Option<MediaKind> {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+
+    if mime == "image/svg+xml" {
+        return None;
+    }
+    if mime.starts_with("image/") {
+        return Some(MediaKind::Image);
+    }
+    if mime == "application/pdf" {
+        return Some(MediaKind::Pdf);
+    }
+    if mime.starts_with("audio/") {
+        return Some(MediaKind::Audio);
+    }
+    if mime.starts_with("video/") {
+        return Some(MediaKind::Video);
+    }
+
+    if image::guess_format(data).is_ok() {
+        Some(MediaKind::Image)
+    } else if data.starts_with(b"%PDF-") {
+        Some(MediaKind::Pdf)
+    } else {
+        av_info(data).map(|info| {
+            if info.video {
+                MediaKind::Video
+            } else {
+                MediaKind::Audio
+            }
+        })
+    }
+}
+
+/// Metadata rows shown for PDFs and audio/video. Reading them can mean
+/// parsing the whole file, so callers keep the result across frames.
+pub fn metadata(kind: MediaKind, data: &[u8]) -> Result<Vec<(String, String)>, String> {
+    match kind {
+        // Images show their header next to the preview instead
+        MediaKind::Image => Ok(Vec::new()),
+        MediaKind::Pdf => pdf_info(data).map_err(|e| format!("Could not read PDF: {}", e)),
+        MediaKind::Audio | MediaKind::Video => match av_info(data) {
+            Some(info) => {
+                let mut rows = vec![("Format".to_string(), info.format.to_string())];
+                rows.extend(info.details);
+                Ok(rows)
+            }
+            None => Err("Unrecognized media container".to_string()),
+        },
+    }
+}
+
+/// Renders a preview of the media with its metadata, as read by [`metadata`].
+pub fn render_media(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    kind: MediaKind,
+    data: &[u8],
+    metadata: &Result<Vec<(String, String)>, String>,
+) {
+    match (kind, metadata) {
+        (MediaKind::Image, _) => render_image(ui, id, data),
+        (_, Ok(rows)) => render_metadata(ui, id, rows),
+        (_, Err(e)) => {
+            render_warning(ui, e);
+            content_view::render_hex(ui, data);
+        }
+    }
+}
+
+/// Shows an image with its format and dimensions and zoom controls.
+pub fn render_image(ui: &mut egui::Ui, id: egui::Id, data: &[u8]) {
+    let view_id = id.with("image_view");
+    let mut view: ImageView = ui.data(|d| d.get_temp(view_id)).unwrap_or_default();
+
+    ui.horizontal(|ui| {
+        match image_info(data) {
+            Ok((format, width, height)) => {
+                ui.label(
+                    egui::RichText::new(format!("{} · {} × {} px", format, width, height))
+                        .monospace(),
+                );
+            }
+            Err(e) => render_warning(ui, &format!("Could not read image header: {}", e)),
+        }
+        ui.separator();
+        ui.checkbox(&mut view.fit, "Fit");
+        ui.add_enabled_ui(!view.fit, |ui| {
+            if ui.small_button("−").clicked() {
+                view.zoom = (view.zoom / 1.25).max(0.1);
+            }
+            ui.add(
+                egui::Slider::new(&mut view.zoom, 0.1..=8.0)
+                    .logarithmic(true)
+                    .custom_formatter(|zoom, _| format!("{:.0}%", zoom * 100.0))
+                    .custom_parser(|text| {
+                        text.trim_end_matches('%')
+                            .trim()
+                            .parse::<f64>()
+                            .ok()
+                            .map(|percent| percent / 100.0)
+                    }),
+            );
+            if ui.small_button("+").clicked() {
+                view.zoom = (view.zoom * 1.25).min(8.0);
+            }
+            if ui.small_button("1:1").clicked() {
+                view.zoom = 1.0;
+            }
+        });
+    });
+    ui.data_mut(|d| d.insert_temp(view_id, view));
+    ui.add_space(5.0);
+
+    let uri = image_uri(ui, id, data);
+    egui::Frame::new()
+        .fill(egui::Color32::from_gray(30))
+        .inner_margin(10.0)
+        .show(ui, |ui| {
+            if view.fit {
+                ui.add(
+                    egui::Image::new(uri)
+                        .max_width(ui.available_width())
+                        .max_height(400.0)
+                        .fit_to_original_size(1.0),
+                );
+            } else {
+                egui::ScrollArea::both()
+                    .id_salt(id.with("image_scroll"))
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        ui.add(egui::Image::new(uri).fit_to_original_size(view.zoom));
+                    });
+            }
+        });
+}
+
+/// URIs of the images handed to egui, with the frame each was last shown in.
+#[derive(Clone, Default)]
+struct IncludedImages(HashMap<String, u64>);
+
+/// Hands the image bytes to egui once per `id` and length; later frames load
+/// them from its cache by URI.
+fn image_uri(ui: &egui::Ui, id: egui::Id, data: &[u8]) -> String {
+    let uri = format!("bytes://inspector-http/{:?}/{}", id, data.len());
+    let frame = ui.ctx().cumulative_frame_nr();
+    let included = ui.ctx().data_mut(|d| {
+        d.get_temp_mut_or_default::<IncludedImages>(egui::Id::new("included_images"))
+            .0
+            .insert(uri.clone(), frame)
+            .is_some()
+    });
+    if !included {
+        ui.ctx().include_bytes(uri.clone(), data.to_vec());
+    }
+    uri
+}
+
+/// Releases images that were not shown in the previous frame, e.g. after
+/// another request was selected or evicted. Call once per frame.
+pub fn forget_hidden_images(ctx: &egui::Context) {
+    let frame = ctx.cumulative_frame_nr();
+    let mut hidden = Vec::new();
+    ctx.data_mut(|d| {
+        d.get_temp_mut_or_default::<IncludedImages>(egui::Id::new("included_images"))
+            .0
+            .retain(|uri, &mut shown| {
+                let keep = shown + 1 >= frame;
+                if !keep {
+                    hidden.push(uri.clone());
+                }
+                keep
+            });
+    });
+    for uri in hidden {
+        ctx.forget_image(&uri);
+    }
+}
+
+/// Format name and pixel dimensions, read from the image header.
+fn image_info(data: &[u8]) -> Result<(String, u32, u32), String> {
+    let reader = image::ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?;
+    let format = reader
+        .format()
+        .and_then(|f| f.extensions_str().first())
+        .map(|ext| ext.to_ascii_uppercase())
+        .ok_or("unknown format")?;
+    let (width, height) = reader.into_dimensions().map_err(|e| e.to_string())?;
+    Ok((format, width, height))
+}
+
+fn render_metadata(ui: &mut egui::Ui, id: egui::Id, rows: &[(String, String)]) {
+    egui::Grid::new(id.with("media_metadata"))
+        .striped(true)
+        .spacing([10.0, 5.0])
+        .show(ui, |ui| {
+            for (name, value) in rows {
+                ui.label(egui::RichText::new(name).strong());
+                ui.label(egui::RichText::new(value).monospace());
+                ui.end_row();
+            }
+        });
+}
+
+fn render_warning(ui: &mut egui::Ui, text: &str) {
+    ui.label(
+        egui::RichText::new(format!("⚠ {}", text))
+            .small()
+            .color(egui::Color32::YELLOW),
+    );
+}
+
+/// Version, page count, document info and the first page's size.
+fn pdf_info(data: &[u8]) -> Result<Vec<(String, String)>, String> {
+    let doc = lopdf::Document::load_mem(data).map_err(|e| e.to_string())?;
+    let pages = doc.get_pages();
+    let mut rows = vec![
+        ("Version".to_string(), format!("PDF {}", doc.version)),
+        ("Pages".to_string(), pages.len().to_string()),
+    ];
+    if doc.is_encrypted() {
+        rows.push(("Encrypted".to_string(), "yes".to_string()));
+    }
+
+    if let Ok(info) = doc
+        .trailer
+        .get_deref(b"Info", &doc)
+        .and_then(|info| info.as_dict())
+    {
+        for key in [
+            "Title",
+            "Author",
+            "Subject",
+            "Keywords",
+            "Creator",
+            "Producer",
+            "CreationDate",
+            "ModDate",
+        ] {
+            if let Ok(value) = info
+                .get_deref(key.as_bytes(), &doc)
+                .and_then(lopdf::decode_text_string)
+                && !value.trim().is_empty()
+            {
+                rows.push((key.to_string(), value));
+            }
+        }
+    }
+
+    if let Some(&page_id) = pages.values().next() {
+        if let Some([x0, y0, x1, y1]) = page_box(&doc, page_id, b"MediaBox") {
+            let (width, height) = ((x1 - x0).abs(), (y1 - y0).abs());
+            rows.push((
+                "First page".to_string(),
+                format!(
+                    "{:.0} × {:.0} pt ({:.0} × {:.0} mm)",
+                    width,
+                    height,
+                    width / 72.0 * 25.4,
+                    height / 72.0 * 25.4
+                ),
+            ));
+        }
+        if let Some(rotate) = page_attribute(&doc, page_id, b"Rotate")
+            .and_then(|rotate| rotate.as_i64().ok())
+            .filter(|rotate| *rotate != 0)
+        {
+            rows.push(("Rotation".to_string(), format!("{}°", rotate)));
+        }
+        if let Ok(fonts) = doc.get_page_fonts(page_id)
+            && !fonts.is_empty()
+        {
+            let names: Vec<String> = fonts
+                .values()
+                .filter_map(|font| font.get(b"BaseFont").and_then(|f| f.as_name()).ok())
+                .map(|name| String::from_utf8_lossy(name).into_owned())
+                .collect();
+            rows.push(("First page fonts".to_string(), names.join(", ")));
+        }
+    }
+    Ok(rows)
+}
+
+/// Looks up a page attribute, following the inheritance from parent page
+/// tree nodes.
+fn page_attribute<'a>(
+    doc: &'a lopdf::Document,
+    page_id: lopdf::ObjectId,
+    key: &[u8],
+) -> Option<&'a lopdf::Object> {
+    let mut node = doc.get_dictionary(page_id).ok()?;
+    // Bounded in case of a cyclic page tree
+    for _ in 0..32 {
+        if let Ok(value) = node.get_deref(key, doc) {
+            return Some(value);
+        }
+        node = node.get_deref(b"Parent", doc).ok()?.as_dict().ok()?;
+    }
+    None
+}
+
+fn page_box(doc: &lopdf::Document, page_id: lopdf::ObjectId, key: &[u8]) -> Option<[f32; 4]> {
+    let values = page_attribute(doc, page_id, key)?.as_array().ok()?;
+    let mut rect = [0.0; 4];
+    for (slot, value) in rect.iter_mut().zip(values) {
+        *slot = doc.dereference(value).ok()?.1.as_float().ok()?;
+    }
+    (values.len() == 4).then_some(rect)
+}
+
+/// Container format and basic stream properties of an audio or video file.
+struct AvInfo {
+    format: &'static str,
+    video: bool,
+    details: Vec<(String, String)>,
+}
+
+impl AvInfo {
+    fn new(format: &'static str, video: bool) -> Self {
+        AvInfo {
+            format,
+            video,
+            details: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, name: &str, value: impl ToString) {
+        self.details.push((name.to_string(), value.to_string()));
+    }
+
+    fn push_duration(&mut self, seconds: f64) {
+        if seconds.is_finite() && seconds > 0.0 {
+            self.push("Duration", format_duration(seconds));
+        }
+    }
+}
+
+/// Sniffs the container from its magic bytes and reads what its headers
+/// say about the streams.
+fn av_info(data: &[u8]) -> Option<AvInfo> {
+    if data.len() >= 12 && &data[0..4] == b"RIFF" {
+        return match &data[8..12] {
+            b"WAVE" => Some(wav_info(data)),
+            b"AVI " => Some(AvInfo::new("AVI", true)),
+            _ => None,
+        };
+    }
+    if data.starts_with(b"fLaC") {
+        return Some(flac_info(data));
+    }
+    if data.starts_with(b"OggS") {
+        return ogg_info(data);
+    }
+    if data.get(4..8) == Some(b"ftyp") {
+        return Some(mp4_info(data));
+    }
+    if data.starts_with(&[0x1a, 0x45, 0xdf, 0xa3]) {
+        return Some(matroska_info(data));
+    }
+    mp3_info(data)
+}
+
+fn format_duration(seconds: f64) -> String {
+    let whole = seconds as u64;
+    format!(
+        "{}:{:02}:{:06.3}",
+        whole / 3600,
+        whole / 60 % 60,
+        seconds - (whole / 60 * 60) as f64
+    )
+}
+
+fn u16_le(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_le(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u32_be(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u64_be(data: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(data.get(at..at + 8)?.try_into().ok()?))
+}
+
+fn wav_info(data: &[u8]) -> AvInfo {
+    let mut info = AvInfo::new("WAV", false);
+    let mut byte_rate = 0;
+    let mut at = 12;
+    while let Some(size) = u32_le(data, at + 4) {
+        let body = at + 8;
+        match &data[at..at + 4] {
+            b"fmt " => {
+                let codec = match u16_le(data, body).unwrap_or(0) {
+                    1 => "PCM",
+                    3 => "IEEE float",
+                    6 => "A-law",
+                    7 => "µ-law",
+                    0x55 => "MP3",
+                    0xfffe => "Extensible",
+                    _ => "Other",
+                };
+                info.push("Codec", codec);
+                if let Some(channels) = u16_le(data, body + 2) {
+                    info.push("Channels", channels);
+                }
+                if let Some(rate) = u32_le(data, body + 4) {
+                    info.push("Sample rate", format!("{} Hz", rate));
+                }
+                byte_rate = u32_le(data, body + 8).unwrap_or(0);
+                if let Some(bits) = u16_le(data, body + 14) {
+                    info.push("Bits per sample", bits);
+                }
+            }
+            b"data" if byte_rate > 0 => {
+                info.push_duration(size as f64 / byte_rate as f64);
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even size
+        let Some(next) = body
+            .checked_add(size as usize)
+            .and_then(|end| end.checked_add(size as usize & 1))
+        else {
+            break;
+        };
+        at = next.min(data.len());
+    }
+    info
+}
+
+fn flac_info(data: &[u8]) -> AvInfo {
+    let mut info = AvInfo::new("FLAC", false);
+    // The first metadata block is STREAMINFO, starting after its 4-byte header
+    if let Some(stream_info) = data.get(8..8 + 18) {
+        let packed = u64::from_be_bytes(stream_info[10..18].try_into().unwrap());
+        let rate = packed >> 44;
+        let channels = (packed >> 41 & 0x7) + 1;
+        let bits = (packed >> 36 & 0x1f) + 1;
+        let samples = packed & 0xf_ffff_ffff;
+        info.push("Channels", channels);
+        info.push("Sample rate", format!("{} Hz", rate));
+        info.push("Bits per sample", bits);
+        if rate > 0 {
+            info.push_duration(samples as f64 / rate as f64);
+        }
+    }
+    info
+}
+
+fn ogg_info(data: &[u8]) -> Option<AvInfo> {
+    let segments = *data.get(26)? as usize;
+    let packet = data.get(27 + segments..)?;
+    let (mut info, rate, pre_skip) = if packet.starts_with(b"\x01vorbis") {
+        let mut info = AvInfo::new("Ogg Vorbis", false);
+        let rate = u32_le(packet, 12)?;
+        info.push("Channels", packet.get(11)?);
+        info.push("Sample rate", format!("{} Hz", rate));
+        (info, rate, 0)
+    } else if packet.starts_with(b"OpusHead") {
+        let mut info = AvInfo::new("Ogg Opus", false);
+        info.push("Channels", packet.get(9)?);
+        info.push("Input sample rate", format!("{} Hz", u32_le(packet, 12)?));
+        // Opus granule positions always count 48 kHz samples
+        (info, 48_000, u16_le(packet, 10)?)
+    } else if packet.starts_with(b"\x7fFLAC") {
+        (AvInfo::new("Ogg FLAC", false), 0, 0)
+    } else if packet.starts_with(b"\x80theora") {
+        (AvInfo::new("Ogg Theora", true), 0, 0)
+    } else {
+        (AvInfo::new("Ogg", false), 0, 0)
+    };
+
+    // The last page's granule position is the total sample count
+    if rate > 0
+        && let Some(last) = data.windows(4).rposition(|w| w == b"OggS")
+        && let Some(granule) = data.get(last + 6..last + 14)
+    {
+        let granule = u64::from_le_bytes(granule.try_into().unwrap());
+        info.push_duration(granule.saturating_sub(pre_skip as u64) as f64 / rate as f64);
+    }
+    Some(info)
+}
+
+fn mp4_info(data: &[u8]) -> AvInfo {
+    let brand = data
+        .get(8..12)
+        .map(|b| String::from_utf8_lossy(b).trim().to_string())
+        .unwrap_or_default();
+    let (format, audio_brand) = match brand.as_str() {
+        "qt" => ("QuickTime", false),
+        "M4A" | "M4B" | "M4P" => ("MPEG-4 audio", true),
+        b if b.starts_with("3g") => ("3GPP", false),
+        _ => ("MPEG-4", false),
+    };
+    let mut info = AvInfo::new(format, !audio_brand);
+    info.push("Brand", &brand);
+
+    let mut has_video = false;
+    for (kind, body) in mp4_boxes(data) {
+        if kind != *b"moov" {
+            continue;
+        }
+        for (kind, body) in mp4_boxes(body) {
+            match &kind {
+                b"mvhd" => {
+                    let (timescale, duration) = if body.first() == Some(&1) {
+                        (u32_be(body, 20), u64_be(body, 24))
+                    } else {
+                        (u32_be(body, 12), u32_be(body, 16).map(u64::from))
+                    };
+                    if let (Some(timescale), Some(duration)) = (timescale, duration)
+                        && timescale > 0
+                    {
+                        info.push_duration(duration as f64 / timescale as f64);
+                    }
+                }
+                b"trak" => has_video |= mp4_track(body, &mut info),
+                _ => {}
+            }
+        }
+    }
+    if !audio_brand {
+        info.video = has_video;
+    }
+    info
+}
+
+/// Adds a track's handler, codec and size. Returns true for video tracks.
+fn mp4_track(trak: &[u8], info: &mut AvInfo) -> bool {
+    let mut size = None;
+    let mut handler = String::new();
+    let mut codec = String::new();
+    for (kind, body) in mp4_boxes(trak) {
+        match &kind {
+            b"tkhd" => {
+                // Width and height are the last two 16.16 fixed-point fields
+                let end = body.len();
+                if end >= 8 {
+                    size = Some((
+                        u32_be(body, end - 8).unwrap_or(0) >> 16,
+                        u32_be(body, end - 4).unwrap_or(0) >> 16,
+                    ));
+                }
+            }
+            b"mdia" => {
+                for (kind, body) in mp4_boxes(body) {
+                    match &kind {
+                        b"hdlr" => {
+                            handler = body
+                                .get(8..12)
+                                .map(|h| String::from_utf8_lossy(h).into_owned())
+                                .unwrap_or_default();
+                        }
+                        b"minf" => {
+                            let stsd = mp4_boxes(body).find(|(kind, _)| kind == b"stbl").and_then(
+                                |(_, stbl)| mp4_boxes(stbl).find(|(kind, _)| kind == b"stsd"),
+                            );
+                            if let Some((_, stsd)) = stsd
+                                && let Some(format) = stsd.get(12..16)
+                            {
+                                codec = String::from_utf8_lossy(format).trim().to_string();
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match handler.as_str() {
+        "vide" => {
+            let mut value = codec;
+            if let Some((width, height)) = size {
+                value.push_str(&format!(" · {} × {}", width, height));
+            }
+            info.push("Video track", value);
+            true
+        }
+        "soun" => {
+            info.push("Audio track", codec);
+            false
+        }
+        _ => false,
+    }
+}
+
+/// Iterates the boxes in an ISO base media file as `(type, body)` pairs.
+fn mp4_boxes(data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    let mut at = 0;
+    std::iter::from_fn(move || {
+        let size = u32_be(data, at)? as usize;
+        let kind: [u8; 4] = data.get(at + 4..at + 8)?.try_into().ok()?;
+        let (header, size) = match size {
+            0 => (8, data.len() - at),
+            1 => (16, usize::try_from(u64_be(data, at + 8)?).ok()?),
+            size => (8, size),
+        };
+        if size < header {
+            return None;
+        }
+        // A truncated upload still yields the part of the box we have
+        let end = at.checked_add(size)?.min(data.len());
+        let body = data.get(at + header..end)?;
+        at = end;
+        Some((kind, body))
+    })
+}
+
+fn matroska_info(data: &[u8]) -> AvInfo {
+    const EBML: u32 = 0x1a45dfa3;
+    const DOC_TYPE: u32 = 0x4282;
+    const SEGMENT: u32 = 0x18538067;
+    const INFO: u32 = 0x1549a966;
+    const TIMESTAMP_SCALE: u32 = 0x2ad7b1;
+    const DURATION: u32 = 0x4489;
+    const TRACKS: u32 = 0x1654ae6b;
+    const TRACK_ENTRY: u32 = 0xae;
+    const CODEC_ID: u32 = 0x86;
+    const TRACK_TYPE: u32 = 0x83;
+    const VIDEO: u32 = 0xe0;
+    const AUDIO: u32 = 0xe1;
+    const PIXEL_WIDTH: u32 = 0xb0;
+    const PIXEL_HEIGHT: u32 = 0xba;
+    const SAMPLING_FREQUENCY: u32 = 0xb5;
+    const CHANNELS: u32 = 0x9f;
+
+    let mut info = AvInfo::new("Matroska", false);
+    let mut has_video = false;
+    for (id, body) in ebml_elements(data) {
+        match id {
+            EBML => {
+                if let Some((_, doc_type)) = ebml_elements(body).find(|(id, _)| *id == DOC_TYPE)
+                    && doc_type == b"webm"
+                {
+                    info.format = "WebM";
+                }
+            }
+            SEGMENT => {
+                for (id, body) in ebml_elements(body) {
+                    match id {
+                        INFO => {
+                            let mut scale = 1_000_000u64;
+                            let mut duration = None;
+                            for (id, body) in ebml_elements(body) {
+                                match id {
+                                    TIMESTAMP_SCALE => scale = ebml_uint(body),
+                                    DURATION => duration = ebml_float(body),
+                                    _ => {}
+                                }
+                            }
+                            if let Some(duration) = duration {
+                                info.push_duration(duration * scale as f64 / 1e9);
+                            }
+                        }
+                        TRACKS => {
+                            for (_, entry) in
+                                ebml_elements(body).filter(|(id, _)| *id == TRACK_ENTRY)
+                            {
+                                let mut codec = String::new();
+                                let mut track_type = 0;
+                                let mut details = String::new();
+                                for (id, body) in ebml_elements(entry) {
+                                    match id {
+                                        CODEC_ID => codec = String::from_utf8_lossy(body).into(),
+                                        TRACK_TYPE => track_type = ebml_uint(body),
+                                        VIDEO => {
+                                            let (mut width, mut height) = (0, 0);
+                                            for (id, body) in ebml_elements(body) {
+                                                match id {
+                                                    PIXEL_WIDTH => width = ebml_uint(body),
+                                                    PIXEL_HEIGHT => height = ebml_uint(body),
+                                                    _ => {}
+                                                }
+                                            }
+                                            details = format!(" · {} × {}", width, height);
+                                        }
+                                        AUDIO => {
+                                            let (mut rate, mut channels) = (8000.0, 1);
+                                            for (id, body) in ebml_elements(body) {
+                                                match id {
+                                                    SAMPLING_FREQUENCY => {
+                                                        rate = ebml_float(body).unwrap_or(rate)
+                                                    }
+                                                    CHANNELS => channels = ebml_uint(body),
+                                                    _ => {}
+                                                }
+                                            }
+                                            details = format!(" · {} Hz · {} ch", rate, channels);
+                                        }
+                                        _ => {}
+                                    }
+                                }
+                                match track_type {
+                                    1 => {
+                                        has_video = true;
+                                        info.push("Video track", codec + &details);
+                                    }
+                                    2 => info.push("Audio track", codec + &details),
+                                    _ => {}
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    info.video = has_video;
+    info
+}
+
+/// Reads an EBML variable-length integer. IDs keep their length marker bit,
+/// sizes drop it; an all-ones size means "unknown".
+fn ebml_vint(data: &[u8], at: usize, keep_marker: bool) -> Option<(u64, usize)> {
+    let first = *data.get(at)?;
+    let len = first.leading_zeros() as usize + 1;
+    if len > 8 {
+        return None;
+    }
+    let mut value = if keep_marker {
+        first as u64
+    } else {
+        (first as u64) & (0xff >> len)
+    };
+    for &byte in data.get(at + 1..at + len)? {
+        value = value << 8 | byte as u64;
+    }
+    Some((value, len))
+}
+
+/// Iterates EBML elements as `(id, body)` pairs. Elements of unknown size,
+/// as in live streams, extend to the end of the data.
+fn ebml_elements(data: &[u8]) -> impl Iterator<Item = (u32, &[u8])> {
+    let mut at = 0;
+    std::iter::from_fn(move || {
+        let (id, id_len) = ebml_vint(data, at, true)?;
+        let (size, size_len) = ebml_vint(data, at + id_len, false)?;
+        let start = at + id_len + size_len;
+        let unknown = size == (1u64 << (7 * size_len)) - 1;
+        let end = if unknown {
+            data.len()
+        } else {
+            start
+                .saturating_add(usize::try_from(size).ok()?)
+                .min(data.len())
+        };
+        let body = data.get(start..end)?;
+        at = end;
+        Some((id as u32, body))
+    })
+}
+
+fn ebml_uint(data: &[u8]) -> u64 {
+    data.iter()
+        .take(8)
+        .fold(0, |value, &byte| value << 8 | byte as u64)
+}
+
+fn ebml_float(data: &[u8]) -> Option<f64> {
+    match data.len() {
+        4 => Some(f32::from_be_bytes(data.try_into().ok()?) as f64),
+        8 => Some(f64::from_be_bytes(data.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+fn mp3_info(data: &[u8]) -> Option<AvInfo> {
+    let mut info = AvInfo::new("MP3", false);
+    let mut at = 0;
+    if data.starts_with(b"ID3") && data.len() >= 10 {
+        let size = data[6..10]
+            .iter()
+            .fold(0usize, |size, &byte| size << 7 | (byte & 0x7f) as usize);
+        id3_tags(data, &mut info);
+        at = 10 + size;
+    }
+
+    // The first MPEG audio frame header follows the tag, possibly after
+    // padding. Without a tag it must start the data, so arbitrary binary
+    // is not mistaken for MP3.
+    let is_frame =
+        |w: &[u8]| w[0] == 0xff && w[1] & 0xe0 == 0xe0 && w[1] & 0x06 != 0 && w[2] & 0xf0 != 0xf0;
+    let frame = if at > 0 {
+        data.get(at..)?.windows(4).position(is_frame)
+    } else {
+        data.get(..4).filter(|w| is_frame(w)).map(|_| 0)
+    };
+    let Some(offset) = frame else {
+        // A tag on its own is still recognizable
+        return (at > 0).then_some(info);
+    };
+    let header = &data[at + offset..at + offset + 4];
+    let version = match header[1] >> 3 & 0x3 {
+        0 => "MPEG-2.5",
+        2 => "MPEG-2",
+        3 => "MPEG-1",
+        _ => return None,
+    };
+    let layer = 4 - (header[1] >> 1 & 0x3);
+    let bitrate_index = (header[2] >> 4) as usize;
+    let bitrates: [u32; 16] = match (version, layer) {
+        ("MPEG-1", 1) => [
+            0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448, 0,
+        ],
+        ("MPEG-1", 2) => [
+            0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384, 0,
+        ],
+        ("MPEG-1", _) => [
+            0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 0,
+        ],
+        (_, 1) => [
+            0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256, 0,
+        ],
+        _ => [
+            0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0,
+        ],
+    };
+    let base_rate = [44_100, 48_000, 32_000, 0][(header[2] >> 2 & 0x3) as usize];
+    let rate = match version {
+        "MPEG-1" => base_rate,
+        "MPEG-2" => base_rate / 2,
+        _ => base_rate / 4,
+    };
+    let channels = if header[3] >> 6 == 3 {
+        "mono"
+    } else {
+        "stereo"
+    };
+    let bitrate = bitrates[bitrate_index];
+
+    if layer != 3 {
+        info.format = "MPEG audio";
+    }
+    info.push(
+        "Codec",
+        format!("{} Layer {}", version, "I".repeat(layer as usize)),
+    );
+    info.push("Sample rate", format!("{} Hz", rate));
+    info.push("Channels", channels);
+    if bitrate > 0 {
+        info.push("Bitrate", format!("{} kbit/s", bitrate));
+        // Exact for constant bitrate files, an estimate otherwise
+        let audio_bytes = data.len() - at - offset;
+        info.push_duration(audio_bytes as f64 * 8.0 / (bitrate as f64 * 1000.0));
+    }
+    Some(info)
+}
+
+/// Adds title, artist and album from an ID3v2.3 or v2.4 tag.
+fn id3_tags(data: &[u8], info: &mut AvInfo) {
+    let version = data[3];
+    if !(3..=4).contains(&version) {
+        return;
+    }
+    let tag_end = 10
+        + data[6..10]
+            .iter()
+            .fold(0usize, |size, &byte| size << 7 | (byte & 0x7f) as usize);
+    let mut at = 10;
+    while at + 10 <= tag_end.min(data.len()) {
+        let id = &data[at..at + 4];
+        if id[0] == 0 {
+            break;
+        }
+        let size = if version == 4 {
+            data[at + 4..at + 8]
+                .iter()
+                .fold(0usize, |size, &byte| size << 7 | (byte & 0x7f) as usize)
+        } else {
+            u32_be(data, at + 4).unwrap_or(0) as usize
+        };
+        let Some(body) = data.get(at + 10..at + 10 + size) else {
+            break;
+        };
+        let label = match id {
+            b"TIT2" => Some("Title"),
+            b"TPE1" => Some("Artist"),
+            b"TALB" => Some("Album"),
+            _ => None,
+        };
+        if let Some(label) = label
+            && let Some(text) = id3_text(body)
+        {
+            info.push(label, text);
+        }
+        at += 10 + size;
+    }
+}
+
+fn id3_text(body: &[u8]) -> Option<String> {
+    let (&encoding, text) = body.split_first()?;
+    let text = match encoding {
+        0 => text.iter().map(|&b| b as char).collect(),
+        1 | 2 => {
+            let big_endian = encoding == 2 || text.starts_with(&[0xfe, 0xff]);
+            let text = text
+                .strip_prefix(&[0xfe, 0xff])
+                .or_else(|| text.strip_prefix(&[0xff, 0xfe]))
+                .unwrap_or(text);
+            let units: Vec<u16> = text
+                .chunks_exact(2)
+                .map(|pair| {
+                    let pair = [pair[0], pair[1]];
+                    if big_endian {
+                        u16::from_be_bytes(pair)
+                    } else {
+                        u16::from_le_bytes(pair)
+                    }
+                })
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(text).into_owned(),
+    };
+    let text = text.trim_end_matches('\0').to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oversized_mp4_boxes_end_the_walk() {
+        let mut data = vec![0, 0, 0, 8];
+        data.extend(b"free");
+        data.extend([0, 0, 0, 1]);
+        data.extend(b"mdat");
+        data.extend(u64::MAX.to_be_bytes());
+        let boxes: Vec<_> = mp4_boxes(&data).map(|(kind, _)| kind).collect();
+        assert_eq!(boxes, [*b"free"]);
+    }
+
+    #[test]
+    fn oversized_wav_chunks_end_the_walk() {
+        let mut data = b"RIFF\0\0\0\0WAVE".to_vec();
+        data.extend(b"data");
+        data.extend(u32::MAX.to_le_bytes());
+        data.extend([0; 16]);
+        let info = wav_info(&data);
+        assert!(info.details.is_empty());
+    }
+}
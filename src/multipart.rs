@@ -0,0 +1,333 @@
+use crate::content_view::{self, ContentView};
+use eframe::egui;
+use std::ops::Range;
+
+/// One part of a `multipart/*` body.
+pub struct Part {
+    pub headers: Vec<(String, String)>,
+    pub name: Option<String>,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    /// Where the part's data lies in the body.
+    pub range: Range<usize>,
+}
+
+impl Part {
+    pub fn data<'a>(&self, body: &'a [u8]) -> &'a [u8] {
+        &body[self.range.clone()]
+    }
+}
+
+/// Extracts the boundary parameter from a `multipart/*` content type.
+pub fn boundary(content_type: &str) -> Option<String> {
+    let mut params = content_type.split(';');
+    let mime = params.next()?.trim();
+    if !mime.to_ascii_lowercase().starts_with("multipart/") {
+        return None;
+    }
+    params
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+        .filter(|boundary| !boundary.is_empty())
+}
+
+/// Splits a multipart body into parts. Returns `None` if the opening
+/// delimiter is missing.
+pub fn parse(body: &[u8], boundary: &str) -> Option<Vec<Part>> {
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let mut pos = find_delimiter(body, &delimiter, 0)? + delimiter.len();
+    let mut parts = Vec::new();
+
+    loop {
+        // Closing delimiter
+        if body[pos..].starts_with(b"--") {
+            break;
+        }
+        pos = skip_line(body, pos)?;
+
+        // Part headers end at the first empty line
+        let mut headers = Vec::new();
+        loop {
+            let line_end = skip_line(body, pos)?;
+            let line = trim_line_ending(&body[pos..line_end]);
+            pos = line_end;
+            if line.is_empty() {
+                break;
+            }
+            let line = String::from_utf8_lossy(line);
+            if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_string(), value.trim().to_string()));
+            }
+        }
+
+        // Part data runs up to the line ending before the next delimiter
+        let next = find_delimiter(body, &delimiter, pos);
+        let data_end = next.unwrap_or(body.len());
+        let mut data = &body[pos..data_end];
+        if next.is_some() {
+            data = data
+                .strip_suffix(b"\r\n")
+                .or_else(|| data.strip_suffix(b"\n"))
+                .unwrap_or(data);
+        }
+        let range = pos..pos + data.len();
+
+        let disposition = header(&headers, "content-disposition").unwrap_or("");
+        parts.push(Part {
+            name: disposition_param(disposition, "name"),
+            filename: disposition_param(disposition, "filename"),
+            content_type: header(&headers, "content-type").map(str::to_string),
+            headers,
+            range,
+        });
+
+        match next {
+            Some(next) => pos = next + delimiter.len(),
+            None => break,
+        }
+    }
+
+    Some(parts)
+}
+
+pub fn render_multipart(ui: &mut egui::Ui, request_id: u64, body: &[u8], parts: &[Part]) {
+    ui.label(egui::RichText::new(format!("Parts ({})", parts.len())).strong());
+    ui.add_space(5.0);
+
+    egui::Grid::new("multipart_parts_grid")
+        .striped(true)
+        .spacing([10.0, 5.0])
+        .show(ui, |ui| {
+            for title in ["#", "Field", "Filename", "Content-Type", "Size", ""] {
+                ui.label(egui::RichText::new(title).strong());
+            }
+            ui.end_row();
+
+            for (index, part) in parts.iter().enumerate() {
+                ui.label((index + 1).to_string());
+                ui.label(egui::RichText::new(part.name.as_deref().unwrap_or("-")).monospace());
+                ui.label(egui::RichText::new(part.filename.as_deref().unwrap_or("-")).monospace());
+                ui.label(part.content_type.as_deref().unwrap_or("-"));
+                ui.label(format!("{} bytes", part.range.len()));
+                if ui.button("💾 Save").clicked() {
+                    content_view::save_to_file(
+                        part_filename(part, index),
+                        part.data(body).to_vec(),
+                    );
+                }
+                ui.end_row();
+            }
+        });
+
+    ui.add_space(10.0);
+
+    for (index, part) in parts.iter().enumerate() {
+        let id = egui::Id::new(("multipart_part", request_id, index));
+        let title = format!(
+            "#{} {}{}",
+            index + 1,
+            part.name.as_deref().unwrap_or("(unnamed)"),
+            part.filename
+                .as_deref()
+                .map(|f| format!(" — {}", f))
+                .unwrap_or_default()
+        );
+
+        egui::CollapsingHeader::new(title)
+            .id_salt(id)
+            .default_open(parts.len() <= 3)
+            .show(ui, |ui| {
+                egui::Grid::new(id.with("headers"))
+                    .striped(true)
+                    .spacing([10.0, 5.0])
+                    .show(ui, |ui| {
+                        for (name, value) in &part.headers {
+                            ui.label(egui::RichText::new(name).strong());
+                            ui.label(egui::RichText::new(value).monospace());
+                            ui.end_row();
+                        }
+                    });
+                ui.add_space(5.0);
+
+                let data = part.data(body);
+                let content_type = part.content_type.as_deref().unwrap_or("");
+                let detected = ContentView::detect(content_type, data);
+                let view_id = id.with("view");
+                let mut view = ui.data(|d| d.get_temp(view_id)).unwrap_or(detected);
+                ui.horizontal(|ui| {
+                    for option in ContentView::ALL {
+                        ui.selectable_value(&mut view, option, option.label());
+                    }
+                });
+                ui.data_mut(|d| d.insert_temp(view_id, view));
+
+                if data.is_empty() {
+                    ui.label(egui::RichText::new("(empty)").italics().weak());
+                } else {
+                    content_view::render_content(ui, id, view, content_type, data);
+                }
+            });
+    }
+}
+
+fn part_filename(part: &Part, index: usize) -> String {
+    part.filename
+        .as_deref()
+        .and_then(|f| f.rsplit(['/', '\\']).next())
+        .filter(|f| !f.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            // Parts without a content type are plain text (RFC 7578)
+            let extension = match &part.content_type {
+                Some(content_type) => content_view::file_extension(content_type).unwrap_or("bin"),
+                None => "txt",
+            };
+            format!(
+                "{}.{}",
+                part.name
+                    .as_deref()
+                    .unwrap_or(&format!("part{}", index + 1)),
+                extension
+            )
+        })
+}
+
+fn header<'h>(headers: &'h [(String, String)], name: &str) -> Option<&'h str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Reads a parameter from a Content-Disposition value, preferring the
+/// RFC 5987 `name*=UTF-8''...` form.
+fn disposition_param(disposition: &str, key: &str) -> Option<String> {
+    let params = split_params(disposition);
+    let extended = format!("{}*", key);
+    if let Some((_, value)) = params
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(&extended))
+        && let Some((_, encoded)) = value.split_once("''")
+    {
+        return urlencoding::decode(encoded).ok().map(|v| v.into_owned());
+    }
+    params
+        .into_iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, value)| value)
+}
+
+/// Splits `form-data; name="a;b"; filename=x` into key/value pairs,
+/// honouring quoted strings.
+fn split_params(value: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut chars = value.chars().peekable();
+
+    // Skip the disposition type
+    for c in chars.by_ref() {
+        if c == ';' {
+            break;
+        }
+    }
+
+    loop {
+        let key: String = chars
+            .by_ref()
+            .take_while(|&c| c != '=')
+            .collect::<String>()
+            .trim()
+            .to_string();
+        if key.is_empty() {
+            break;
+        }
+
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        let mut val = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => val.extend(chars.next()),
+                    '"' => break,
+                    c => val.push(c),
+                }
+            }
+            // Skip to the next parameter
+            for c in chars.by_ref() {
+                if c == ';' {
+                    break;
+                }
+            }
+        } else {
+            val = chars
+                .by_ref()
+                .take_while(|&c| c != ';')
+                .collect::<String>()
+                .trim()
+                .to_string();
+        }
+        params.push((key, val));
+    }
+    params
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|pos| pos + from)
+}
+
+/// Position of the next delimiter at the start of a line, so `--boundary`
+/// inside part data is not taken for one (RFC 2046). Bare `\n` line endings
+/// are accepted as well as CRLF.
+fn find_delimiter(body: &[u8], delimiter: &[u8], mut from: usize) -> Option<usize> {
+    loop {
+        let pos = find(body, delimiter, from)?;
+        if pos == 0 || body[pos - 1] == b'\n' {
+            return Some(pos);
+        }
+        from = pos + 1;
+    }
+}
+
+/// Position just past the next `\n`.
+fn skip_line(body: &[u8], pos: usize) -> Option<usize> {
+    body.get(pos..)?
+        .iter()
+        .position(|&b| b == b'\n')
+        .map(|i| pos + i + 1)
+}
+
+fn trim_line_ending(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delimiters_inside_part_data_are_ignored() {
+        let body = b"--b\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nx--b y\r\n--b\r\n\r\nz\r\n--b--\r\n";
+        let parts = parse(body, "b").unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].name.as_deref(), Some("a"));
+        assert_eq!(parts[0].data(body), b"x--b y");
+        assert_eq!(parts[1].data(body), b"z");
+    }
+
+    #[test]
+    fn part_filenames_follow_the_content_type() {
+        let body = b"--b\r\nContent-Disposition: form-data; name=\"photo\"\r\nContent-Type: image/png\r\n\r\nx\r\n--b\r\nContent-Disposition: form-data; name=\"note\"\r\n\r\ny\r\n--b\r\nContent-Disposition: form-data; name=\"f\"; filename=\"C:\\\\dir\\\\a.csv\"\r\n\r\nz\r\n--b--\r\n";
+        let parts = parse(body, "b").unwrap();
+        assert_eq!(part_filename(&parts[0], 0), "photo.png");
+        assert_eq!(part_filename(&parts[1], 1), "note.txt");
+        assert_eq!(part_filename(&parts[2], 2), "a.csv");
+    }
+}
@@ -0,0 +1,446 @@
+use crate::content_view;
+use eframe::egui;
+use egui_json_tree::JsonTree;
+use prost_reflect::{DescriptorPool, DynamicMessage, SerializeOptions};
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Nesting depth up to which length-delimited fields are tried as messages
+/// and groups are decoded.
+const MAX_NESTING: usize = 32;
+
+/// Message types loaded from `.proto` files or descriptor sets.
+#[derive(Default)]
+pub struct ProtoSchemas {
+    pub pool: DescriptorPool,
+    /// Files or descriptor sets that were loaded, for display.
+    pub sources: Vec<String>,
+    pub error: Option<String>,
+    /// Changes whenever types are loaded or cleared, so decoded bodies are
+    /// decoded again.
+    pub revision: u64,
+}
+
+impl ProtoSchemas {
+    /// Adds an encoded `FileDescriptorSet` to the pool. The pool is left
+    /// untouched if the set conflicts with what is already loaded.
+    fn add(&mut self, source: String, encoded: Result<Vec<u8>, String>) {
+        let mut pool = self.pool.clone();
+        match encoded.and_then(|bytes| {
+            pool.decode_file_descriptor_set(bytes.as_slice())
+                .map_err(|e| e.to_string())
+        }) {
+            Ok(()) => {
+                self.pool = pool;
+                self.sources.push(source);
+                self.error = None;
+                self.revision += 1;
+            }
+            Err(e) => self.error = Some(format!("{}: {}", source, e)),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self {
+            revision: self.revision + 1,
+            ..Self::default()
+        };
+    }
+
+    /// Fully qualified names of all loaded message types, sorted.
+    pub fn message_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .pool
+            .all_messages()
+            .map(|m| m.full_name().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+/// Asks for `.proto` files and compiles them into the schema pool. Each
+/// file's directory is used as an include path for its imports.
+pub fn load_proto_files(ctx: egui::Context, schemas: Arc<Mutex<ProtoSchemas>>) {
+    std::thread::spawn(move || {
+        let Some(paths) = rfd::FileDialog::new()
+            .add_filter("Protocol Buffers", &["proto"])
+            .pick_files()
+        else {
+            return;
+        };
+
+        let source = paths
+            .iter()
+            .filter_map(|p| p.file_name())
+            .map(|name| name.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let encoded = compile_proto_files(&paths);
+        schemas.lock().unwrap().add(source, encoded);
+        ctx.request_repaint();
+    });
+}
+
+/// Asks for a binary `FileDescriptorSet`, e.g. from `protoc --descriptor_set_out`.
+pub fn load_descriptor_set(ctx: egui::Context, schemas: Arc<Mutex<ProtoSchemas>>) {
+    std::thread::spawn(move || {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Descriptor set", &["pb", "desc", "binpb", "protoset"])
+            .add_filter("All files", &["*"])
+            .pick_file()
+        else {
+            return;
+        };
+
+        let source = path.display().to_string();
+        let encoded = std::fs::read(&path).map_err(|e| e.to_string());
+        schemas.lock().unwrap().add(source, encoded);
+        ctx.request_repaint();
+    });
+}
+
+fn compile_proto_files(paths: &[PathBuf]) -> Result<Vec<u8>, String> {
+    let mut includes: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        if let Some(dir) = path.parent()
+            && !includes.iter().any(|include| include == dir)
+        {
+            includes.push(dir.to_path_buf());
+        }
+    }
+
+    let mut compiler = protox::Compiler::new(includes).map_err(|e| e.to_string())?;
+    compiler.include_imports(true);
+    compiler.open_files(paths).map_err(|e| e.to_string())?;
+    Ok(compiler.encode_file_descriptor_set())
+}
+
+/// Returns true for the protobuf media types clients commonly send.
+pub fn is_protobuf(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    mime.contains("protobuf")
+}
+
+/// Decodes a message with a schema into its canonical JSON mapping.
+pub fn decode_with_schema(
+    pool: &DescriptorPool,
+    message_type: &str,
+    data: &[u8],
+) -> Result<Value, String> {
+    let descriptor = pool
+        .get_message_by_name(message_type)
+        .ok_or_else(|| format!("Unknown message type {}", message_type))?;
+    let message = DynamicMessage::decode(descriptor, data).map_err(|e| e.to_string())?;
+    let options = SerializeOptions::new()
+        .stringify_64_bit_integers(false)
+        .skip_default_fields(false);
+    message
+        .serialize_with_options(serde_json::value::Serializer, &options)
+        .map_err(|e| e.to_string())
+}
+
+/// Decodes the wire format without a schema. Keys are `<field number> (<kind>)`;
+/// repeated fields become arrays. Length-delimited fields are shown as text
+/// when printable, as a nested message when they parse as one, and as hex
+/// otherwise.
+pub fn decode_wire(data: &[u8]) -> Result<Value, String> {
+    let mut input = data;
+    decode_fields(&mut input, 0, None).map(Value::Object)
+}
+
+fn decode_fields(
+    input: &mut &[u8],
+    depth: usize,
+    group: Option<u64>,
+) -> Result<Map<String, Value>, String> {
+    let mut fields = Map::new();
+    while !input.is_empty() {
+        let key = read_varint(input)?;
+        let number = key >> 3;
+        if number == 0 {
+            return Err("Field number 0 is invalid".to_string());
+        }
+
+        let (kind, value) = match key & 7 {
+            0 => ("varint", Value::from(read_varint(input)?)),
+            1 => {
+                let bits = u64::from_le_bytes(take(input, 8)?.try_into().unwrap());
+                fixed64_value(bits)
+            }
+            2 => {
+                let len = usize::try_from(read_varint(input)?).map_err(|_| "Length overflow")?;
+                length_delimited_value(take(input, len)?, depth)
+            }
+            3 if depth >= MAX_NESTING => {
+                return Err(format!("Groups nested deeper than {} levels", MAX_NESTING));
+            }
+            3 => (
+                "group",
+                Value::Object(decode_fields(input, depth + 1, Some(number))?),
+            ),
+            4 if group == Some(number) => return Ok(fields),
+            4 => return Err(format!("Unexpected end of group {}", number)),
+            5 => {
+                let bits = u32::from_le_bytes(take(input, 4)?.try_into().unwrap());
+                fixed32_value(bits)
+            }
+            wire_type => {
+                return Err(format!(
+                    "Invalid wire type {} for field {}",
+                    wire_type, number
+                ));
+            }
+        };
+
+        let key = format!("{} ({})", number, kind);
+        match fields.get_mut(&key) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+            None => {
+                fields.insert(key, value);
+            }
+        }
+    }
+
+    match group {
+        Some(number) => Err(format!("Unterminated group {}", number)),
+        None => Ok(fields),
+    }
+}
+
+fn length_delimited_value(data: &[u8], depth: usize) -> (&'static str, Value) {
+    if let Ok(text) = std::str::from_utf8(data)
+        && text
+            .chars()
+            .all(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'))
+    {
+        return ("string", Value::String(text.to_string()));
+    }
+    if depth < MAX_NESTING {
+        let mut input = data;
+        if let Ok(fields) = decode_fields(&mut input, depth + 1, None) {
+            return ("message", Value::Object(fields));
+        }
+    }
+    let hex: Vec<String> = data.iter().map(|b| format!("{:02x}", b)).collect();
+    ("bytes", Value::String(hex.join(" ")))
+}
+
+/// Shows fixed64 values as a double when that reads as an ordinary number.
+fn fixed64_value(bits: u64) -> (&'static str, Value) {
+    let float = f64::from_bits(bits);
+    if bits > 1 << 53 && float.is_finite() && (1e-6..1e15).contains(&float.abs()) {
+        ("double", Value::from(float))
+    } else {
+        ("fixed64", Value::from(bits))
+    }
+}
+
+/// Shows fixed32 values as a float when that reads as an ordinary number.
+fn fixed32_value(bits: u32) -> (&'static str, Value) {
+    let float = f32::from_bits(bits);
+    if bits > 1 << 24 && float.is_finite() && (1e-4..1e9).contains(&float.abs()) {
+        ("float", Value::from(float as f64))
+    } else {
+        ("fixed32", Value::from(bits))
+    }
+}
+
+fn read_varint(input: &mut &[u8]) -> Result<u64, String> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = input.split_first().ok_or("Truncated varint")?;
+        *input = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("Varint longer than 10 bytes".to_string())
+}
+
+fn take<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8], String> {
+    if input.len() < len {
+        return Err(format!("Field needs {} bytes, {} left", len, input.len()));
+    }
+    let (value, rest) = input.split_at(len);
+    *input = rest;
+    Ok(value)
+}
+
+/// A message decoded by [`decode`].
+pub struct Decoded {
+    /// Why decoding with the selected type failed, if it did.
+    pub schema_error: Option<String>,
+    /// The decoded message, in the wire format if there was no schema.
+    pub value: Result<Value, String>,
+}
+
+/// Decodes a message with `message_type`, falling back to the wire format
+/// without a type or if decoding with it fails.
+pub fn decode(schemas: &ProtoSchemas, message_type: Option<&str>, data: &[u8]) -> Decoded {
+    let Some(name) = message_type else {
+        return Decoded {
+            schema_error: None,
+            value: decode_wire(data),
+        };
+    };
+    match decode_with_schema(&schemas.pool, name, data) {
+        Ok(value) => Decoded {
+            schema_error: None,
+            value: Ok(value),
+        },
+        Err(e) => Decoded {
+            schema_error: Some(format!("Could not decode as {}: {}", name, e)),
+            value: decode_wire(data),
+        },
+    }
+}
+
+/// Combo box choosing the message type bodies are decoded as.
+pub fn render_message_type_selector(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    schemas: &ProtoSchemas,
+    message_type: &mut Option<String>,
+) {
+    let names = schemas.message_names();
+    ui.horizontal(|ui| {
+        ui.label("Message type:");
+        egui::ComboBox::from_id_salt(id.with("message_type"))
+            .selected_text(message_type.as_deref().unwrap_or("Schema-less"))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(message_type, None, "Schema-less");
+                for name in &names {
+                    ui.selectable_value(message_type, Some(name.clone()), name);
+                }
+            });
+        if names.is_empty() {
+            ui.label(
+                egui::RichText::new("Load .proto files in Settings to decode with a schema")
+                    .small()
+                    .weak(),
+            );
+        }
+    });
+}
+
+/// Renders a decoded message in the JSON tree, or `data` as a hex dump if
+/// it is not a valid message.
+pub fn render_message(ui: &mut egui::Ui, id: egui::Id, data: &[u8], decoded: &Decoded) {
+    if let Some(e) = &decoded.schema_error {
+        ui.label(
+            egui::RichText::new(format!("⚠ {}", e))
+                .small()
+                .color(egui::Color32::YELLOW),
+        );
+    }
+
+    match &decoded.value {
+        Ok(value) => {
+            let default_expand = if data.len() > 100_000 {
+                egui_json_tree::DefaultExpand::None
+            } else {
+                egui_json_tree::DefaultExpand::All
+            };
+            egui::Frame::new()
+                .fill(egui::Color32::from_gray(30))
+                .inner_margin(10.0)
+                .show(ui, |ui| {
+                    JsonTree::new(id.with("protobuf"), value)
+                        .default_expand(default_expand)
+                        .show(ui);
+                });
+        }
+        Err(e) => {
+            ui.label(
+                egui::RichText::new(format!("⚠ Not a valid protobuf message: {}", e))
+                    .small()
+                    .color(egui::Color32::YELLOW),
+            );
+            content_view::render_hex(ui, data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deeply_nested_groups_are_rejected() {
+        let data = vec![0x0B; 100_000];
+        assert!(decode_wire(&data).is_err());
+    }
+
+    #[test]
+    fn groups_within_the_limit_decode() {
+        // Field 1 group containing field 2 varint 150
+        let data = [0x0B, 0x10, 0x96, 0x01, 0x0C];
+        let value = decode_wire(&data).unwrap();
+        assert_eq!(value["1 (group)"]["2 (varint)"], 150);
+    }
+
+    #[test]
+    fn wire_format_shows_strings_messages_and_repeated_fields() {
+        // 1: "hi", 2: { 1: 7 }, 3: 1, 3: 2, 4: bytes ff
+        let data = [
+            0x0A, 0x02, b'h', b'i', 0x12, 0x02, 0x08, 0x07, 0x18, 0x01, 0x18, 0x02, 0x22, 0x01,
+            0xFF,
+        ];
+        let value = decode_wire(&data).unwrap();
+        assert_eq!(value["1 (string)"], "hi");
+        assert_eq!(value["2 (message)"]["1 (varint)"], 7);
+        assert_eq!(value["3 (varint)"], serde_json::json!([1, 2]));
+        assert_eq!(value["4 (bytes)"], "ff");
+    }
+
+    #[test]
+    fn truncated_fields_are_errors() {
+        assert!(decode_wire(&[0x0A, 0x05, b'a']).is_err());
+        assert!(decode_wire(&[0x08, 0x80]).is_err());
+        assert!(decode_wire(&[0x00]).is_err());
+    }
+
+    #[test]
+    fn schemas_decode_and_fall_back_to_the_wire_format() {
+        let dir = std::env::temp_dir().join(format!("protobuf-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("point.proto");
+        std::fs::write(
+            &path,
+            "syntax = \"proto3\";\npackage test;\nmessage Point { int32 x = 1; string label = 2; }\n",
+        )
+        .unwrap();
+        let mut schemas = ProtoSchemas::default();
+        schemas.add(String::from("point.proto"), compile_proto_files(&[path]));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(schemas.error, None);
+        assert_eq!(schemas.message_names(), ["test.Point"]);
+        assert_eq!(schemas.revision, 1);
+
+        let data = [0x08, 0x03, 0x12, 0x01, b'a'];
+        let decoded = decode(&schemas, Some("test.Point"), &data);
+        assert!(decoded.schema_error.is_none());
+        assert_eq!(
+            decoded.value.unwrap(),
+            serde_json::json!({"x": 3, "label": "a"})
+        );
+
+        // Field 2 as a varint does not match the schema
+        let decoded = decode(&schemas, Some("test.Point"), &[0x10, 0x01]);
+        assert!(decoded.schema_error.is_some());
+        assert_eq!(decoded.value.unwrap()["2 (varint)"], 1);
+
+        schemas.clear();
+        assert!(schemas.message_names().is_empty());
+        assert_eq!(schemas.revision, 2);
+    }
+}
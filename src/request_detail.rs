@@ -1,115 +1,538 @@
 use crate::HttpRequest;
+use crate::auth::{self, JwtKeys};
+use crate::binary_json::BinaryFormat;
+use crate::capture_store::{BodyState, TruncatedBody};
+use crate::code_view::{self, Language};
+use crate::content_encoding;
+use crate::content_view::{self, save_file_copy, save_to_file};
+use crate::cookies;
+use crate::graphql;
+use crate::grpc;
+use crate::json_query::Query;
+use crate::media;
+use crate::multipart;
+use crate::protobuf::{self, ProtoSchemas};
+use crate::request_overview::{ColumnKind, ListLayout};
+use crate::timestamps::TimestampDisplay;
+use crate::timing;
+use crate::url_encoded;
+use crate::xml;
+use chrono::{DateTime, Local, SecondsFormat, Utc};
 use eframe::egui;
+use egui::util::cache::{ComputerMut, FrameCache};
+use egui_extras::{Column, TableBuilder};
 use egui_json_tree::JsonTree;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
-pub fn render_request_detail(ui: &mut egui::Ui, request: Option<&HttpRequest>) {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DetailView {
+    Parsed,
+    Raw,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormView {
+    Table,
+    Tree,
+    Text,
+}
+
+/// How structured JSON and XML bodies are shown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TreeView {
+    Tree,
+    Source,
+}
+
+/// View state of the detail panel that persists across requests.
+pub struct RequestDetailState {
+    pub view: DetailView,
+    pub show_line_endings: bool,
+    pub form_view: FormView,
+    pub tree_view: TreeView,
+    /// JSONPath or jq query applied to JSON bodies; kept across requests.
+    pub json_query: String,
+    /// Reformat source views instead of showing the body as received.
+    pub pretty: bool,
+    pub line_numbers: bool,
+    /// Message type protobuf bodies are decoded as, `None` for schema-less.
+    pub proto_message: Option<String>,
+    pub query_plus_as_space: bool,
+    pub group_query_params: bool,
+}
+
+impl Default for RequestDetailState {
+    fn default() -> Self {
+        Self {
+            view: DetailView::Parsed,
+            show_line_endings: true,
+            form_view: FormView::Table,
+            tree_view: TreeView::Tree,
+            json_query: String::new(),
+            pretty: true,
+            line_numbers: true,
+            proto_message: None,
+            query_plus_as_space: true,
+            group_query_params: true,
+        }
+    }
+}
+
+pub fn render_request_detail(
+    ui: &mut egui::Ui,
+    request: Option<&HttpRequest>,
+    state: &mut RequestDetailState,
+    schemas: &ProtoSchemas,
+    jwt_keys: &JwtKeys,
+    layout: &mut ListLayout,
+    timestamps: &TimestampDisplay,
+) {
     if let Some(req) = request {
-        ui.heading("Request Details");
+        ui.horizontal(|ui| {
+            ui.heading("Request Details");
+            ui.separator();
+            ui.selectable_value(&mut state.view, DetailView::Parsed, "Parsed");
+            ui.selectable_value(&mut state.view, DetailView::Raw, "Raw");
+        });
         ui.separator();
 
-        egui::ScrollArea::both().show(ui, |ui| {
-            // Timestamp
-            ui.horizontal(|ui| {
-                ui.label(egui::RichText::new("Timestamp:").strong());
-                ui.label(egui::RichText::new(&req.timestamp).monospace());
-            });
-            ui.add_space(5.0);
+        match state.view {
+            DetailView::Parsed => {
+                render_parsed(ui, req, state, schemas, jwt_keys, layout, timestamps)
+            }
+            DetailView::Raw => render_raw(ui, req, state),
+        }
+    } else {
+        ui.centered_and_justified(|ui| {
+            ui.label(egui::RichText::new("Select a request from the list to view details").weak());
+        });
+    }
+}
 
-            // Method and Path
-            ui.horizontal(|ui| {
-                ui.label(egui::RichText::new("Method:").strong());
-                ui.label(
-                    egui::RichText::new(&req.method)
+fn render_parsed(
+    ui: &mut egui::Ui,
+    req: &HttpRequest,
+    state: &mut RequestDetailState,
+    schemas: &ProtoSchemas,
+    jwt_keys: &JwtKeys,
+    layout: &mut ListLayout,
+    timestamps: &TimestampDisplay,
+) {
+    egui::ScrollArea::both().show(ui, |ui| {
+        // Rejected request banner
+        if let Some(error) = &req.parse_error {
+            ui.label(
+                egui::RichText::new(format!("⚠ Rejected by the server: {}", error))
+                    .strong()
+                    .color(egui::Color32::RED),
+            );
+            ui.label(
+                egui::RichText::new(
+                    "Fields below were recovered leniently. See the Raw tab for the exact bytes.",
+                )
+                .small()
+                .weak(),
+            );
+            ui.add_space(10.0);
+        }
+
+        // Upload progress banner
+        match &req.body_state {
+            BodyState::Receiving => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "Receiving body, {} bytes so far",
+                            req.body_size
+                        ))
                         .strong()
-                        .color(get_method_color(&req.method)),
+                        .color(egui::Color32::LIGHT_BLUE),
+                    );
+                });
+                ui.add_space(10.0);
+            }
+            BodyState::Aborted(reason) => {
+                ui.label(
+                    egui::RichText::new(format!(
+                        "✖ Upload aborted after {} bytes: {}",
+                        req.body_size, reason
+                    ))
+                    .strong()
+                    .color(egui::Color32::RED),
                 );
-            });
+                ui.add_space(10.0);
+            }
+            BodyState::Complete => {}
+        }
+
+        // Timestamp
+        timestamps.request_repaint(ui.ctx());
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("Timestamp:").strong());
+            ui.label(egui::RichText::new(timestamps.format(req.timestamp, true)).monospace())
+                .on_hover_text(format!(
+                    "{}\nUnix epoch {:.3}",
+                    req.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+                    req.timestamp.timestamp_millis() as f64 / 1000.0
+                ));
+            ui.label(egui::RichText::new(format!("#{}", req.id)).weak())
+                .on_hover_text("Sequence number");
+        });
+        ui.add_space(5.0);
+
+        // Method and Path
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("Method:").strong());
+            ui.label(
+                egui::RichText::new(&req.method)
+                    .strong()
+                    .color(get_method_color(&req.method)),
+            );
+        });
+        ui.add_space(5.0);
+
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("Path:").strong());
+            ui.label(egui::RichText::new(&req.path).monospace());
+        });
+        ui.add_space(5.0);
+
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("From:").strong());
+            ui.label(egui::RichText::new(&req.remote_addr).monospace());
+        });
+        ui.add_space(5.0);
+
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("Connection:").strong());
+            ui.label(egui::RichText::new(format!("#{}", req.connection_id)).monospace());
+        });
+        ui.add_space(10.0);
+
+        // Timing section
+        ui.separator();
+        ui.label(egui::RichText::new("Timing").heading());
+        ui.add_space(5.0);
+        timing::render_waterfall(ui, &req.timings);
+        ui.add_space(10.0);
+
+        // Query parameters section (if present)
+        if let Some(query) = req.raw_query().filter(|query| !query.is_empty()) {
+            ui.separator();
+            render_query_params(ui, query, state);
+            ui.add_space(10.0);
+        }
+
+        // Headers section
+        ui.separator();
+        ui.label(egui::RichText::new(format!("Headers ({})", req.headers.len())).heading());
+        ui.add_space(5.0);
+
+        render_headers(ui, &req.headers);
+
+        ui.add_space(10.0);
+
+        // Cookies section (if present)
+        let cookies = cookies::request_cookies(&req.headers);
+        if !cookies.is_empty() {
+            ui.separator();
+            ui.label(egui::RichText::new(format!("Cookies ({})", cookies.len())).heading());
             ui.add_space(5.0);
 
-            ui.horizontal(|ui| {
-                ui.label(egui::RichText::new("Path:").strong());
-                ui.label(egui::RichText::new(&req.path).monospace());
-            });
+            render_params_grid(ui, "cookies_grid", &cookies);
+
+            ui.add_space(10.0);
+        }
+
+        // Trailers section (if present)
+        if !req.trailers.is_empty() {
+            ui.separator();
+            ui.label(egui::RichText::new(format!("Trailers ({})", req.trailers.len())).heading());
             ui.add_space(5.0);
 
-            ui.horizontal(|ui| {
-                ui.label(egui::RichText::new("From:").strong());
-                ui.label(egui::RichText::new(&req.remote_addr).monospace());
-            });
+            render_params_grid(ui, "trailers_grid", &req.trailers);
+
             ui.add_space(10.0);
+        }
 
-            // Query parameters section (if present)
-            if !req.query_params.is_empty() {
-                ui.separator();
-                ui.label(
-                    egui::RichText::new(format!("Query Parameters ({})", req.query_params.len()))
-                        .heading(),
-                );
-                ui.add_space(5.0);
+        // Credentials section (if any JWTs or Basic credentials were found)
+        let credentials = cached::<CredentialFinder, _, _>(ui, RequestKey(req));
+        if !credentials.is_empty() {
+            ui.separator();
+            ui.label(
+                egui::RichText::new(format!("Authentication ({})", credentials.len())).heading(),
+            );
+            ui.add_space(5.0);
 
-                render_query_params(ui, &req.query_params);
+            auth::render_credentials(ui, req.id, &credentials, jwt_keys);
 
-                ui.add_space(10.0);
-            }
+            ui.add_space(10.0);
+        }
 
-            // Headers section
+        // gRPC call section
+        if grpc::is_grpc(content_type(&req.headers)) {
             ui.separator();
-            ui.label(egui::RichText::new(format!("Headers ({})", req.headers.len())).heading());
+            ui.label(egui::RichText::new("gRPC").heading());
             ui.add_space(5.0);
 
-            render_headers(ui, &req.headers);
+            grpc::render_call(ui, req, schemas);
 
             ui.add_space(10.0);
+        }
 
-            // Body section
+        // GraphQL section
+        if req.graphql.is_some() {
+            let operations = cached::<GraphqlParser, _, _>(ui, RequestKey(req));
             ui.separator();
-            ui.horizontal(|ui| {
-                ui.label(egui::RichText::new(format!("Body ({} bytes)", req.body_size)).heading());
+            ui.label(egui::RichText::new("GraphQL").heading());
+            ui.add_space(5.0);
 
-                if req.body_size > 0 {
-                    ui.add_space(10.0);
+            graphql::render_operations(ui, req.id, &operations);
 
-                    // Copy to clipboard button
-                    if ui.button("📋 Copy to Clipboard").clicked() {
-                        ui.ctx().copy_text(req.body.clone());
-                    }
+            ui.add_space(10.0);
+        }
 
-                    // Save to file button
-                    if ui.button("💾 Save to File").clicked() {
-                        let body = req.body.clone();
-                        let filename = generate_filename(&req.timestamp, &req.path, &req.headers);
-
-                        std::thread::spawn(move || {
-                            if let Some(path) =
-                                rfd::FileDialog::new().set_file_name(&filename).save_file()
-                            {
-                                if let Err(e) = std::fs::write(&path, body) {
-                                    eprintln!("Failed to save file: {}", e);
-                                } else {
-                                    println!("Body saved to: {:?}", path);
-                                }
-                            }
-                        });
+        // Body section
+        ui.separator();
+        let encodings = content_encoding::encodings(&req.headers);
+        ui.horizontal(|ui| {
+            let heading = match &req.decoded_body {
+                Some(decoded) => format!(
+                    "Body ({} bytes {}, {} bytes decoded)",
+                    req.body_size,
+                    encodings.join("+"),
+                    decoded.len()
+                ),
+                None => format!("Body ({} bytes)", req.body_size),
+            };
+            ui.label(egui::RichText::new(heading).heading());
+
+            if req.body_size > 0 {
+                ui.add_space(10.0);
+
+                // Copy to clipboard button
+                if ui.button("📋 Copy to Clipboard").clicked() {
+                    ui.ctx()
+                        .copy_text(String::from_utf8_lossy(req.display_body()).into_owned());
+                }
+
+                // Save to file button, writes the body exactly as received.
+                // Without a spill file only the captured prefix is left.
+                let spill_path = req.truncated.as_ref().and_then(|t| t.spill_path.clone());
+                let save = if req.truncated.is_some() && spill_path.is_none() {
+                    ui.button(format!(
+                        "💾 Save Captured Prefix ({} of {} bytes)",
+                        req.body.len(),
+                        req.body_size
+                    ))
+                    .on_hover_text(
+                        "⚠ The rest of the body was not kept; the file will be incomplete",
+                    )
+                } else {
+                    ui.button("💾 Save to File")
+                };
+                if save.clicked() {
+                    let mut filename = generate_filename(&req.timestamp, &req.path, &req.headers);
+                    for coding in &encodings {
+                        filename.push_str(encoding_extension(coding));
+                    }
+                    match spill_path {
+                        Some(path) => save_file_copy(filename, path),
+                        None => save_to_file(filename, req.body.clone()),
                     }
                 }
-            });
-            ui.add_space(5.0);
 
-            render_body(ui, &req.body, req.body_size, &req.headers);
+                if let Some(decoded) = &req.decoded_body
+                    && ui.button("💾 Save Decoded").clicked()
+                {
+                    let filename = generate_filename(&req.timestamp, &req.path, &req.headers);
+                    save_to_file(filename, decoded.clone());
+                }
+
+                render_decode_as_selector(ui, req.id);
+            }
         });
-    } else {
-        ui.centered_and_justified(|ui| {
-            ui.label(egui::RichText::new("Select a request from the list to view details").weak());
+        if let Some(error) = &req.decode_error {
+            ui.label(
+                egui::RichText::new(format!("⚠ Could not decode body: {}", error))
+                    .small()
+                    .color(egui::Color32::YELLOW),
+            );
+        }
+        if let Some(truncated) = &req.truncated {
+            render_truncation_note(ui, req, truncated);
+        }
+        if req.chunks.len() > 1 {
+            render_chunks(ui, req);
+        }
+        ui.add_space(5.0);
+
+        render_body(ui, req, state, schemas, layout);
+    });
+}
+
+/// Explains that only a prefix of the body is kept and what is known about
+/// the rest.
+fn render_truncation_note(ui: &mut egui::Ui, req: &HttpRequest, truncated: &TruncatedBody) {
+    ui.label(
+        egui::RichText::new(format!(
+            "✂ Body exceeded the capture limit; showing the first {} of {} bytes",
+            req.body.len(),
+            req.body_size
+        ))
+        .small()
+        .color(egui::Color32::YELLOW),
+    );
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new("SHA-256:").small());
+        ui.label(egui::RichText::new(&truncated.sha256).small().monospace());
+        if ui.small_button("📋").on_hover_text("Copy hash").clicked() {
+            ui.ctx().copy_text(truncated.sha256.clone());
+        }
+    });
+    if let Some(path) = &truncated.spill_path {
+        ui.label(egui::RichText::new(format!("Complete body saved to {}", path.display())).small());
+    }
+    if let Some(error) = &truncated.spill_error {
+        ui.label(
+            egui::RichText::new(format!("⚠ Could not write the body to disk: {}", error))
+                .small()
+                .color(egui::Color32::YELLOW),
+        );
+    }
+}
+
+/// Arrival times of the body pieces, to tell slow or streaming clients apart.
+fn render_chunks(ui: &mut egui::Ui, req: &HttpRequest) {
+    egui::CollapsingHeader::new(format!("Chunks ({})", req.chunks.len()))
+        .id_salt(("body_chunks", req.id))
+        .show(ui, |ui| {
+            let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+            let mut offsets = Vec::with_capacity(req.chunks.len());
+            let mut offset = 0;
+            for chunk in &req.chunks {
+                offsets.push(offset);
+                offset += chunk.len;
+            }
+            egui::ScrollArea::vertical()
+                .id_salt("body_chunks_scroll")
+                .max_height(200.0)
+                .show_rows(ui, row_height, req.chunks.len(), |ui, rows| {
+                    for i in rows {
+                        let chunk = &req.chunks[i];
+                        ui.label(
+                            egui::RichText::new(format!(
+                                "+{:>9.1} ms  {:>10} bytes at offset {}",
+                                chunk.at.as_secs_f64() * 1000.0,
+                                chunk.len,
+                                offsets[i]
+                            ))
+                            .monospace(),
+                        );
+                    }
+                });
         });
+}
+
+fn render_raw(ui: &mut egui::Ui, req: &HttpRequest, state: &mut RequestDetailState) {
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new(format!("Raw Request ({} bytes)", req.raw_size)).heading());
+        ui.add_space(10.0);
+
+        if ui.button("📋 Copy to Clipboard").clicked() {
+            ui.ctx()
+                .copy_text(String::from_utf8_lossy(&req.raw).into_owned());
+        }
+
+        if ui.button("💾 Save to File").clicked() {
+            let filename = generate_filename(&req.timestamp, &req.path, &[]);
+            let filename = format!("{}.http", filename.trim_end_matches(".txt"));
+            save_to_file(filename, req.raw.clone());
+        }
+
+        ui.checkbox(&mut state.show_line_endings, "Show line endings");
+    });
+
+    if req.raw_size == 0 {
+        ui.label(
+            egui::RichText::new(
+                "No raw bytes were captured. HTTP/2 requests share interleaved frames on their connection, so only HTTP/1 requests have a raw view.",
+            )
+            .italics()
+            .weak(),
+        );
+        return;
     }
+
+    if req.raw.len() < req.raw_size {
+        ui.label(
+            egui::RichText::new(format!(
+                "⚠ Showing the first {} of {} bytes",
+                req.raw.len(),
+                req.raw_size
+            ))
+            .small()
+            .color(egui::Color32::YELLOW),
+        );
+    }
+    ui.add_space(5.0);
+
+    egui::Frame::new()
+        .fill(egui::Color32::from_gray(30))
+        .inner_margin(10.0)
+        .show(ui, |ui| {
+            egui::ScrollArea::both().show(ui, |ui| {
+                ui.label(wire_text_layout(ui, &req.raw, state.show_line_endings));
+            });
+        });
 }
 
-fn render_query_params(ui: &mut egui::Ui, query_params: &[(String, String)]) {
-    egui::Grid::new("query_params_grid")
+/// Lays out raw bytes as text, escaping control characters and invalid UTF-8
+/// as highlighted `\r`, `\n` and `\xNN` sequences.
+fn wire_text_layout(ui: &egui::Ui, raw: &[u8], show_line_endings: bool) -> egui::text::LayoutJob {
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+    let text_format = egui::TextFormat::simple(font.clone(), ui.visuals().text_color());
+    let escape_format = egui::TextFormat::simple(font, egui::Color32::from_rgb(255, 140, 0));
+
+    let mut job = egui::text::LayoutJob::default();
+    let mut text = String::new();
+    let push_escape = |job: &mut egui::text::LayoutJob, text: &mut String, escape: &str| {
+        job.append(&std::mem::take(text), 0.0, text_format.clone());
+        job.append(escape, 0.0, escape_format.clone());
+    };
+
+    for chunk in raw.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\r' if show_line_endings => push_escape(&mut job, &mut text, "\\r"),
+                '\r' => {}
+                '\n' if show_line_endings => {
+                    push_escape(&mut job, &mut text, "\\n");
+                    text.push('\n');
+                }
+                '\n' | '\t' => text.push(c),
+                c if c.is_control() => {
+                    push_escape(&mut job, &mut text, &format!("\\x{:02X}", c as u32));
+                }
+                c => text.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            push_escape(&mut job, &mut text, &format!("\\x{:02X}", byte));
+        }
+    }
+    job.append(&text, 0.0, text_format);
+    job
+}
+
+fn render_params_grid(ui: &mut egui::Ui, id_salt: &str, params: &[(String, String)]) {
+    egui::Grid::new(id_salt)
         .striped(true)
         .spacing([10.0, 5.0])
         .show(ui, |ui| {
-            for (key, value) in query_params {
+            for (key, value) in params {
                 ui.label(egui::RichText::new(key).strong());
                 ui.label(egui::RichText::new(value).monospace());
                 ui.end_row();
@@ -117,6 +540,87 @@ fn render_query_params(ui: &mut egui::Ui, query_params: &[(String, String)]) {
         });
 }
 
+/// Parameters are decoded from the raw query on display, so `+` handling can
+/// be switched and invalid escapes are shown as received.
+fn render_query_params(ui: &mut egui::Ui, query: &str, state: &mut RequestDetailState) {
+    let params = url_encoded::parse_params(query, state.query_plus_as_space);
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new(format!("Query Parameters ({})", params.len())).heading());
+        ui.add_space(10.0);
+        ui.checkbox(&mut state.query_plus_as_space, "+ as space")
+            .on_hover_text("Decode + as a space, as HTML forms do");
+        ui.checkbox(&mut state.group_query_params, "Group")
+            .on_hover_text("Show repeated names like ids[]=1&ids[]=2 in one row");
+    });
+    ui.add_space(5.0);
+
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new("Raw:").strong());
+        ui.label(egui::RichText::new(query).monospace());
+        if ui
+            .small_button("📋")
+            .on_hover_text("Copy raw query")
+            .clicked()
+        {
+            ui.ctx().copy_text(query.to_string());
+        }
+    });
+    let invalid = params.iter().filter(|param| param.error.is_some()).count();
+    if invalid > 0 {
+        ui.label(
+            egui::RichText::new(format!(
+                "⚠ {} parameter(s) with invalid encoding, kept as received",
+                invalid
+            ))
+            .small()
+            .color(egui::Color32::YELLOW),
+        );
+    }
+    ui.add_space(5.0);
+
+    // Repeated names are grouped in order of first appearance
+    let mut groups: Vec<(&str, Vec<&url_encoded::Param>)> = Vec::new();
+    for param in &params {
+        match groups.iter_mut().find(|(name, _)| *name == param.name) {
+            Some((_, values)) if state.group_query_params => values.push(param),
+            _ => groups.push((&param.name, vec![param])),
+        }
+    }
+
+    egui::Grid::new("query_params_grid")
+        .striped(true)
+        .spacing([10.0, 5.0])
+        .show(ui, |ui| {
+            for (name, values) in groups {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(name).strong());
+                    if values.len() > 1 {
+                        ui.label(egui::RichText::new(format!("×{}", values.len())).weak());
+                    }
+                });
+                ui.vertical(|ui| {
+                    for param in values {
+                        render_param_value(ui, param);
+                    }
+                });
+                ui.end_row();
+            }
+        });
+}
+
+fn render_param_value(ui: &mut egui::Ui, param: &url_encoded::Param) {
+    let Some(error) = &param.error else {
+        ui.label(egui::RichText::new(&param.value).monospace());
+        return;
+    };
+    ui.label(
+        egui::RichText::new(format!("⚠ {}", param.value))
+            .monospace()
+            .color(egui::Color32::YELLOW),
+    )
+    .on_hover_text(format!("{}\nRaw: {}", error, param.raw));
+}
+
 fn render_headers(ui: &mut egui::Ui, headers: &[(String, String)]) {
     egui::Grid::new("headers_grid")
         .striped(true)
@@ -130,14 +634,124 @@ fn render_headers(ui: &mut egui::Ui, headers: &[(String, String)]) {
         });
 }
 
-fn render_body(ui: &mut egui::Ui, body: &str, body_size: usize, headers: &[(String, String)]) {
+fn render_body(
+    ui: &mut egui::Ui,
+    req: &HttpRequest,
+    state: &mut RequestDetailState,
+    schemas: &ProtoSchemas,
+    layout: &mut ListLayout,
+) {
+    let body_size = req.display_body().len();
     if body_size > 0 {
+        let content_type = content_type(&req.headers);
+
+        // Binary JSON-like formats, detected or chosen with "Decode as"
+        if let Some(format) = decode_as(ui, req.id).or_else(|| BinaryFormat::detect(content_type)) {
+            match cached::<BinaryDecoder, _, _>(ui, (RequestKey(req), format)).as_ref() {
+                Ok(value) => {
+                    render_json_tree(
+                        ui,
+                        ("binary-body-tree", req.id),
+                        value,
+                        body_size,
+                        format.label(),
+                    );
+                }
+                Err(e) => {
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "⚠ Could not decode as {}: {}",
+                            format.label(),
+                            e
+                        ))
+                        .small()
+                        .color(egui::Color32::YELLOW),
+                    );
+                    content_view::render_hex(ui, req.display_body());
+                }
+            }
+            return;
+        }
+
+        // Multipart bodies are shown as a table of parts
+        if let Some(parts) = cached::<MultipartParser, _, _>(ui, RequestKey(req)).as_ref() {
+            multipart::render_multipart(ui, req.id, req.display_body(), parts);
+            return;
+        }
+
+        // Form bodies are shown like query parameters
+        if content_type
+            .to_ascii_lowercase()
+            .starts_with("application/x-www-form-urlencoded")
+            && state.form_view != FormView::Text
+        {
+            render_form_body(ui, req, state);
+            return;
+        }
+
+        // gRPC bodies are split into their length-prefixed messages
+        if grpc::is_grpc(content_type) {
+            grpc::render_message_type_selector(ui, req, schemas, &mut state.proto_message);
+            let key = ProtoKey {
+                req: RequestKey(req),
+                schemas,
+                message_type: state.proto_message.as_deref(),
+            };
+            let messages = cached::<GrpcDecoder, _, _>(ui, key);
+            grpc::render_messages(ui, req.id, &messages);
+            return;
+        }
+
+        // Protobuf bodies are decoded with the selected schema or wire format
+        if protobuf::is_protobuf(content_type) {
+            let id = egui::Id::new(("protobuf-body", req.id));
+            protobuf::render_message_type_selector(ui, id, schemas, &mut state.proto_message);
+            ui.add_space(5.0);
+            let key = ProtoKey {
+                req: RequestKey(req),
+                schemas,
+                message_type: state.proto_message.as_deref(),
+            };
+            let decoded = cached::<ProtobufDecoder, _, _>(ui, key);
+            protobuf::render_message(ui, id, req.display_body(), &decoded);
+            return;
+        }
+
+        // Images, PDFs and audio/video uploads are previewed with their metadata
+        if let Some((kind, metadata)) = cached::<MediaReader, _, _>(ui, RequestKey(req)).as_ref() {
+            media::render_media(
+                ui,
+                egui::Id::new(("media-body", req.id)),
+                *kind,
+                req.display_body(),
+                metadata,
+            );
+            return;
+        }
+
+        // XML bodies are shown as an element tree or pretty-printed source
+        if xml::is_xml(content_type, req.display_body()) {
+            match cached::<XmlParser, _, _>(ui, RequestKey(req)).as_ref() {
+                Ok(tree) => {
+                    let text = cached::<TextDecoder, _, _>(ui, RequestKey(req));
+                    render_xml_body(ui, req, &text.text, tree, state);
+                    return;
+                }
+                Err(e) => {
+                    if content_type.to_ascii_lowercase().contains("xml") {
+                        ui.label(
+                            egui::RichText::new(format!("⚠ Invalid XML: {}", e))
+                                .small()
+                                .color(egui::Color32::YELLOW),
+                        );
+                    }
+                }
+            }
+        }
+
         // Check if content is JSON
-        let content_type = headers
-            .iter()
-            .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
-            .map(|(_, value)| value.as_str())
-            .unwrap_or("");
+        let text = cached::<TextDecoder, _, _>(ui, RequestKey(req));
+        let body = text.text.as_str();
 
         let is_json = content_type.contains("application/json")
             || content_type.contains("text/json")
@@ -145,64 +759,597 @@ fn render_body(ui: &mut egui::Ui, body: &str, body_size: usize, headers: &[(Stri
 
         if is_json {
             // Try to parse and render as JSON tree
-            match serde_json::from_str::<serde_json::Value>(body) {
-                Ok(json_value) => {
-                    // Determine default expansion based on size
-                    // For large JSON (>100KB), start collapsed to avoid performance issues
-                    let default_expand = if body_size > 100_000 {
-                        egui_json_tree::DefaultExpand::None
-                    } else {
-                        egui_json_tree::DefaultExpand::All
-                    };
-
-                    egui::Frame::new()
-                        .fill(egui::Color32::from_gray(30))
-                        .inner_margin(10.0)
-                        .show(ui, |ui| {
-                            // Show warning for very large JSON
-                            if body_size > 100_000 {
-                                ui.label(
-                                    egui::RichText::new(
-                                        format!("⚠ Large JSON ({:.1} KB) - expand nodes carefully for better performance",
-                                        body_size as f32 / 1024.0)
-                                    )
-                                    .small()
-                                    .color(egui::Color32::YELLOW)
+            match cached::<JsonParser, _, _>(ui, RequestKey(req)).as_ref() {
+                Some(json_value) => {
+                    render_tree_view_selector(ui, state, Language::Json, None);
+                    match state.tree_view {
+                        TreeView::Tree => {
+                            if !render_json_query(ui, req, json_value, state, layout) {
+                                render_json_tree(
+                                    ui,
+                                    "json-body-tree",
+                                    json_value,
+                                    body_size,
+                                    "JSON",
                                 );
-                                ui.add_space(5.0);
                             }
-
-                            JsonTree::new("json-body-tree", &json_value)
-                                .default_expand(default_expand)
-                                .show(ui);
-                        });
+                        }
+                        TreeView::Source => {
+                            code_view::render_code(
+                                ui,
+                                egui::Id::new(("json-body-source", req.id)),
+                                body,
+                                Some(Language::Json),
+                                state.pretty,
+                                state.line_numbers,
+                            );
+                        }
+                    }
                     return;
                 }
-                Err(_) => {
+                None => {
                     // If JSON parsing fails, fall through to plain text rendering
                 }
             }
         }
 
-        // Render as plain text
+        if content_type
+            .to_ascii_lowercase()
+            .starts_with("application/x-www-form-urlencoded")
+        {
+            render_form_view_selector(ui, state);
+        }
+
+        // Render as text, highlighted when the language is known or chosen
+        let detected = text.language;
+        let language = language_override(ui, req.id).unwrap_or(detected);
+        ui.horizontal(|ui| {
+            render_language_selector(ui, req.id, detected);
+            ui.separator();
+            render_code_options(ui, state, language);
+        });
+        ui.add_space(5.0);
+        code_view::render_code(
+            ui,
+            egui::Id::new(("text-body", req.id)),
+            body,
+            language,
+            state.pretty,
+            state.line_numbers,
+        );
+    } else {
+        ui.label(egui::RichText::new("(empty)").italics().weak());
+    }
+}
+
+/// Identifies a request's content for `FrameCache`. Hashing the whole body
+/// every frame would cost about as much as parsing it.
+#[derive(Clone, Copy)]
+struct RequestKey<'a>(&'a HttpRequest);
+
+impl Hash for RequestKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.id.hash(state);
+        self.0.display_body().len().hash(state);
+    }
+}
+
+/// The body as text, with the language detected from it.
+struct BodyText {
+    text: String,
+    language: Option<Language>,
+}
+
+#[derive(Default)]
+struct TextDecoder;
+
+impl ComputerMut<RequestKey<'_>, Arc<BodyText>> for TextDecoder {
+    fn compute(&mut self, key: RequestKey<'_>) -> Arc<BodyText> {
+        let text = String::from_utf8_lossy(key.0.display_body()).into_owned();
+        let language = Language::detect(content_type(&key.0.headers), &text);
+        Arc::new(BodyText { text, language })
+    }
+}
+
+#[derive(Default)]
+struct JsonParser;
+
+impl ComputerMut<RequestKey<'_>, Arc<Option<serde_json::Value>>> for JsonParser {
+    fn compute(&mut self, key: RequestKey<'_>) -> Arc<Option<serde_json::Value>> {
+        let body = String::from_utf8_lossy(key.0.display_body());
+        Arc::new(serde_json::from_str(&body).ok())
+    }
+}
+
+#[derive(Default)]
+struct CredentialFinder;
+
+impl ComputerMut<RequestKey<'_>, Arc<Vec<auth::Found>>> for CredentialFinder {
+    fn compute(&mut self, key: RequestKey<'_>) -> Arc<Vec<auth::Found>> {
+        Arc::new(auth::find_credentials(key.0))
+    }
+}
+
+#[derive(Default)]
+struct GraphqlParser;
+
+impl ComputerMut<RequestKey<'_>, Arc<Vec<graphql::Operation>>> for GraphqlParser {
+    fn compute(&mut self, key: RequestKey<'_>) -> Arc<Vec<graphql::Operation>> {
+        Arc::new(graphql::operations(key.0))
+    }
+}
+
+#[derive(Default)]
+struct BinaryDecoder;
+
+impl ComputerMut<(RequestKey<'_>, BinaryFormat), Arc<Result<serde_json::Value, String>>>
+    for BinaryDecoder
+{
+    fn compute(
+        &mut self,
+        (key, format): (RequestKey<'_>, BinaryFormat),
+    ) -> Arc<Result<serde_json::Value, String>> {
+        Arc::new(format.decode(key.0.display_body()))
+    }
+}
+
+#[derive(Default)]
+struct MultipartParser;
+
+impl ComputerMut<RequestKey<'_>, Arc<Option<Vec<multipart::Part>>>> for MultipartParser {
+    fn compute(&mut self, key: RequestKey<'_>) -> Arc<Option<Vec<multipart::Part>>> {
+        let parts = multipart::boundary(content_type(&key.0.headers))
+            .and_then(|boundary| multipart::parse(key.0.display_body(), &boundary));
+        Arc::new(parts)
+    }
+}
+
+#[derive(Default)]
+struct MediaReader;
+
+type MediaInfo = Option<(media::MediaKind, Result<Vec<(String, String)>, String>)>;
+
+impl ComputerMut<RequestKey<'_>, Arc<MediaInfo>> for MediaReader {
+    fn compute(&mut self, key: RequestKey<'_>) -> Arc<MediaInfo> {
+        let body = key.0.display_body();
+        let info = media::detect(content_type(&key.0.headers), body)
+            .map(|kind| (kind, media::metadata(kind, body)));
+        Arc::new(info)
+    }
+}
+
+#[derive(Default)]
+struct XmlParser;
+
+impl ComputerMut<RequestKey<'_>, Arc<Result<xml::Tree, String>>> for XmlParser {
+    fn compute(&mut self, key: RequestKey<'_>) -> Arc<Result<xml::Tree, String>> {
+        let text = String::from_utf8_lossy(key.0.display_body());
+        let tree = xml::parse(&text)
+            .map(|doc| xml::Tree::new(&doc))
+            .map_err(|e| e.to_string());
+        Arc::new(tree)
+    }
+}
+
+/// A request body decoded as protobuf, which also depends on the loaded
+/// schemas and the chosen message type.
+#[derive(Clone, Copy)]
+struct ProtoKey<'a> {
+    req: RequestKey<'a>,
+    schemas: &'a ProtoSchemas,
+    message_type: Option<&'a str>,
+}
+
+impl Hash for ProtoKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.req.hash(state);
+        self.schemas.revision.hash(state);
+        self.message_type.hash(state);
+    }
+}
+
+#[derive(Default)]
+struct ProtobufDecoder;
+
+impl ComputerMut<ProtoKey<'_>, Arc<protobuf::Decoded>> for ProtobufDecoder {
+    fn compute(&mut self, key: ProtoKey<'_>) -> Arc<protobuf::Decoded> {
+        let body = key.req.0.display_body();
+        Arc::new(protobuf::decode(key.schemas, key.message_type, body))
+    }
+}
+
+#[derive(Default)]
+struct GrpcDecoder;
+
+impl ComputerMut<ProtoKey<'_>, Arc<grpc::Messages>> for GrpcDecoder {
+    fn compute(&mut self, key: ProtoKey<'_>) -> Arc<grpc::Messages> {
+        Arc::new(grpc::decode_messages(
+            key.req.0,
+            key.schemas,
+            key.message_type,
+        ))
+    }
+}
+
+#[derive(Default)]
+struct FormParser;
+
+impl ComputerMut<RequestKey<'_>, Arc<Vec<(String, String)>>> for FormParser {
+    fn compute(&mut self, key: RequestKey<'_>) -> Arc<Vec<(String, String)>> {
+        let body = String::from_utf8_lossy(key.0.display_body());
+        Arc::new(url_encoded::parse_pairs(body.trim(), true))
+    }
+}
+
+#[derive(Default)]
+struct FormTreeBuilder;
+
+impl ComputerMut<RequestKey<'_>, Arc<serde_json::Value>> for FormTreeBuilder {
+    fn compute(&mut self, key: RequestKey<'_>) -> Arc<serde_json::Value> {
+        let body = String::from_utf8_lossy(key.0.display_body());
+        let fields = url_encoded::parse_pairs(body.trim(), true);
+        Arc::new(url_encoded::nested_tree(&fields))
+    }
+}
+
+/// A query over a request's JSON body.
+#[derive(Clone, Copy)]
+struct QueryKey<'a> {
+    req: RequestKey<'a>,
+    /// The parsed body, cached itself.
+    json: &'a serde_json::Value,
+    query: &'a str,
+}
+
+impl Hash for QueryKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.req.hash(state);
+        self.query.hash(state);
+    }
+}
+
+struct QueryResult {
+    matches: usize,
+    /// The single match, or all matches as an array.
+    value: Option<serde_json::Value>,
+}
+
+#[derive(Default)]
+struct QueryRunner;
+
+impl ComputerMut<QueryKey<'_>, Arc<Result<QueryResult, String>>> for QueryRunner {
+    fn compute(&mut self, key: QueryKey<'_>) -> Arc<Result<QueryResult, String>> {
+        let result = Query::parse(key.query).map(|query| {
+            let mut matches = query.evaluate(key.json);
+            let count = matches.len();
+            let value = match count {
+                0 => None,
+                1 => matches.pop().map(|m| m.into_owned()),
+                _ => Some(serde_json::Value::Array(
+                    matches.into_iter().map(|m| m.into_owned()).collect(),
+                )),
+            };
+            QueryResult {
+                matches: count,
+                value,
+            }
+        });
+        Arc::new(result)
+    }
+}
+
+/// Work derived from a request, done once while the request stays selected
+/// instead of every frame.
+fn cached<C, K, V>(ui: &egui::Ui, key: K) -> V
+where
+    C: ComputerMut<K, V> + Default + Send + Sync + 'static,
+    K: Copy + Hash,
+    V: Clone + Send + Sync + 'static,
+{
+    ui.ctx()
+        .memory_mut(|mem| mem.caches.cache::<FrameCache<V, C>>().get(key))
+}
+
+fn content_type(headers: &[(String, String)]) -> &str {
+    headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        .map(|(_, value)| value.as_str())
+        .unwrap_or("")
+}
+
+fn render_json_tree(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    value: &serde_json::Value,
+    body_size: usize,
+    format: &str,
+) {
+    // Determine default expansion based on size
+    // For large bodies (>100KB), start collapsed to avoid performance issues
+    let default_expand = if body_size > 100_000 {
+        egui_json_tree::DefaultExpand::None
+    } else {
+        egui_json_tree::DefaultExpand::All
+    };
+
+    egui::Frame::new()
+        .fill(egui::Color32::from_gray(30))
+        .inner_margin(10.0)
+        .show(ui, |ui| {
+            // Show warning for very large bodies
+            if body_size > 100_000 {
+                ui.label(
+                    egui::RichText::new(format!(
+                        "⚠ Large {} ({:.1} KB) - expand nodes carefully for better performance",
+                        format,
+                        body_size as f32 / 1024.0
+                    ))
+                    .small()
+                    .color(egui::Color32::YELLOW),
+                );
+                ui.add_space(5.0);
+            }
+
+            JsonTree::new(id, value)
+                .default_expand(default_expand)
+                .show(ui);
+        });
+}
+
+/// Query box above the JSON tree. Returns whether matches were shown in
+/// place of the whole body.
+fn render_json_query(
+    ui: &mut egui::Ui,
+    req: &HttpRequest,
+    json: &serde_json::Value,
+    state: &mut RequestDetailState,
+    layout: &mut ListLayout,
+) -> bool {
+    let key = QueryKey {
+        req: RequestKey(req),
+        json,
+        query: state.json_query.trim(),
+    };
+    let result = cached::<QueryRunner, _, _>(ui, key);
+    ui.horizontal(|ui| {
+        ui.label("Query:");
+        ui.add(
+            egui::TextEdit::singleline(&mut state.json_query)
+                .font(egui::TextStyle::Monospace)
+                .desired_width(300.0)
+                .hint_text("$.event.type or .items[] | select(.id == 1)"),
+        );
+        let column = ColumnKind::Json(state.json_query.trim().to_string());
+        if result.is_ok()
+            && !layout.has_column(&column)
+            && ui
+                .button("➕ Add Column")
+                .on_hover_text("Show the result for every request in the list")
+                .clicked()
+        {
+            layout.add_column(column);
+        }
+    });
+    ui.add_space(5.0);
+
+    if state.json_query.trim().is_empty() {
+        return false;
+    }
+    let result = match result.as_ref() {
+        Ok(result) => result,
+        Err(e) => {
+            ui.label(
+                egui::RichText::new(format!("⚠ Invalid query: {}", e))
+                    .small()
+                    .color(egui::Color32::YELLOW),
+            );
+            return false;
+        }
+    };
+
+    ui.label(
+        egui::RichText::new(match result.matches {
+            0 => "No matches".to_string(),
+            1 => "1 match".to_string(),
+            n => format!("{} matches", n),
+        })
+        .small()
+        .weak(),
+    );
+    if let Some(value) = &result.value {
         egui::Frame::new()
             .fill(egui::Color32::from_gray(30))
             .inner_margin(10.0)
             .show(ui, |ui| {
-                egui::ScrollArea::vertical()
-                    .max_height(400.0)
-                    .show(ui, |ui| {
-                        let mut body_text = body;
-                        ui.add(
-                            egui::TextEdit::multiline(&mut body_text)
-                                .font(egui::TextStyle::Monospace)
-                                .desired_width(f32::INFINITY)
-                                .interactive(false),
-                        );
-                    });
+                JsonTree::new(("json-query-result", req.id), value).show(ui);
             });
-    } else {
-        ui.label(egui::RichText::new("(empty)").italics().weak());
+    }
+    true
+}
+
+/// Language the text body of this request was manually chosen to be shown
+/// as; `Some(None)` for plain text.
+fn language_override(ui: &egui::Ui, request_id: u64) -> Option<Option<Language>> {
+    ui.ctx().data(|d| {
+        d.get_temp::<Option<Option<Language>>>(egui::Id::new(("highlight-as", request_id)))
+            .flatten()
+    })
+}
+
+fn render_language_selector(ui: &mut egui::Ui, request_id: u64, detected: Option<Language>) {
+    let id = egui::Id::new(("highlight-as", request_id));
+    let mut selected = language_override(ui, request_id);
+    let auto = format!("Auto ({})", detected.map_or("Plain text", Language::label));
+    ui.label("Highlight as:");
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(match selected {
+            None => auto.as_str(),
+            Some(language) => language.map_or("Plain text", Language::label),
+        })
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut selected, None, auto.as_str());
+            ui.selectable_value(&mut selected, Some(None), "Plain text");
+            for language in Language::ALL {
+                ui.selectable_value(&mut selected, Some(Some(language)), language.label());
+            }
+        });
+    ui.ctx().data_mut(|d| d.insert_temp(id, selected));
+}
+
+fn render_code_options(
+    ui: &mut egui::Ui,
+    state: &mut RequestDetailState,
+    language: Option<Language>,
+) {
+    let can_pretty_print = language.is_some_and(Language::can_pretty_print);
+    ui.add_enabled_ui(can_pretty_print, |ui| {
+        ui.selectable_value(&mut state.pretty, false, "Raw");
+        ui.selectable_value(&mut state.pretty, true, "Pretty");
+    });
+    ui.checkbox(&mut state.line_numbers, "Line numbers");
+}
+
+fn render_tree_view_selector(
+    ui: &mut egui::Ui,
+    state: &mut RequestDetailState,
+    language: Language,
+    summary: Option<String>,
+) {
+    ui.horizontal(|ui| {
+        ui.selectable_value(&mut state.tree_view, TreeView::Tree, "Tree");
+        ui.selectable_value(&mut state.tree_view, TreeView::Source, "Source");
+        if state.tree_view == TreeView::Source {
+            ui.separator();
+            render_code_options(ui, state, Some(language));
+        }
+        if let Some(summary) = summary {
+            ui.separator();
+            ui.label(egui::RichText::new(summary).strong());
+        }
+    });
+    ui.add_space(5.0);
+}
+
+/// Format the body of this request was manually chosen to be decoded as.
+fn decode_as(ui: &egui::Ui, request_id: u64) -> Option<BinaryFormat> {
+    ui.ctx()
+        .data(|d| d.get_temp::<Option<BinaryFormat>>(egui::Id::new(("decode-as", request_id))))
+        .flatten()
+}
+
+fn render_decode_as_selector(ui: &mut egui::Ui, request_id: u64) {
+    let id = egui::Id::new(("decode-as", request_id));
+    let mut selected = decode_as(ui, request_id);
+    ui.label("Decode as:");
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(selected.map_or("Auto", BinaryFormat::label))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut selected, None, "Auto");
+            for format in BinaryFormat::ALL {
+                ui.selectable_value(&mut selected, Some(format), format.label());
+            }
+        });
+    ui.ctx().data_mut(|d| d.insert_temp(id, selected));
+}
+
+fn render_form_view_selector(ui: &mut egui::Ui, state: &mut RequestDetailState) {
+    ui.horizontal(|ui| {
+        ui.selectable_value(&mut state.form_view, FormView::Table, "Table");
+        ui.selectable_value(&mut state.form_view, FormView::Tree, "Tree");
+        ui.selectable_value(&mut state.form_view, FormView::Text, "Text");
+    });
+    ui.add_space(5.0);
+}
+
+fn render_form_body(ui: &mut egui::Ui, req: &HttpRequest, state: &mut RequestDetailState) {
+    let fields = cached::<FormParser, _, _>(ui, RequestKey(req));
+
+    render_form_view_selector(ui, state);
+    ui.label(egui::RichText::new(format!("Form Fields ({})", fields.len())).strong());
+    ui.add_space(5.0);
+
+    match state.form_view {
+        FormView::Tree => {
+            // Nested bracket keys like a[b][c]=1 become objects and arrays
+            let tree = cached::<FormTreeBuilder, _, _>(ui, RequestKey(req));
+            egui::Frame::new()
+                .fill(egui::Color32::from_gray(30))
+                .inner_margin(10.0)
+                .show(ui, |ui| {
+                    JsonTree::new(("form-body-tree", req.id), tree.as_ref()).show(ui);
+                });
+        }
+        _ => render_fields_table(ui, ("form-fields", req.id), &fields),
+    }
+}
+
+/// Name/value rows of which only the visible ones are laid out, for forms
+/// with many fields.
+fn render_fields_table(ui: &mut egui::Ui, id_salt: impl Hash, fields: &[(String, String)]) {
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace) + 4.0;
+    TableBuilder::new(ui)
+        .id_salt(id_salt)
+        .striped(true)
+        .max_scroll_height(400.0)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::auto().at_least(80.0))
+        .column(Column::auto().at_least(80.0))
+        .body(|body| {
+            body.rows(row_height, fields.len(), |mut row| {
+                let (key, value) = &fields[row.index()];
+                row.col(|ui| {
+                    ui.label(egui::RichText::new(key).strong());
+                });
+                row.col(|ui| {
+                    ui.label(egui::RichText::new(value).monospace());
+                });
+            });
+        });
+}
+
+fn render_xml_body(
+    ui: &mut egui::Ui,
+    req: &HttpRequest,
+    text: &str,
+    tree: &xml::Tree,
+    state: &mut RequestDetailState,
+) {
+    let body_size = req.display_body().len();
+
+    render_tree_view_selector(ui, state, Language::Xml, tree.soap_summary.clone());
+
+    match state.tree_view {
+        TreeView::Tree => {
+            egui::Frame::new()
+                .fill(egui::Color32::from_gray(30))
+                .inner_margin(10.0)
+                .show(ui, |ui| {
+                    // Like JSON, large documents start collapsed
+                    if body_size > 100_000 {
+                        ui.label(
+                            egui::RichText::new(format!(
+                                "⚠ Large XML ({:.1} KB) - expand nodes carefully for better performance",
+                                body_size as f32 / 1024.0
+                            ))
+                            .small()
+                            .color(egui::Color32::YELLOW),
+                        );
+                        ui.add_space(5.0);
+                    }
+                    xml::render_tree(ui, egui::Id::new(("xml-body-tree", req.id)), tree, body_size > 100_000);
+                });
+        }
+        TreeView::Source => {
+            code_view::render_code(
+                ui,
+                egui::Id::new(("xml-body-source", req.id)),
+                text,
+                Some(Language::Xml),
+                state.pretty,
+                state.line_numbers,
+            );
+        }
     }
 }
 
@@ -216,7 +1363,21 @@ fn get_method_color(method: &str) -> egui::Color32 {
     }
 }
 
-fn generate_filename(timestamp: &str, path: &str, headers: &[(String, String)]) -> String {
+fn encoding_extension(coding: &str) -> &'static str {
+    match coding {
+        "gzip" | "x-gzip" => ".gz",
+        "deflate" => ".zz",
+        "br" => ".br",
+        "zstd" => ".zst",
+        _ => "",
+    }
+}
+
+fn generate_filename(
+    timestamp: &DateTime<Utc>,
+    path: &str,
+    headers: &[(String, String)],
+) -> String {
     // Extract content-type from headers
     let content_type = headers
         .iter()
@@ -224,48 +1385,22 @@ fn generate_filename(timestamp: &str, path: &str, headers: &[(String, String)])
         .map(|(_, value)| value.as_str())
         .unwrap_or("text/plain");
 
-    // Determine file extension based on content-type
-    let extension = match content_type.split(';').next().unwrap_or("").trim() {
-        "application/json" => "json",
-        "application/xml" | "text/xml" => "xml",
-        "text/html" => "html",
-        "text/css" => "css",
-        "text/javascript" | "application/javascript" => "js",
-        "application/x-www-form-urlencoded" => "txt",
-        "multipart/form-data" => "txt",
-        "text/csv" => "csv",
-        "application/pdf" => "pdf",
-        "image/png" => "png",
-        "image/jpeg" => "jpg",
-        "image/gif" => "gif",
-        "image/svg+xml" => "svg",
-        "application/zip" => "zip",
-        "application/octet-stream" => "bin",
-        _ => "txt",
-    };
+    let extension = content_view::file_extension(content_type).unwrap_or("txt");
 
-    // Clean up path to use as part of filename
-    let path_clean = path
+    // Clean up path to use as part of filename, truncated if too long
+    let path_part = path
         .trim_start_matches('/')
         .replace(['/', '?', '&', '='], "_")
         .chars()
         .filter(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+        .take(50)
         .collect::<String>();
 
-    // Truncate path if too long
-    let path_part = if path_clean.len() > 50 {
-        &path_clean[..50]
-    } else {
-        &path_clean
-    };
-
-    // Format timestamp for filename (YYYYMMDD_HHMMSS)
+    // Format timestamp for filename (YYYYMMDD_HHMMSSmmm)
     let datetime = timestamp
-        .replace(' ', "_")
-        .replace([':', '.'], "")
-        .chars()
-        .filter(|c| c.is_alphanumeric() || *c == '_')
-        .collect::<String>();
+        .with_timezone(&Local)
+        .format("%Y%m%d_%H%M%S%3f")
+        .to_string();
 
     // Combine parts: datetime_path.extension
     if path_part.is_empty() {
@@ -274,3 +1409,25 @@ fn generate_filename(timestamp: &str, path: &str, headers: &[(String, String)])
         format!("{}_{}.{}", datetime, path_part, extension)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_non_ascii_paths_are_truncated_by_character() {
+        let path = format!("/{}", "ü".repeat(60));
+        let filename = generate_filename(&Utc::now(), &path, &[]);
+        assert!(filename.ends_with(&format!("_{}.txt", "ü".repeat(50))));
+    }
+
+    #[test]
+    fn multipart_bodies_are_not_saved_as_text() {
+        let headers = [(
+            "Content-Type".to_string(),
+            "multipart/form-data; boundary=x".to_string(),
+        )];
+        let filename = generate_filename(&Utc::now(), "/upload", &headers);
+        assert!(filename.ends_with("_upload.multipart"));
+    }
+}
@@ -1,150 +1,748 @@
 use crate::HttpRequest;
+use crate::capture_store::{BodyState, CaptureStore};
+use crate::json_query::{Query, QueryColumn};
+use crate::listen_address::ListenAddress;
+use crate::timestamps::TimestampDisplay;
+use crate::timing::format_duration;
+use chrono::SecondsFormat;
 use eframe::egui;
+use egui_extras::{Column, TableBuilder};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Storage key of the request list layout.
+pub const LAYOUT_KEY: &str = "request_list_layout";
+
+const ROW_HEIGHT: f32 = 18.0;
+
+/// What a column of the request list shows.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ColumnKind {
+    Time,
+    Method,
+    Path,
+    Status,
+    Size,
+    Duration,
+    RemoteAddr,
+    ContentType,
+    /// Value of a request header.
+    Header(String),
+    /// Result of a JSONPath or jq query over JSON bodies.
+    Json(String),
+}
+
+impl ColumnKind {
+    pub const BUILT_IN: [ColumnKind; 8] = [
+        ColumnKind::Time,
+        ColumnKind::Method,
+        ColumnKind::Path,
+        ColumnKind::Status,
+        ColumnKind::Size,
+        ColumnKind::Duration,
+        ColumnKind::RemoteAddr,
+        ColumnKind::ContentType,
+    ];
+
+    pub fn label(&self) -> &str {
+        match self {
+            ColumnKind::Time => "Time",
+            ColumnKind::Method => "Method",
+            ColumnKind::Path => "Path",
+            ColumnKind::Status => "Status",
+            ColumnKind::Size => "Size",
+            ColumnKind::Duration => "Duration",
+            ColumnKind::RemoteAddr => "Remote Address",
+            ColumnKind::ContentType => "Content-Type",
+            ColumnKind::Header(name) => name,
+            ColumnKind::Json(query) => query,
+        }
+    }
+
+    fn default_width(&self) -> f32 {
+        match self {
+            ColumnKind::Time => 90.0,
+            ColumnKind::Method => 60.0,
+            ColumnKind::Path => 220.0,
+            ColumnKind::Status => 50.0,
+            ColumnKind::Size => 70.0,
+            ColumnKind::Duration => 70.0,
+            ColumnKind::RemoteAddr => 130.0,
+            ColumnKind::ContentType => 150.0,
+            ColumnKind::Header(_) | ColumnKind::Json(_) => 150.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListColumn {
+    pub kind: ColumnKind,
+    pub width: f32,
+}
+
+/// Columns, widths and sort order of the request list, kept across sessions.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListLayout {
+    pub columns: Vec<ListColumn>,
+    /// `None` lists the newest request first.
+    pub sort_by: Option<ColumnKind>,
+    pub ascending: bool,
+    /// Bumped when columns move so the table starts from the stored widths.
+    #[serde(skip)]
+    generation: u64,
+    /// Parsed JSON column queries; a failed parse keeps its error for the
+    /// column header.
+    #[serde(skip)]
+    json_columns: HashMap<String, Result<QueryColumn, String>>,
+    /// Request ids in display order, updated when requests arrive or are
+    /// evicted and rebuilt when the sort changes.
+    #[serde(skip)]
+    order: Vec<u64>,
+    #[serde(skip)]
+    order_valid: bool,
+    /// Id of the newest request in `order`.
+    #[serde(skip)]
+    newest: u64,
+    /// Sort keys by request id.
+    #[serde(skip)]
+    keys: HashMap<u64, SortKey>,
+    /// Ids of requests that were still in progress when they were sorted.
+    #[serde(skip)]
+    unsettled: Vec<u64>,
+    #[serde(skip)]
+    new_header: String,
+    #[serde(skip)]
+    new_query: String,
+}
+
+impl Default for ListLayout {
+    fn default() -> Self {
+        Self {
+            columns: [
+                ColumnKind::Time,
+                ColumnKind::Method,
+                ColumnKind::Path,
+                ColumnKind::Status,
+                ColumnKind::Size,
+                ColumnKind::Duration,
+            ]
+            .into_iter()
+            .map(|kind| ListColumn {
+                width: kind.default_width(),
+                kind,
+            })
+            .collect(),
+            sort_by: None,
+            ascending: true,
+            generation: 0,
+            json_columns: HashMap::new(),
+            order: Vec::new(),
+            order_valid: false,
+            newest: 0,
+            keys: HashMap::new(),
+            unsettled: Vec::new(),
+            new_header: String::new(),
+            new_query: String::new(),
+        }
+    }
+}
+
+impl ListLayout {
+    pub fn has_column(&self, kind: &ColumnKind) -> bool {
+        self.columns.iter().any(|c| &c.kind == kind)
+    }
+
+    pub fn add_column(&mut self, kind: ColumnKind) {
+        if !self.has_column(&kind) {
+            self.columns.push(ListColumn {
+                width: kind.default_width(),
+                kind,
+            });
+            self.generation += 1;
+        }
+    }
+
+    fn remove_column(&mut self, index: usize) {
+        let removed = self.columns.remove(index);
+        if self.sort_by.as_ref() == Some(&removed.kind) {
+            self.sort_by = None;
+            self.order_valid = false;
+        }
+        self.generation += 1;
+    }
+
+    fn move_column(&mut self, from: usize, to: usize) {
+        if from != to && from < self.columns.len() && to < self.columns.len() {
+            let column = self.columns.remove(from);
+            self.columns.insert(to, column);
+            self.generation += 1;
+        }
+    }
+
+    fn toggle_sort(&mut self, kind: &ColumnKind) {
+        if self.sort_by.as_ref() == Some(kind) {
+            self.ascending = !self.ascending;
+        } else {
+            self.sort_by = Some(kind.clone());
+            self.ascending = true;
+        }
+        self.order_valid = false;
+    }
+
+    /// Forgets cached values of requests that were cleared.
+    pub fn clear_cache(&mut self) {
+        self.json_columns.clear();
+        self.order.clear();
+        self.keys.clear();
+        self.unsettled.clear();
+        self.order_valid = false;
+    }
+
+    /// New requests are inserted into the existing order and evicted ones
+    /// removed, so the list is only fully sorted when the sort changes.
+    fn update_order(&mut self, requests: &CaptureStore) {
+        // Requests may be evicted and others arrive in the same frame, so
+        // evicted ids are looked for even when the count did not change
+        let first = requests.first_id();
+        self.order.retain(|&id| id >= first);
+        self.keys.retain(|&id, _| id >= first);
+        self.unsettled.retain(|&id| id >= first);
+        for column in self.json_columns.values_mut().flatten() {
+            column.forget_before(first);
+        }
+        let known = self.newest;
+        if self.order_valid && known == requests.last_id() && self.unsettled.is_empty() {
+            return;
+        }
+        self.newest = requests.last_id();
+        let Some(kind) = self.sort_by.clone() else {
+            self.order = requests.iter().rev().map(|req| req.id).collect();
+            self.order_valid = true;
+            return;
+        };
+
+        if !self.order_valid {
+            self.keys = requests
+                .iter()
+                .map(|req| (req.id, sort_key(&kind, req, &mut self.json_columns)))
+                .collect();
+            let keys = &self.keys;
+            let ascending = self.ascending;
+            self.order = requests.iter().map(|req| req.id).collect();
+            self.order
+                .sort_by(|a, b| compare_keys(&keys[a], &keys[b], ascending));
+            self.unsettled = requests
+                .iter()
+                .filter(|req| !is_settled(req))
+                .map(|req| req.id)
+                .collect();
+        } else {
+            // Requests sorted while they were in progress are sorted again
+            // once they are done
+            let settled: Vec<u64> = self
+                .unsettled
+                .extract_if(.., |id| requests.get(*id).is_none_or(is_settled))
+                .collect();
+            for id in settled {
+                self.order.retain(|&other| other != id);
+                self.keys.remove(&id);
+                if let Some(req) = requests.get(id) {
+                    self.insert_sorted(&kind, req);
+                }
+            }
+            for req in requests.newer_than(known) {
+                self.insert_sorted(&kind, req);
+            }
+        }
+        self.order_valid = true;
+    }
+
+    fn insert_sorted(&mut self, kind: &ColumnKind, req: &HttpRequest) {
+        let key = sort_key(kind, req, &mut self.json_columns);
+        let keys = &self.keys;
+        let ascending = self.ascending;
+        let position = self
+            .order
+            .partition_point(|id| compare_keys(&keys[id], &key, ascending).is_le());
+        self.order.insert(position, req.id);
+        self.keys.insert(req.id, key);
+        if !is_settled(req) {
+            self.unsettled.push(req.id);
+        }
+    }
+}
+
+/// Whether a request's cells are final, i.e. its body arrived or was aborted
+/// and its response was sent.
+fn is_settled(req: &HttpRequest) -> bool {
+    match req.body_state {
+        BodyState::Receiving => false,
+        BodyState::Aborted(_) => true,
+        BodyState::Complete => req.parse_error.is_some() || req.timings.response_end.is_some(),
+    }
+}
+
+fn compare_keys(a: &SortKey, b: &SortKey, ascending: bool) -> Ordering {
+    match (a, b) {
+        (SortKey::Missing, SortKey::Missing) => Ordering::Equal,
+        (SortKey::Missing, _) => Ordering::Greater,
+        (_, SortKey::Missing) => Ordering::Less,
+        (a, b) if ascending => a.cmp(b),
+        (a, b) => b.cmp(a),
+    }
+}
+
+/// Cell values compared as numbers where they are numbers. Missing values
+/// sort last.
+#[derive(PartialEq, PartialOrd)]
+enum SortKey {
+    Number(f64),
+    Text(String),
+    Missing,
+}
+
+impl SortKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.partial_cmp(other).unwrap_or(Ordering::Equal)
+    }
+}
+
+fn sort_key(
+    kind: &ColumnKind,
+    req: &HttpRequest,
+    json_columns: &mut HashMap<String, Result<QueryColumn, String>>,
+) -> SortKey {
+    match kind {
+        ColumnKind::Time => SortKey::Number(req.id as f64),
+        ColumnKind::Size => SortKey::Number(req.body_size as f64),
+        ColumnKind::Duration => req
+            .timings
+            .total()
+            .map_or(SortKey::Missing, |d| SortKey::Number(d.as_secs_f64())),
+        _ => match cell_text(kind, req, json_columns) {
+            None => SortKey::Missing,
+            Some(text) => match text.parse::<f64>() {
+                Ok(number) => SortKey::Number(number),
+                Err(_) => SortKey::Text(text.to_lowercase()),
+            },
+        },
+    }
+}
+
+fn cell_text(
+    kind: &ColumnKind,
+    req: &HttpRequest,
+    json_columns: &mut HashMap<String, Result<QueryColumn, String>>,
+) -> Option<String> {
+    match kind {
+        ColumnKind::Time => Some(req.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)),
+        ColumnKind::Method => Some(req.method.clone()),
+        ColumnKind::Path => Some(req.path.clone()),
+        ColumnKind::Status => match &req.body_state {
+            BodyState::Receiving => Some(String::from("⏳")),
+            BodyState::Aborted(_) => Some(String::from("aborted")),
+            BodyState::Complete => req.response_status.map(|status| status.to_string()),
+        },
+        ColumnKind::Size => Some(format_size(req.body_size)),
+        ColumnKind::Duration => req.timings.total().map(format_duration),
+        ColumnKind::RemoteAddr => Some(req.remote_addr.clone()),
+        ColumnKind::ContentType => header_value(req, "content-type"),
+        ColumnKind::Header(name) => header_value(req, name),
+        ColumnKind::Json(source) => query_column(json_columns, source)
+            .as_mut()
+            .ok()?
+            .value(req)
+            .map(str::to_string),
+    }
+}
+
+fn query_column<'c>(
+    json_columns: &'c mut HashMap<String, Result<QueryColumn, String>>,
+    source: &str,
+) -> &'c mut Result<QueryColumn, String> {
+    json_columns
+        .entry(source.to_string())
+        .or_insert_with(|| QueryColumn::new(source))
+}
+
+fn header_value(req: &HttpRequest, name: &str) -> Option<String> {
+    req.headers
+        .iter()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.clone())
+}
+
+fn format_size(size: usize) -> String {
+    if size < 1024 {
+        format!("{} B", size)
+    } else if size < 1024 * 1024 {
+        format!("{:.1} KB", size as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", size as f64 / (1024.0 * 1024.0))
+    }
+}
 
 pub fn render_request_overview(
     ui: &mut egui::Ui,
-    requests: &[HttpRequest],
-    selected_request: &mut Option<usize>,
-    server_port: u16,
+    requests: &CaptureStore,
+    selected_request: &mut Option<u64>,
+    listen_address: &ListenAddress,
+    layout: &mut ListLayout,
+    timestamps: &TimestampDisplay,
 ) -> bool {
     let mut clear_requests = false;
 
     ui.horizontal(|ui| {
         ui.heading("Requests");
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-            if !requests.is_empty() {
-                if ui.button("🗑 Clear All").clicked() {
-                    clear_requests = true;
-                }
+            if !requests.is_empty() && ui.button("🗑 Clear All").clicked() {
+                clear_requests = true;
             }
+            ui.menu_button("Columns", |ui| render_column_menu(ui, layout));
         });
     });
     ui.separator();
 
-    egui::ScrollArea::vertical().show(ui, |ui| {
-        // Show helpful message when no requests yet
-        if requests.is_empty() {
-            ui.add_space(20.0);
-            ui.vertical_centered(|ui| {
-                ui.label(
-                    egui::RichText::new("No requests yet")
-                        .size(18.0)
-                        .color(egui::Color32::GRAY),
-                );
-                ui.add_space(10.0);
-                ui.label(
-                    egui::RichText::new("Send a request to get started:")
-                        .size(14.0)
-                        .color(egui::Color32::LIGHT_GRAY),
-                );
-                ui.add_space(10.0);
-
-                // Show curl command
-                let curl_command = format!("curl http://localhost:{}", server_port);
-                egui::Frame::new()
-                    .fill(egui::Color32::from_gray(30))
-                    .inner_margin(10.0)
-                    .corner_radius(5.0)
-                    .show(ui, |ui| {
-                        ui.horizontal(|ui| {
-                            ui.label(
-                                egui::RichText::new(&curl_command)
-                                    .monospace()
-                                    .color(egui::Color32::LIGHT_GREEN),
-                            );
-                            if ui
-                                .button("📋")
-                                .on_hover_text("Copy to clipboard")
-                                .clicked()
-                            {
-                                ui.ctx().copy_text(curl_command.clone());
+    // Show helpful message when no requests yet
+    if requests.is_empty() {
+        egui::ScrollArea::vertical().show(ui, |ui| render_empty_state(ui, listen_address));
+        return clear_requests;
+    }
+
+    layout.update_order(requests);
+    timestamps.request_repaint(ui.ctx());
+    // Dates are shown only when they tell requests apart
+    let show_date = match (requests.iter().next(), requests.iter().next_back()) {
+        (Some(first), Some(last)) => {
+            timestamps.date(first.timestamp) != timestamps.date(last.timestamp)
+        }
+        _ => false,
+    };
+    render_table(
+        ui,
+        requests,
+        selected_request,
+        layout,
+        timestamps,
+        show_date,
+    );
+
+    clear_requests
+}
+
+/// Table of requests. Only visible rows are laid out.
+fn render_table(
+    ui: &mut egui::Ui,
+    requests: &CaptureStore,
+    selected_request: &mut Option<u64>,
+    layout: &mut ListLayout,
+    timestamps: &TimestampDisplay,
+    show_date: bool,
+) {
+    let mut sort_clicked = None;
+    let mut moved = None;
+    let mut widths = Vec::new();
+
+    let ListLayout {
+        columns,
+        sort_by,
+        ascending,
+        generation,
+        json_columns,
+        order,
+        ..
+    } = layout;
+
+    let mut table = TableBuilder::new(ui)
+        .id_salt(("request_table", *generation))
+        .striped(true)
+        .resizable(true)
+        .sense(egui::Sense::click())
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center));
+    for column in columns.iter() {
+        table = table.column(Column::initial(column.width).at_least(30.0).clip(true));
+    }
+
+    table
+        .header(20.0, |mut header| {
+            for (i, column) in columns.iter().enumerate() {
+                header.col(|ui| {
+                    let arrow = match sort_by {
+                        Some(kind) if *kind == column.kind => {
+                            if *ascending {
+                                " ⏶"
+                            } else {
+                                " ⏷"
                             }
-                        });
+                        }
+                        _ => "",
+                    };
+                    let error = match &column.kind {
+                        ColumnKind::Json(source) => {
+                            query_column(json_columns, source).as_ref().err()
+                        }
+                        _ => None,
+                    };
+                    let title = match error {
+                        Some(_) => {
+                            egui::RichText::new(format!("⚠ {}{}", column.kind.label(), arrow))
+                                .color(egui::Color32::YELLOW)
+                        }
+                        None => egui::RichText::new(format!("{}{}", column.kind.label(), arrow)),
+                    };
+                    let hover = match error {
+                        Some(e) => format!("Invalid query: {}", e),
+                        None => String::from("Click to sort, drag to move"),
+                    };
+                    let response = ui
+                        .add(
+                            egui::Label::new(title.strong())
+                                .selectable(false)
+                                .truncate()
+                                .sense(egui::Sense::click_and_drag()),
+                        )
+                        .on_hover_text(hover);
+                    // Columns are reordered by dropping one header onto another
+                    response.dnd_set_drag_payload(i);
+                    if let Some(from) = response.dnd_release_payload::<usize>() {
+                        moved = Some((*from, i));
+                    } else if response.clicked() {
+                        sort_clicked = Some(column.kind.clone());
+                    }
+                });
+            }
+        })
+        .body(|body| {
+            widths = body.widths().to_vec();
+            body.rows(ROW_HEIGHT, order.len(), |mut row| {
+                let id = order[row.index()];
+                let Some(req) = requests.get(id) else {
+                    return;
+                };
+                row.set_selected(*selected_request == Some(id));
+
+                for column in columns.iter() {
+                    row.col(|ui| {
+                        let text = match column.kind {
+                            ColumnKind::Time => timestamps.format(req.timestamp, show_date),
+                            _ => cell_text(&column.kind, req, json_columns).unwrap_or_default(),
+                        };
+                        render_cell(ui, &column.kind, req, text);
                     });
+                }
 
-                ui.add_space(10.0);
+                if row.response().clicked() {
+                    *selected_request = Some(id);
+                }
+            });
+        });
+
+    // Widths the user dragged are kept for the next session
+    for (column, width) in layout.columns.iter_mut().zip(widths) {
+        column.width = width;
+    }
+    if let Some(kind) = sort_clicked {
+        layout.toggle_sort(&kind);
+    }
+    if let Some((from, to)) = moved {
+        layout.move_column(from, to);
+    }
+}
+
+fn render_cell(ui: &mut egui::Ui, kind: &ColumnKind, req: &HttpRequest, text: String) {
+    let text = match kind {
+        ColumnKind::Method if req.parse_error.is_some() => {
+            egui::RichText::new(format!("⚠ {}", text)).color(egui::Color32::RED)
+        }
+        ColumnKind::Method => egui::RichText::new(text).color(get_method_color(&req.method)),
+        ColumnKind::Status if matches!(req.body_state, BodyState::Aborted(_)) => {
+            egui::RichText::new(text).color(egui::Color32::RED)
+        }
+        ColumnKind::Status => {
+            let color = match req.response_status {
+                Some(200..=299) => egui::Color32::GREEN,
+                Some(300..=399) => egui::Color32::LIGHT_BLUE,
+                Some(400..=499) => egui::Color32::YELLOW,
+                Some(500..) => egui::Color32::RED,
+                _ => egui::Color32::GRAY,
+            };
+            egui::RichText::new(text).color(color)
+        }
+        ColumnKind::Path => {
+            if let Some(operation) = &req.graphql {
+                // GraphQL operations are shown next to the endpoint
                 ui.label(
-                    egui::RichText::new("Or send a POST request:")
-                        .size(12.0)
-                        .color(egui::Color32::LIGHT_GRAY),
+                    egui::RichText::new(format!("◆ {}", operation))
+                        .color(egui::Color32::from_rgb(229, 53, 171)),
                 );
-                ui.add_space(5.0);
+            }
+            egui::RichText::new(text)
+        }
+        ColumnKind::Json(_) => {
+            egui::RichText::new(text).color(egui::Color32::from_rgb(156, 220, 254))
+        }
+        ColumnKind::Size if req.body_state == BodyState::Receiving => {
+            // Live byte counter of an upload in progress
+            egui::RichText::new(format!("⏳ {}", text)).color(egui::Color32::LIGHT_BLUE)
+        }
+        ColumnKind::Size if req.truncated.is_some() => {
+            egui::RichText::new(format!("✂ {}", text)).color(egui::Color32::YELLOW)
+        }
+        ColumnKind::Time | ColumnKind::Size | ColumnKind::Duration => {
+            egui::RichText::new(text).weak()
+        }
+        _ => egui::RichText::new(text),
+    };
+    ui.add(egui::Label::new(text).selectable(false).truncate());
+}
 
-                let post_command = format!(
-                    "curl -X POST http://localhost:{} -d '{{\"key\":\"value\"}}'",
-                    server_port
-                );
-                egui::Frame::new()
-                    .fill(egui::Color32::from_gray(30))
-                    .inner_margin(10.0)
-                    .corner_radius(5.0)
-                    .show(ui, |ui| {
-                        ui.horizontal(|ui| {
-                            ui.label(
-                                egui::RichText::new(&post_command)
-                                    .monospace()
-                                    .color(egui::Color32::LIGHT_GREEN),
-                            );
-                            if ui
-                                .button("📋")
-                                .on_hover_text("Copy to clipboard")
-                                .clicked()
-                            {
-                                ui.ctx().copy_text(post_command.clone());
-                            }
-                        });
-                    });
+fn render_column_menu(ui: &mut egui::Ui, layout: &mut ListLayout) {
+    ui.set_min_width(250.0);
+    let mut remove = None;
+    let mut moved = None;
+    for (i, column) in layout.columns.iter().enumerate() {
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(i > 0, egui::Button::new("⏶"))
+                .on_hover_text("Move left")
+                .clicked()
+            {
+                moved = Some((i, i - 1));
+            }
+            if ui
+                .add_enabled(i + 1 < layout.columns.len(), egui::Button::new("⏷"))
+                .on_hover_text("Move right")
+                .clicked()
+            {
+                moved = Some((i, i + 1));
+            }
+            if ui.button("🗑").on_hover_text("Remove column").clicked() {
+                remove = Some(i);
+            }
+            let label = egui::RichText::new(column.kind.label());
+            ui.label(match column.kind {
+                ColumnKind::Header(_) | ColumnKind::Json(_) => label.monospace(),
+                _ => label,
             });
-        }
+        });
+    }
+    if let Some(i) = remove {
+        layout.remove_column(i);
+    }
+    if let Some((from, to)) = moved {
+        layout.move_column(from, to);
+    }
 
-        for (idx, req) in requests.iter().enumerate().rev() {
-            let actual_idx = requests.len() - 1 - (requests.len() - 1 - idx);
-            let is_selected = *selected_request == Some(actual_idx);
+    ui.separator();
+    for kind in ColumnKind::BUILT_IN {
+        if !layout.has_column(&kind) && ui.button(format!("➕ {}", kind.label())).clicked() {
+            layout.add_column(kind);
+        }
+    }
 
-            let response = ui.selectable_label(
-                is_selected,
-                egui::RichText::new(format!(
-                    "{} {} {}",
-                    req.timestamp.split_whitespace().nth(1).unwrap_or(""),
-                    req.method,
-                    req.path
-                )),
-            );
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut layout.new_header)
+                .desired_width(150.0)
+                .hint_text("Header name"),
+        );
+        let name = layout.new_header.trim().to_string();
+        if ui
+            .add_enabled(!name.is_empty(), egui::Button::new("➕ Header"))
+            .clicked()
+        {
+            layout.add_column(ColumnKind::Header(name));
+            layout.new_header.clear();
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut layout.new_query)
+                .font(egui::TextStyle::Monospace)
+                .desired_width(150.0)
+                .hint_text("$.event.type"),
+        );
+        let query = Query::parse(&layout.new_query);
+        let response = ui.add_enabled(query.is_ok(), egui::Button::new("➕ JSON Field"));
+        if response.clicked() {
+            layout.add_column(ColumnKind::Json(layout.new_query.trim().to_string()));
+            layout.new_query.clear();
+        }
+        if let Err(e) = query
+            && !layout.new_query.trim().is_empty()
+        {
+            response.on_disabled_hover_text(e);
+        }
+    });
+}
 
-            if response.clicked() {
-                *selected_request = Some(actual_idx);
-            }
+fn render_empty_state(ui: &mut egui::Ui, listen_address: &ListenAddress) {
+    ui.add_space(20.0);
+    ui.vertical_centered(|ui| {
+        ui.label(
+            egui::RichText::new("No requests yet")
+                .size(18.0)
+                .color(egui::Color32::GRAY),
+        );
+        ui.add_space(10.0);
+        ui.label(
+            egui::RichText::new("Send a request to get started:")
+                .size(14.0)
+                .color(egui::Color32::LIGHT_GRAY),
+        );
+        ui.add_space(10.0);
 
-            // Show method color indicator and basic info
-            ui.horizontal(|ui| {
-                ui.add_space(10.0);
-                ui.label(
-                    egui::RichText::new(&req.method)
-                        .small()
-                        .color(get_method_color(&req.method)),
-                );
-                ui.label(egui::RichText::new(&req.remote_addr).small().weak());
-                if !req.query_params.is_empty() {
+        // Show curl command
+        let curl_command = format!("curl {}", listen_address.curl_target());
+        egui::Frame::new()
+            .fill(egui::Color32::from_gray(30))
+            .inner_margin(10.0)
+            .corner_radius(5.0)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
                     ui.label(
-                        egui::RichText::new(format!("{} params", req.query_params.len()))
-                            .small()
-                            .weak(),
+                        egui::RichText::new(&curl_command)
+                            .monospace()
+                            .color(egui::Color32::LIGHT_GREEN),
                     );
-                }
-                if req.body_size > 0 {
+                    if ui.button("📋").on_hover_text("Copy to clipboard").clicked() {
+                        ui.ctx().copy_text(curl_command.clone());
+                    }
+                });
+            });
+
+        ui.add_space(10.0);
+        ui.label(
+            egui::RichText::new("Or send a POST request:")
+                .size(12.0)
+                .color(egui::Color32::LIGHT_GRAY),
+        );
+        ui.add_space(5.0);
+
+        let post_command = format!(
+            "curl -X POST {} -d '{{\"key\":\"value\"}}'",
+            listen_address.curl_target()
+        );
+        egui::Frame::new()
+            .fill(egui::Color32::from_gray(30))
+            .inner_margin(10.0)
+            .corner_radius(5.0)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
                     ui.label(
-                        egui::RichText::new(format!("{} bytes", req.body_size))
-                            .small()
-                            .weak(),
+                        egui::RichText::new(&post_command)
+                            .monospace()
+                            .color(egui::Color32::LIGHT_GREEN),
                     );
-                }
+                    if ui.button("📋").on_hover_text("Copy to clipboard").clicked() {
+                        ui.ctx().copy_text(post_command.clone());
+                    }
+                });
             });
-
-            ui.separator();
-        }
     });
-
-    clear_requests
 }
 
 fn get_method_color(method: &str) -> egui::Color32 {
@@ -156,3 +754,34 @@ fn get_method_color(method: &str) -> egui::Color32 {
         _ => egui::Color32::WHITE,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicted_requests_leave_the_order_when_others_arrive() {
+        let mut store = CaptureStore::default();
+        store.limits.max_requests = 2;
+        // Settled, so they are not sorted again when they finish
+        let request = || HttpRequest {
+            parse_error: Some(String::from("bad request")),
+            ..Default::default()
+        };
+        store.push(request());
+        store.push(request());
+
+        let mut layout = ListLayout {
+            sort_by: Some(ColumnKind::Method),
+            ..Default::default()
+        };
+        layout.update_order(&store);
+        assert_eq!(layout.order, [1, 2]);
+
+        // One evicted and one added: the count stays the same
+        store.push(request());
+        layout.update_order(&store);
+        assert_eq!(layout.order, [2, 3]);
+        assert!(!layout.keys.contains_key(&1));
+    }
+}
@@ -1,3 +1,5 @@
+use crate::cookies::{self, ResponseCookie};
+use crate::grpc;
 use eframe::egui;
 
 #[derive(Clone, Debug)]
@@ -5,6 +7,11 @@ pub struct ResponseConfig {
     pub status_code: u16,
     pub status_code_input: String,
     pub response_body: String,
+    /// Sent with `Set-Cookie` on every HTTP response.
+    pub cookies: Vec<ResponseCookie>,
+    /// Sent in the `grpc-status` trailer to gRPC requests.
+    pub grpc_status: u32,
+    pub grpc_message: String,
 }
 
 impl Default for ResponseConfig {
@@ -13,6 +20,9 @@ impl Default for ResponseConfig {
             status_code: 200,
             status_code_input: "200".to_string(),
             response_body: "OK\n".to_string(),
+            cookies: Vec::new(),
+            grpc_status: 0,
+            grpc_message: String::new(),
         }
     }
 }
@@ -153,6 +163,51 @@ pub fn render_response_config(ui: &mut egui::Ui, config: &mut ResponseConfig) {
 
         ui.add_space(20.0);
 
+        // Set-Cookie section
+        ui.label(egui::RichText::new("Set-Cookie").heading());
+        ui.add_space(5.0);
+
+        cookies::render_cookie_jar(ui, &mut config.cookies);
+
+        ui.add_space(20.0);
+
+        // gRPC section
+        ui.label(egui::RichText::new("gRPC").heading());
+        ui.add_space(5.0);
+
+        ui.horizontal(|ui| {
+            ui.label("Status:");
+            egui::ComboBox::from_id_salt("grpc_status")
+                .selected_text(format!(
+                    "{} {}",
+                    config.grpc_status,
+                    grpc::status_name(config.grpc_status)
+                ))
+                .show_ui(ui, |ui| {
+                    for (code, name) in grpc::STATUS_CODES {
+                        ui.selectable_value(&mut config.grpc_status, code, format!("{} {}", code, name));
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Message:");
+            ui.add(
+                egui::TextEdit::singleline(&mut config.grpc_message)
+                    .desired_width(300.0)
+                    .hint_text("grpc-message (optional)"),
+            );
+        });
+        ui.add_space(5.0);
+        ui.label(
+            egui::RichText::new(
+                "Requests with an application/grpc content type get HTTP 200 with this status in the trailers. An OK status returns one empty message.",
+            )
+            .small()
+            .weak(),
+        );
+
+        ui.add_space(20.0);
+
         // Info box
         ui.separator();
         ui.add_space(5.0);
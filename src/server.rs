@@ -0,0 +1,930 @@
+use crate::HttpRequest;
+use crate::capture_store::{BodyCapture, BodyChunk, BodyState, CaptureStore};
+use crate::connection_io::{ConnectionCounters, ConnectionIo, RawCapture};
+use crate::connections::{self, CloseReason, ConnectionInfo};
+use crate::content_encoding;
+use crate::graphql;
+use crate::grpc;
+use crate::listen_address::{self, ListenAddress};
+use crate::malformed;
+use crate::response_config;
+use crate::settings::ParserOptions;
+use crate::timing::RequestTimings;
+use crate::url_encoded;
+use chrono::Utc;
+use eframe::egui;
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::header::{HeaderMap, HeaderValue};
+use hyper::server::conn::{http1, http2};
+use hyper::service::service_fn;
+use hyper::{Request, Response, body::Incoming};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+
+/// How long open connections get to finish in-flight requests on rebind or stop
+/// before they are closed forcibly.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How often the GUI is repainted while a request body is arriving.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The server state requested by the GUI.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServerTarget {
+    pub address: ListenAddress,
+    pub running: bool,
+    /// Bumped to force a restart on the same address.
+    pub generation: u64,
+}
+
+/// State shared between the GUI and the server thread.
+#[derive(Clone)]
+pub struct ServerShared {
+    pub requests: Arc<Mutex<CaptureStore>>,
+    pub response_config: Arc<Mutex<response_config::ResponseConfig>>,
+    pub status: Arc<Mutex<String>>,
+    pub last_working_address: Arc<Mutex<ListenAddress>>,
+    pub active_connections: Arc<AtomicUsize>,
+    pub connections: Arc<Mutex<Vec<ConnectionInfo>>>,
+    pub next_connection_id: Arc<AtomicU64>,
+    pub parser_options: Arc<Mutex<ParserOptions>>,
+    /// Set once the GUI is up; without input it only repaints when asked.
+    pub gui: Arc<OnceLock<egui::Context>>,
+}
+
+impl ServerShared {
+    /// Asks the GUI to show changed requests, connections or status.
+    pub fn notify_gui(&self) {
+        if let Some(ctx) = self.gui.get() {
+            ctx.request_repaint();
+        }
+    }
+}
+
+/// GUI-side handle for controlling the server lifecycle.
+pub struct ServerHandle {
+    control: watch::Sender<ServerTarget>,
+}
+
+impl ServerHandle {
+    pub fn target(&self) -> ServerTarget {
+        self.control.borrow().clone()
+    }
+
+    pub fn rebind(&self, address: ListenAddress) {
+        self.control.send_if_modified(|target| {
+            let changed = target.address != address;
+            target.address = address;
+            changed
+        });
+    }
+
+    pub fn start(&self) {
+        self.control
+            .send_if_modified(|target| !std::mem::replace(&mut target.running, true));
+    }
+
+    pub fn stop(&self) {
+        self.control
+            .send_if_modified(|target| std::mem::replace(&mut target.running, false));
+    }
+
+    pub fn restart(&self) {
+        self.control.send_modify(|target| {
+            target.running = true;
+            target.generation += 1;
+        });
+    }
+}
+
+/// Spawns the server thread, which follows the target published through the
+/// returned handle.
+pub fn spawn(initial_address: ListenAddress, shared: ServerShared) -> ServerHandle {
+    let (control, control_rx) = watch::channel(ServerTarget {
+        address: initial_address,
+        running: true,
+        generation: 0,
+    });
+
+    std::thread::spawn(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(supervise(control_rx, shared));
+    });
+
+    ServerHandle { control }
+}
+
+async fn supervise(mut control: watch::Receiver<ServerTarget>, shared: ServerShared) {
+    loop {
+        let target = control.borrow_and_update().clone();
+
+        if target.running {
+            match run_server(&target.address, &shared, &mut control).await {
+                Ok(()) => {
+                    println!("Server on {} stopped", target.address);
+                    // The GUI has exited
+                    if control.has_changed().is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("Server error: {}", e);
+                    *shared.status.lock().unwrap() = format!("Error: {}", e);
+                    shared.notify_gui();
+                }
+            }
+        } else {
+            *shared.status.lock().unwrap() = String::from("Stopped");
+            shared.notify_gui();
+        }
+
+        // Wait for the GUI to request something different
+        if control.changed().await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Serves `address` until the target changes, then drains open connections.
+async fn run_server(
+    address: &ListenAddress,
+    shared: &ServerShared,
+    control: &mut watch::Receiver<ServerTarget>,
+) -> Result<(), String> {
+    let listener = Listener::bind(address)
+        .await
+        .map_err(|e| format!("Failed to bind to {}: {}", address, e))?;
+
+    // Successfully bound - update last working address
+    *shared.last_working_address.lock().unwrap() = address.clone();
+    *shared.status.lock().unwrap() = format!("Listening on {}", address);
+    shared.notify_gui();
+    println!("HTTP Server listening on {}", address);
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let mut connections = JoinSet::new();
+
+    loop {
+        tokio::select! {
+            _ = control.changed() => break,
+            Some(_) = connections.join_next(), if !connections.is_empty() => {}
+            accepted = listener.accept() => match accepted {
+                Ok((stream, remote_addr)) => {
+                    connections.spawn(serve_connection(
+                        stream,
+                        remote_addr,
+                        shared.clone(),
+                        shutdown_rx.clone(),
+                    ));
+                }
+                Err(e) => {
+                    eprintln!("Error accepting connection: {}", e);
+                }
+            },
+        }
+    }
+
+    // Stop accepting and ask open connections to finish their current request
+    drop(listener);
+    let _ = shutdown_tx.send(true);
+    if !connections.is_empty() {
+        *shared.status.lock().unwrap() = format!("Draining {} connection(s)...", connections.len());
+        shared.notify_gui();
+        let drained = tokio::time::timeout(DRAIN_TIMEOUT, async {
+            while connections.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            eprintln!("Closing {} connection(s) forcibly", connections.len());
+            connections.shutdown().await;
+        }
+    }
+
+    Ok(())
+}
+
+/// A bound listener for either transport.
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener, std::path::PathBuf),
+}
+
+/// An accepted connection from either transport.
+enum Stream {
+    Tcp(tokio::net::TcpStream),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixStream),
+}
+
+impl Stream {
+    fn transport(&self) -> &'static str {
+        match self {
+            Stream::Tcp(_) => "TCP",
+            #[cfg(unix)]
+            Stream::Unix(_) => "Unix",
+        }
+    }
+}
+
+impl Listener {
+    async fn bind(address: &ListenAddress) -> std::io::Result<Self> {
+        match address {
+            ListenAddress::Tcp(addr) => Ok(Listener::Tcp(listen_address::bind_tcp(*addr)?)),
+            #[cfg(unix)]
+            ListenAddress::Unix(path) => {
+                use std::os::unix::fs::FileTypeExt;
+
+                // Remove a stale socket left behind by a previous run, but
+                // not one another process is still listening on
+                if let Ok(metadata) = std::fs::symlink_metadata(path)
+                    && metadata.file_type().is_socket()
+                {
+                    match tokio::net::UnixStream::connect(path).await {
+                        Ok(_) => {
+                            return Err(std::io::Error::new(
+                                std::io::ErrorKind::AddrInUse,
+                                format!("{} is in use by another process", path.display()),
+                            ));
+                        }
+                        Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                            std::fs::remove_file(path)?;
+                        }
+                        Err(_) => {}
+                    }
+                }
+                let listener = tokio::net::UnixListener::bind(path)?;
+                Ok(Listener::Unix(listener, path.clone()))
+            }
+            #[cfg(not(unix))]
+            ListenAddress::Unix(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "Unix domain sockets are not supported on this platform",
+            )),
+        }
+    }
+
+    async fn accept(&self) -> std::io::Result<(Stream, String)> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, remote_addr) = listener.accept().await?;
+                Ok((Stream::Tcp(stream), remote_addr.to_string()))
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener, path) => {
+                let (stream, _) = listener.accept().await?;
+                // Unix peers are usually unnamed, identify them by process instead
+                let remote_addr = match stream.peer_cred().ok().and_then(|c| c.pid()) {
+                    Some(pid) => format!("unix:{} (pid {})", path.display(), pid),
+                    None => format!("unix:{}", path.display()),
+                };
+                Ok((Stream::Unix(stream), remote_addr))
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Listener {
+    fn drop(&mut self) {
+        if let Listener::Unix(_, path) = self {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Tracks a connection for its whole lifetime. Dropping the guard, including
+/// when the task is aborted, decrements the active count and records the close.
+struct ConnectionGuard {
+    id: u64,
+    shared: ServerShared,
+    close_reason: Option<CloseReason>,
+}
+
+impl ConnectionGuard {
+    fn new(id: u64, shared: &ServerShared) -> Self {
+        shared.active_connections.fetch_add(1, Ordering::SeqCst);
+        Self {
+            id,
+            shared: shared.clone(),
+            close_reason: None,
+        }
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.shared
+            .active_connections
+            .fetch_sub(1, Ordering::SeqCst);
+
+        let mut connections = self.shared.connections.lock().unwrap();
+        if let Some(conn) = connections.iter_mut().rev().find(|c| c.id == self.id) {
+            conn.closed = Some(Instant::now());
+            // A guard dropped without a reason belongs to a forcibly closed connection
+            conn.close_reason = Some(
+                self.close_reason
+                    .take()
+                    .unwrap_or(CloseReason::ServerShutdown),
+            );
+        }
+        drop(connections);
+        self.shared.notify_gui();
+    }
+}
+
+async fn serve_connection(
+    stream: Stream,
+    remote_addr: String,
+    shared: ServerShared,
+    shutdown: watch::Receiver<bool>,
+) {
+    let id = shared.next_connection_id.fetch_add(1, Ordering::SeqCst) + 1;
+    let counters = Arc::new(ConnectionCounters::default());
+    let max_connections = shared.requests.lock().unwrap().limits.max_requests;
+    {
+        let mut connections = shared.connections.lock().unwrap();
+        connections.push(ConnectionInfo {
+            id,
+            remote_addr: remote_addr.clone(),
+            transport: stream.transport(),
+            protocol: String::new(),
+            opened_at: Utc::now(),
+            opened: Instant::now(),
+            closed: None,
+            close_reason: None,
+            request_count: 0,
+            counters: Arc::clone(&counters),
+        });
+        connections::enforce_limit(&mut connections, max_connections);
+    }
+    let guard = ConnectionGuard::new(id, &shared);
+    shared.notify_gui();
+    let raw = Arc::new(Mutex::new(RawCapture::default()));
+
+    match stream {
+        Stream::Tcp(stream) => {
+            let io = ConnectionIo::new(stream, counters, Arc::clone(&raw));
+            serve_io(io, remote_addr, guard, raw, shutdown).await
+        }
+        #[cfg(unix)]
+        Stream::Unix(stream) => {
+            let io = ConnectionIo::new(stream, counters, Arc::clone(&raw));
+            serve_io(io, remote_addr, guard, raw, shutdown).await
+        }
+    }
+}
+
+async fn serve_io<S>(
+    mut io: ConnectionIo<S>,
+    remote_addr: String,
+    mut guard: ConnectionGuard,
+    raw: Arc<Mutex<RawCapture>>,
+    mut shutdown: watch::Receiver<bool>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    // HTTP/2 is only served to clients with prior knowledge (h2c), like gRPC.
+    // A client that sends nothing must not hold up a server shutdown.
+    let detected = tokio::select! {
+        detected = io.detect_http2() => detected,
+        _ = shutdown.wait_for(|stop| *stop) => {
+            guard.close_reason = Some(CloseReason::ServerShutdown);
+            return;
+        }
+    };
+    let use_http2 = match detected {
+        Ok(use_http2) => use_http2,
+        Err(err) => {
+            guard.close_reason = Some(CloseReason::Error(err.to_string()));
+            return;
+        }
+    };
+    if use_http2 {
+        raw.lock().unwrap().disable();
+    }
+    let io = TokioIo::new(io);
+
+    let connection_id = guard.id;
+    let shared = guard.shared.clone();
+    let parser_options = shared.parser_options.lock().unwrap().clone();
+    let service = {
+        let remote_addr = remote_addr.clone();
+        let raw = Arc::clone(&raw);
+        let shared = shared.clone();
+        service_fn(move |req| {
+            handle_request(
+                req,
+                remote_addr.clone(),
+                connection_id,
+                Arc::clone(&raw),
+                shared.clone(),
+            )
+        })
+    };
+
+    let (result, shutting_down) = if use_http2 {
+        let conn = http2::Builder::new(TokioExecutor::new()).serve_connection(io, service);
+        drive_connection(conn, &mut shutdown, |conn| conn.graceful_shutdown()).await
+    } else {
+        let conn = http1::Builder::new()
+            .allow_multiple_spaces_in_request_line_delimiters(
+                parser_options.allow_multiple_spaces_in_request_line,
+            )
+            .ignore_invalid_headers(parser_options.ignore_invalid_headers)
+            .serve_connection(io, service);
+        drive_connection(conn, &mut shutdown, |conn| conn.graceful_shutdown()).await
+    };
+
+    guard.close_reason = Some(match result {
+        Ok(()) if shutting_down => CloseReason::ServerShutdown,
+        Ok(()) => CloseReason::ClientClosed,
+        Err(err) => {
+            eprintln!("Error serving connection: {:?}", err);
+            record_malformed_request(
+                &err,
+                remote_addr,
+                connection_id,
+                &raw,
+                &shared,
+                &parser_options,
+            );
+            CloseReason::Error(err.to_string())
+        }
+    });
+}
+
+/// Runs a connection until it closes, shutting it down gracefully once the
+/// server stops. Returns the result and whether the server closed it.
+async fn drive_connection<C>(
+    conn: C,
+    shutdown: &mut watch::Receiver<bool>,
+    graceful_shutdown: impl FnOnce(Pin<&mut C>),
+) -> (Result<(), hyper::Error>, bool)
+where
+    C: Future<Output = Result<(), hyper::Error>>,
+{
+    tokio::pin!(conn);
+    tokio::select! {
+        result = conn.as_mut() => (result, false),
+        _ = async { shutdown.wait_for(|stop| *stop).await.map(|_| ()) } => {
+            // Let in-flight requests finish, then close
+            graceful_shutdown(conn.as_mut());
+            (conn.await, true)
+        }
+    }
+}
+
+/// Lists the bytes of a request hyper rejected, so broken clients show up in
+/// the GUI instead of only on stderr.
+fn record_malformed_request(
+    err: &hyper::Error,
+    remote_addr: String,
+    connection_id: u64,
+    raw: &Mutex<RawCapture>,
+    shared: &ServerShared,
+    parser_options: &ParserOptions,
+) {
+    let Some(kind) = malformed::request_error_kind(err) else {
+        return;
+    };
+    let (raw, raw_size) = raw.lock().unwrap().take_all();
+    if raw.is_empty() {
+        return;
+    }
+
+    let parsed = malformed::parse_leniently(&raw, parser_options);
+    let query_params = parsed
+        .target
+        .split_once('?')
+        .map(|(_, query)| url_encoded::parse_pairs(query, true))
+        .unwrap_or_default();
+    let body_size = parsed.body.len();
+
+    if let Some(conn) = shared
+        .connections
+        .lock()
+        .unwrap()
+        .iter_mut()
+        .rev()
+        .find(|c| c.id == connection_id)
+    {
+        conn.request_count += 1;
+    }
+
+    shared.requests.lock().unwrap().push(HttpRequest {
+        id: 0,
+        timestamp: Utc::now(),
+        method: parsed.method,
+        path: parsed.target,
+        query_params,
+        headers: parsed.headers,
+        trailers: Vec::new(),
+        remote_addr,
+        connection_id,
+        body: parsed.body,
+        body_size,
+        body_state: BodyState::Complete,
+        chunks: Vec::new(),
+        truncated: None,
+        decoded_body: None,
+        decode_error: None,
+        raw,
+        raw_size,
+        parse_error: Some(format!("{}: {}", kind, err)),
+        graphql: None,
+        response_status: None,
+        timings: RequestTimings::default(),
+    });
+    shared.notify_gui();
+}
+
+/// Marks a request as aborted if its handler ends before the body is
+/// complete, including when the connection is closed mid-upload.
+struct ReceivingGuard {
+    id: u64,
+    shared: ServerShared,
+    reason: Option<String>,
+    done: bool,
+}
+
+impl Drop for ReceivingGuard {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        let reason = self
+            .reason
+            .take()
+            .unwrap_or_else(|| String::from("connection closed"));
+        self.shared.requests.lock().unwrap().update(self.id, |req| {
+            req.body_state = BodyState::Aborted(reason);
+        });
+        self.shared.notify_gui();
+    }
+}
+
+async fn handle_request(
+    req: Request<Incoming>,
+    remote_addr: String,
+    connection_id: u64,
+    raw: Arc<Mutex<RawCapture>>,
+    shared: ServerShared,
+) -> Result<Response<BoxBody<Bytes, Infallible>>, hyper::Error> {
+    let received = Instant::now();
+    let timestamp = Utc::now();
+    let method = req.method().to_string();
+
+    let mut timings = RequestTimings {
+        first_byte: raw.lock().unwrap().first_byte(),
+        headers: Some(received),
+        ..Default::default()
+    };
+    if let Some(conn) = shared
+        .connections
+        .lock()
+        .unwrap()
+        .iter_mut()
+        .rev()
+        .find(|c| c.id == connection_id)
+    {
+        conn.request_count += 1;
+        if conn.protocol.is_empty() {
+            conn.protocol = format!("{:?}", req.version());
+        }
+        if conn.request_count == 1 {
+            timings.accepted = Some(conn.opened);
+        }
+    }
+
+    // Capture full URI including query parameters
+    let path = if let Some(query) = req.uri().query() {
+        format!("{}?{}", req.uri().path(), query)
+    } else {
+        req.uri().path().to_string()
+    };
+
+    // Parse query parameters
+    let query_params = req
+        .uri()
+        .query()
+        .map(|query| url_encoded::parse_pairs(query, true))
+        .unwrap_or_default();
+
+    let headers: Vec<(String, String)> = req
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                value.to_str().unwrap_or("<binary>").to_string(),
+            )
+        })
+        .collect();
+
+    // Body framing, used to find where this request ends in the raw bytes
+    let chunked = req
+        .headers()
+        .get_all(hyper::header::TRANSFER_ENCODING)
+        .iter()
+        .any(|v| {
+            v.to_str()
+                .is_ok_and(|v| v.to_ascii_lowercase().contains("chunked"))
+        });
+    let content_length = req
+        .headers()
+        .get(hyper::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<usize>().ok());
+
+    let is_grpc = grpc::is_grpc(
+        req.headers()
+            .get(hyper::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or(""),
+    );
+    let is_http2 = req.version() == hyper::Version::HTTP_2;
+
+    // The request is listed as soon as its head has arrived
+    let encodings = content_encoding::encodings(&headers);
+    let (limits, id) = {
+        let mut requests = shared.requests.lock().unwrap();
+        let id = requests.push(HttpRequest {
+            id: 0,
+            timestamp,
+            method,
+            path,
+            query_params,
+            headers,
+            trailers: Vec::new(),
+            remote_addr,
+            connection_id,
+            body: Vec::new(),
+            body_size: 0,
+            body_state: BodyState::Receiving,
+            chunks: Vec::new(),
+            truncated: None,
+            decoded_body: None,
+            decode_error: None,
+            raw: Vec::new(),
+            raw_size: 0,
+            parse_error: None,
+            graphql: None,
+            response_status: None,
+            timings,
+        });
+        (requests.limits.clone(), id)
+    };
+    shared.notify_gui();
+    let mut guard = ReceivingGuard {
+        id,
+        shared: shared.clone(),
+        reason: None,
+        done: false,
+    };
+
+    // Stream the body into the capture, keeping at most the body size limit
+    // in memory
+    let mut capture = BodyCapture::new(&limits);
+    let mut trailers = Vec::new();
+    let mut body = req.into_body();
+    let mut last_notify = Instant::now();
+    while let Some(frame) = body.frame().await {
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                guard.reason = Some(match std::error::Error::source(&e) {
+                    Some(source) => format!("{}: {}", e, source),
+                    None => e.to_string(),
+                });
+                return Err(e);
+            }
+        };
+        match frame.into_data() {
+            Ok(data) => {
+                let chunk = BodyChunk {
+                    at: received.elapsed(),
+                    len: data.len(),
+                };
+                let (kept, rest) = capture.push(&data);
+                let needs_prefix = capture.needs_prefix(rest);
+                let total = capture.total();
+                let prefix = shared.requests.lock().unwrap().update(id, |req| {
+                    req.body.extend_from_slice(kept);
+                    req.body_size = total;
+                    req.chunks.push(chunk);
+                    needs_prefix.then(|| req.body.clone())
+                });
+                capture.spill(prefix.flatten(), rest).await;
+                if last_notify.elapsed() >= PROGRESS_INTERVAL {
+                    last_notify = Instant::now();
+                    shared.notify_gui();
+                }
+            }
+            Err(frame) => {
+                if let Some(fields) = frame.trailers_ref() {
+                    trailers = fields
+                        .iter()
+                        .map(|(name, value)| {
+                            (
+                                name.to_string(),
+                                value.to_str().unwrap_or("<binary>").to_string(),
+                            )
+                        })
+                        .collect();
+                }
+            }
+        }
+    }
+    let body_complete = Instant::now();
+    let truncated = capture.finish().await;
+
+    // Decoded and summarised outside the lock, from a copy of the parts
+    // that matter; a truncated body cannot be decompressed
+    let stored = shared
+        .requests
+        .lock()
+        .unwrap()
+        .get(id)
+        .map(|req| HttpRequest {
+            method: req.method.clone(),
+            headers: req.headers.clone(),
+            query_params: req.query_params.clone(),
+            body: req.body.clone(),
+            ..Default::default()
+        });
+    let (decoded_body, decode_error, graphql) = match stored {
+        Some(mut stored) => {
+            let (decoded_body, decode_error) = if truncated.is_none() && !encodings.is_empty() {
+                match content_encoding::decode(&stored.headers, &stored.body) {
+                    Some(Ok(decoded)) => (Some(decoded), None),
+                    Some(Err(e)) => (None, Some(e)),
+                    None => (None, None),
+                }
+            } else {
+                (None, None)
+            };
+            stored.decoded_body = decoded_body;
+            let graphql = graphql::summary(&stored);
+            (stored.decoded_body, decode_error, graphql)
+        }
+        None => (None, None, None),
+    };
+
+    // The whole request has been read from the socket now. HTTP/2 frames of
+    // concurrent requests interleave, so those are not captured.
+    let (raw, raw_size) = if is_http2 {
+        (Vec::new(), 0)
+    } else {
+        raw.lock().unwrap().take_request(chunked, content_length)
+    };
+
+    // Build response using configured status code and body
+    let config = shared.response_config.lock().unwrap();
+    let response_body = config.response_body.clone();
+    let status_code = config.status_code;
+    let grpc_status = config.grpc_status;
+    let grpc_message = config.grpc_message.clone();
+    let set_cookies: Vec<String> = config
+        .cookies
+        .iter()
+        .filter(|cookie| cookie.enabled)
+        .filter_map(|cookie| cookie.to_header().ok())
+        .collect();
+    drop(config); // Release lock early
+
+    // gRPC calls always succeed at the HTTP level
+    let response_status = if is_grpc { 200 } else { status_code };
+    let spill_path = truncated.as_ref().and_then(|t| t.spill_path.clone());
+    let stored = shared.requests.lock().unwrap().update(id, |req| {
+        req.trailers = trailers;
+        req.truncated = truncated;
+        req.decoded_body = decoded_body;
+        req.decode_error = decode_error;
+        req.raw = raw;
+        req.raw_size = raw_size;
+        req.body_state = BodyState::Complete;
+        req.graphql = graphql;
+        req.response_status = Some(response_status);
+        req.timings.body_complete = Some(body_complete);
+        req.timings.response_start = Some(Instant::now());
+    });
+    if stored.is_none()
+        && let Some(path) = spill_path
+    {
+        // Evicted while the body was arriving
+        let _ = std::fs::remove_file(path);
+    }
+    guard.done = true;
+    shared.notify_gui();
+
+    let response = if is_grpc {
+        grpc_response(grpc_status, &grpc_message)
+    } else {
+        // Build the response - this shouldn't fail with valid status codes
+        let mut builder = Response::builder().status(status_code);
+        for cookie in set_cookies {
+            builder = builder.header(hyper::header::SET_COOKIE, cookie);
+        }
+        builder
+            .body(Full::new(Bytes::from(response_body)).boxed())
+            .unwrap_or_else(|e| {
+                eprintln!("Error building response: {}", e);
+                // Fallback to a simple 200 OK response
+                Response::new(Full::new(Bytes::from("OK\n")).boxed())
+            })
+    };
+
+    Ok(response.map(|body| TimedBody { body, id, shared }.boxed()))
+}
+
+/// A response body that records when it was sent in full, or dropped
+/// because the connection closed.
+struct TimedBody {
+    body: BoxBody<Bytes, Infallible>,
+    id: u64,
+    shared: ServerShared,
+}
+
+impl hyper::body::Body for TimedBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Result<hyper::body::Frame<Bytes>, Infallible>>> {
+        Pin::new(&mut self.body).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> hyper::body::SizeHint {
+        self.body.size_hint()
+    }
+}
+
+impl Drop for TimedBody {
+    fn drop(&mut self) {
+        let now = Instant::now();
+        self.shared.requests.lock().unwrap().update(self.id, |req| {
+            req.timings.response_end = Some(now);
+        });
+        self.shared.notify_gui();
+    }
+}
+
+/// A unary gRPC response with the configured status in the trailers. Calls
+/// that succeed get one empty message, which decodes as any message type with
+/// all fields at their defaults.
+fn grpc_response(status: u32, message: &str) -> Response<BoxBody<Bytes, Infallible>> {
+    let mut trailers = HeaderMap::new();
+    trailers.insert("grpc-status", HeaderValue::from(status));
+    if !message.is_empty()
+        && let Ok(value) = HeaderValue::from_str(&grpc::encode_message(message))
+    {
+        trailers.insert("grpc-message", value);
+    }
+
+    let body = if status == 0 {
+        Bytes::from_static(&[0, 0, 0, 0, 0])
+    } else {
+        Bytes::new()
+    };
+    Response::builder()
+        .header(hyper::header::CONTENT_TYPE, "application/grpc")
+        .body(
+            Full::new(body)
+                .with_trailers(async move { Some(Ok(trailers)) })
+                .boxed(),
+        )
+        .unwrap()
+}
+
+pub fn find_available_port(ip: IpAddr, start_port: u16) -> u16 {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    rt.block_on(async {
+        for port in start_port..=65535 {
+            if listen_address::bind_tcp(SocketAddr::new(ip, port)).is_ok() {
+                return port;
+            }
+        }
+        // Fallback to start_port if no port is available (unlikely)
+        start_port
+    })
+}
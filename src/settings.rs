@@ -0,0 +1,250 @@
+use crate::auth::{self, JwtKeys};
+use crate::capture_store::CaptureStore;
+use crate::protobuf::{self, ProtoSchemas};
+use crate::timestamps::{self, TimestampDisplay};
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+
+/// How strictly incoming requests are parsed.
+#[derive(Clone, Debug)]
+pub struct ParserOptions {
+    /// Passed to hyper: accept `GET  /path  HTTP/1.1`.
+    pub allow_multiple_spaces_in_request_line: bool,
+    /// Passed to hyper: drop malformed header lines instead of rejecting the request.
+    pub ignore_invalid_headers: bool,
+    /// Fallback parsing of rejected requests: accept `Name : value`.
+    pub allow_spaces_in_header_names: bool,
+    /// Fallback parsing of rejected requests: join continuation lines starting
+    /// with whitespace to the previous header.
+    pub allow_obsolete_line_folding: bool,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            allow_multiple_spaces_in_request_line: false,
+            ignore_invalid_headers: false,
+            allow_spaces_in_header_names: true,
+            allow_obsolete_line_folding: true,
+        }
+    }
+}
+
+pub fn render_settings(
+    ui: &mut egui::Ui,
+    parser: &mut ParserOptions,
+    capture: &mut CaptureStore,
+    timestamps: &mut TimestampDisplay,
+    proto_schemas: &Arc<Mutex<ProtoSchemas>>,
+    jwt_keys: &Arc<Mutex<JwtKeys>>,
+) {
+    ui.heading("Settings");
+    ui.separator();
+
+    egui::ScrollArea::both().show(ui, |ui| {
+        ui.add_space(10.0);
+
+        // Parser leniency section
+        ui.label(egui::RichText::new("Parser Leniency").heading());
+        ui.add_space(5.0);
+
+        ui.checkbox(
+            &mut parser.allow_multiple_spaces_in_request_line,
+            "Allow multiple spaces in the request line",
+        );
+        ui.checkbox(
+            &mut parser.ignore_invalid_headers,
+            "Ignore invalid header lines",
+        );
+        ui.add_space(5.0);
+
+        ui.label(egui::RichText::new("Fallback parsing of rejected requests:").small());
+        ui.checkbox(
+            &mut parser.allow_spaces_in_header_names,
+            "Accept spaces in header names (Name : value)",
+        );
+        ui.checkbox(
+            &mut parser.allow_obsolete_line_folding,
+            "Accept obsolete line folding",
+        );
+
+        ui.add_space(10.0);
+        ui.label(
+            egui::RichText::new(
+                "ℹ Requests the server rejects are still listed. With fallback parsing their headers and body are recovered from the raw bytes; hyper rejects the request (400, or the connection is closed).\nParser changes apply to new connections.",
+            )
+            .small()
+            .color(egui::Color32::LIGHT_BLUE),
+        );
+
+        ui.add_space(20.0);
+        ui.separator();
+        ui.add_space(10.0);
+
+        // Capture limits section
+        ui.label(egui::RichText::new("Capture Limits").heading());
+        ui.add_space(5.0);
+        render_capture_limits(ui, capture);
+
+        ui.add_space(20.0);
+        ui.separator();
+        ui.add_space(10.0);
+
+        // Timestamps section
+        ui.label(egui::RichText::new("Timestamps").heading());
+        ui.add_space(5.0);
+        timestamps::render_timestamp_settings(ui, timestamps);
+
+        ui.add_space(20.0);
+        ui.separator();
+        ui.add_space(10.0);
+
+        // Protobuf schemas section
+        ui.label(egui::RichText::new("Protobuf Schemas").heading());
+        ui.add_space(5.0);
+
+        ui.horizontal(|ui| {
+            if ui.button("📂 Load .proto Files").clicked() {
+                protobuf::load_proto_files(ui.ctx().clone(), Arc::clone(proto_schemas));
+            }
+            if ui.button("📂 Load Descriptor Set").clicked() {
+                protobuf::load_descriptor_set(ui.ctx().clone(), Arc::clone(proto_schemas));
+            }
+        });
+        ui.add_space(5.0);
+
+        let mut schemas = proto_schemas.lock().unwrap();
+        if let Some(error) = &schemas.error {
+            ui.label(egui::RichText::new(error).color(egui::Color32::RED));
+        }
+        if schemas.sources.is_empty() {
+            ui.label(egui::RichText::new("No schemas loaded").italics().weak());
+        } else {
+            for source in &schemas.sources {
+                ui.label(egui::RichText::new(source).monospace());
+            }
+            ui.horizontal(|ui| {
+                ui.label(format!("{} message types", schemas.pool.all_messages().len()));
+                if ui.button("🗑 Clear").clicked() {
+                    schemas.clear();
+                }
+            });
+        }
+
+        ui.add_space(10.0);
+        ui.label(
+            egui::RichText::new(
+                "ℹ Protobuf bodies are decoded without a schema by default. Pick a loaded message type in the request details to decode with field names.",
+            )
+            .small()
+            .color(egui::Color32::LIGHT_BLUE),
+        );
+
+        ui.add_space(20.0);
+        ui.separator();
+        ui.add_space(10.0);
+
+        // JWT verification section
+        ui.label(egui::RichText::new("JWT Verification").heading());
+        ui.add_space(5.0);
+
+        let mut keys = jwt_keys.lock().unwrap();
+        ui.horizontal(|ui| {
+            ui.label("HMAC secret:");
+            ui.add(
+                egui::TextEdit::singleline(&mut keys.hmac_secret)
+                    .password(true)
+                    .desired_width(250.0),
+            );
+            ui.checkbox(&mut keys.secret_is_base64, "Base64-encoded");
+        });
+        ui.add_space(5.0);
+
+        ui.horizontal(|ui| {
+            if ui.button("📂 Load JWKS").clicked() {
+                auth::load_jwks(ui.ctx().clone(), Arc::clone(jwt_keys));
+            }
+            if keys.jwks.is_some() && ui.button("🗑 Clear").clicked() {
+                keys.clear_jwks();
+            }
+        });
+        if let Some(error) = &keys.error {
+            ui.label(egui::RichText::new(error).color(egui::Color32::RED));
+        }
+        match (&keys.jwks_source, &keys.jwks) {
+            (Some(source), Some(jwks)) => {
+                ui.label(egui::RichText::new(source).monospace());
+                ui.label(format!("{} keys", jwks.keys.len()));
+            }
+            _ => {
+                ui.label(egui::RichText::new("No JWKS loaded").italics().weak());
+            }
+        }
+
+        ui.add_space(10.0);
+        ui.label(
+            egui::RichText::new(
+                "ℹ JWTs found in headers, cookies, query parameters and body fields are decoded in the request details. HS256/384/512 signatures are checked against the HMAC secret, others against the JWKS key named by the token's kid.",
+            )
+            .small()
+            .color(egui::Color32::LIGHT_BLUE),
+        );
+    });
+}
+
+fn render_capture_limits(ui: &mut egui::Ui, capture: &mut CaptureStore) {
+    const MB: usize = 1024 * 1024;
+    let limits = &mut capture.limits;
+    let mut body_mb = limits.max_body_size / MB;
+    let mut budget_mb = limits.memory_budget / MB;
+
+    egui::Grid::new("capture_limits")
+        .num_columns(2)
+        .spacing([10.0, 5.0])
+        .show(ui, |ui| {
+            ui.label("Max stored requests:");
+            ui.add(egui::DragValue::new(&mut limits.max_requests).range(1..=1_000_000))
+                .on_hover_text("Closed connections are limited to the same count");
+            ui.end_row();
+
+            ui.label("Max body size:");
+            ui.add(
+                egui::DragValue::new(&mut body_mb)
+                    .range(1..=4096)
+                    .suffix(" MB"),
+            );
+            ui.end_row();
+
+            ui.label("Memory budget:");
+            ui.add(
+                egui::DragValue::new(&mut budget_mb)
+                    .range(16..=65536)
+                    .suffix(" MB"),
+            );
+            ui.end_row();
+        });
+    ui.checkbox(
+        &mut limits.spill_to_disk,
+        "Write bodies over the size limit to a temporary file in full",
+    );
+    limits.max_body_size = body_mb * MB;
+    limits.memory_budget = budget_mb * MB;
+    capture.enforce_limits();
+
+    ui.add_space(5.0);
+    ui.label(format!(
+        "In use: {:.1} MB of {} MB, {} requests evicted",
+        capture.memory_used() as f64 / MB as f64,
+        budget_mb,
+        capture.evicted
+    ));
+
+    ui.add_space(10.0);
+    ui.label(
+        egui::RichText::new(
+            "ℹ The oldest requests are dropped when either limit is reached. Larger bodies keep their first bytes, total size and SHA-256; spilled files are deleted with their request.\nBody limits apply to new requests.",
+        )
+        .small()
+        .color(egui::Color32::LIGHT_BLUE),
+    );
+}
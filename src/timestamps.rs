@@ -0,0 +1,229 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, Offset, SecondsFormat, Utc};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+/// Storage key of the timestamp display settings.
+pub const SETTINGS_KEY: &str = "timestamp_display";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimeZoneChoice {
+    Local,
+    Utc,
+    /// Fixed offset east of UTC, in minutes.
+    Fixed(i32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimeFormat {
+    /// `2024-05-01T14:03:05.123+02:00`, the date only where needed.
+    Iso8601,
+    /// `5s ago`.
+    Relative,
+    /// Unix seconds with milliseconds.
+    Epoch,
+}
+
+impl TimeFormat {
+    const ALL: [TimeFormat; 3] = [TimeFormat::Iso8601, TimeFormat::Relative, TimeFormat::Epoch];
+
+    fn label(self) -> &'static str {
+        match self {
+            TimeFormat::Iso8601 => "ISO 8601",
+            TimeFormat::Relative => "Relative",
+            TimeFormat::Epoch => "Unix epoch",
+        }
+    }
+}
+
+/// How capture times are shown, kept across sessions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimestampDisplay {
+    pub zone: TimeZoneChoice,
+    pub format: TimeFormat,
+}
+
+impl Default for TimestampDisplay {
+    fn default() -> Self {
+        Self {
+            zone: TimeZoneChoice::Local,
+            format: TimeFormat::Iso8601,
+        }
+    }
+}
+
+impl TimestampDisplay {
+    fn in_zone(&self, time: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self.zone {
+            TimeZoneChoice::Local => time.with_timezone(&Local).fixed_offset(),
+            TimeZoneChoice::Utc => time.fixed_offset(),
+            TimeZoneChoice::Fixed(minutes) => {
+                let offset = FixedOffset::east_opt(minutes * 60).unwrap_or(Utc.fix());
+                time.with_timezone(&offset)
+            }
+        }
+    }
+
+    /// Calendar date in the display zone, to tell whether captures span days.
+    pub fn date(&self, time: DateTime<Utc>) -> NaiveDate {
+        self.in_zone(time).date_naive()
+    }
+
+    /// `time` in the chosen zone and format. ISO 8601 times leave out the
+    /// date and offset unless `with_date` is set.
+    pub fn format(&self, time: DateTime<Utc>, with_date: bool) -> String {
+        match self.format {
+            TimeFormat::Iso8601 if with_date => self
+                .in_zone(time)
+                .to_rfc3339_opts(SecondsFormat::Millis, true),
+            TimeFormat::Iso8601 => self.in_zone(time).format("%H:%M:%S%.3f").to_string(),
+            TimeFormat::Relative => {
+                let elapsed = (Utc::now() - time).num_seconds().max(0).unsigned_abs();
+                format!("{} ago", format_span(elapsed))
+            }
+            TimeFormat::Epoch => format!("{:.3}", time.timestamp_millis() as f64 / 1000.0),
+        }
+    }
+
+    /// Relative times go stale, so views showing them repaint every second.
+    pub fn request_repaint(&self, ctx: &egui::Context) {
+        if self.format == TimeFormat::Relative {
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        }
+    }
+}
+
+fn format_span(seconds: u64) -> String {
+    match seconds {
+        0..60 => format!("{}s", seconds),
+        60..3600 => format!("{}m {}s", seconds / 60, seconds % 60),
+        3600..86400 => format!("{}h {}m", seconds / 3600, seconds / 60 % 60),
+        _ => format!("{}d {}h", seconds / 86400, seconds / 3600 % 24),
+    }
+}
+
+fn format_offset(minutes: i32) -> String {
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let minutes = minutes.unsigned_abs();
+    format!("UTC{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+}
+
+/// Accepts `UTC+02:00`, `-5:30`, `+2` (hours) or plain minutes.
+fn parse_offset(text: &str) -> Option<f64> {
+    let text = text.trim().trim_start_matches("UTC");
+    match text.split_once(':') {
+        Some((hours, minutes)) => {
+            let hours: i32 = hours.parse().ok()?;
+            let minutes: i32 = minutes.parse().ok()?;
+            let sign = if text.starts_with('-') { -1 } else { 1 };
+            Some((hours * 60 + sign * minutes) as f64)
+        }
+        None if text.starts_with(['+', '-']) => text.parse::<f64>().ok().map(|hours| hours * 60.0),
+        None => text.parse().ok(),
+    }
+}
+
+pub fn render_timestamp_settings(ui: &mut egui::Ui, display: &mut TimestampDisplay) {
+    ui.horizontal(|ui| {
+        ui.label("Time zone:");
+        ui.radio_value(&mut display.zone, TimeZoneChoice::Local, "Local");
+        ui.radio_value(&mut display.zone, TimeZoneChoice::Utc, "UTC");
+        let custom = matches!(display.zone, TimeZoneChoice::Fixed(_));
+        if ui.radio(custom, "Custom offset").clicked() && !custom {
+            display.zone = TimeZoneChoice::Fixed(0);
+        }
+        if let TimeZoneChoice::Fixed(minutes) = &mut display.zone {
+            ui.add(
+                egui::DragValue::new(minutes)
+                    .range(-720..=840)
+                    .speed(15.0)
+                    .custom_formatter(|minutes, _| format_offset(minutes as i32))
+                    .custom_parser(parse_offset),
+            )
+            .on_hover_text("Drag, or type an offset like +05:30");
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Format:");
+        for format in TimeFormat::ALL {
+            ui.radio_value(&mut display.format, format, format.label());
+        }
+    });
+
+    ui.add_space(5.0);
+    ui.label(
+        egui::RichText::new(format!("Example: {}", display.format(Utc::now(), true)))
+            .monospace()
+            .weak(),
+    );
+
+    ui.add_space(10.0);
+    ui.label(
+        egui::RichText::new(
+            "ℹ Requests are captured with a UTC time and a sequence number, so sorting does not depend on these settings. The request list shows dates only when captures span several days.",
+        )
+        .small()
+        .color(egui::Color32::LIGHT_BLUE),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn offsets_parse_in_every_accepted_form() {
+        for (text, minutes) in [
+            ("UTC+02:00", 120.0),
+            ("+05:30", 330.0),
+            ("-5:30", -330.0),
+            ("-0:30", -30.0),
+            ("UTC-00:45", -45.0),
+            ("+2", 120.0),
+            ("-1.5", -90.0),
+            ("90", 90.0),
+            (" UTC+01:00 ", 60.0),
+        ] {
+            assert_eq!(parse_offset(text), Some(minutes), "{}", text);
+        }
+        for text in ["", "UTC", "+x", "1:xx", "a:30"] {
+            assert_eq!(parse_offset(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn offsets_format_back_to_what_they_parse_from() {
+        for minutes in [-720, -330, -30, 0, 45, 840] {
+            let text = format_offset(minutes);
+            assert_eq!(parse_offset(&text), Some(minutes as f64), "{}", text);
+        }
+        assert_eq!(format_offset(-30), "UTC-00:30");
+    }
+
+    #[test]
+    fn times_follow_the_zone_and_format() {
+        let time = Utc.with_ymd_and_hms(2024, 5, 1, 23, 30, 5).unwrap();
+        let mut display = TimestampDisplay {
+            zone: TimeZoneChoice::Utc,
+            format: TimeFormat::Iso8601,
+        };
+        assert_eq!(display.format(time, false), "23:30:05.000");
+        assert_eq!(display.format(time, true), "2024-05-01T23:30:05.000Z");
+
+        display.zone = TimeZoneChoice::Fixed(90);
+        assert_eq!(display.format(time, true), "2024-05-02T01:00:05.000+01:30");
+        assert_eq!(display.date(time).to_string(), "2024-05-02");
+
+        display.format = TimeFormat::Epoch;
+        assert_eq!(display.format(time, false), "1714606205.000");
+    }
+
+    #[test]
+    fn spans_use_the_two_largest_units() {
+        assert_eq!(format_span(59), "59s");
+        assert_eq!(format_span(61), "1m 1s");
+        assert_eq!(format_span(3 * 3600 + 120), "3h 2m");
+        assert_eq!(format_span(2 * 86400 + 5 * 3600), "2d 5h");
+    }
+}
@@ -0,0 +1,119 @@
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+/// Monotonic timestamps of the stages of a request, `None` where unknown.
+#[derive(Clone, Debug, Default)]
+pub struct RequestTimings {
+    /// Set for the first request on a connection only.
+    pub accepted: Option<Instant>,
+    /// Not known for HTTP/2, whose frames interleave requests.
+    pub first_byte: Option<Instant>,
+    pub headers: Option<Instant>,
+    pub body_complete: Option<Instant>,
+    pub response_start: Option<Instant>,
+    /// When the response body was handed to the connection.
+    pub response_end: Option<Instant>,
+}
+
+impl RequestTimings {
+    /// From the first byte of the request to the end of the response.
+    pub fn total(&self) -> Option<Duration> {
+        let start = self.first_byte.or(self.headers)?;
+        Some(self.response_end? - start)
+    }
+
+    /// Name, start and end of each stage whose bounds are known.
+    fn phases(&self) -> Vec<(&'static str, egui::Color32, Instant, Instant)> {
+        let stages = [
+            (
+                "Connection",
+                egui::Color32::GRAY,
+                self.accepted,
+                self.first_byte.or(self.headers),
+            ),
+            (
+                "Request head",
+                egui::Color32::from_rgb(86, 156, 214),
+                self.first_byte,
+                self.headers,
+            ),
+            (
+                "Request body",
+                egui::Color32::from_rgb(78, 201, 176),
+                self.headers,
+                self.body_complete,
+            ),
+            (
+                "Server",
+                egui::Color32::from_rgb(220, 220, 170),
+                self.body_complete,
+                self.response_start,
+            ),
+            (
+                "Response",
+                egui::Color32::from_rgb(197, 134, 192),
+                self.response_start,
+                self.response_end,
+            ),
+        ];
+        stages
+            .into_iter()
+            .filter_map(|(name, color, start, end)| Some((name, color, start?, end?)))
+            .collect()
+    }
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let ms = duration.as_secs_f64() * 1000.0;
+    if ms < 1000.0 {
+        format!("{:.1} ms", ms)
+    } else {
+        format!("{:.2} s", ms / 1000.0)
+    }
+}
+
+/// One bar per stage, placed on a shared time axis.
+pub fn render_waterfall(ui: &mut egui::Ui, timings: &RequestTimings) {
+    let phases = timings.phases();
+    let (Some(origin), Some(end)) = (
+        phases.iter().map(|p| p.2).min(),
+        phases.iter().map(|p| p.3).max(),
+    ) else {
+        ui.label(egui::RichText::new("No timings recorded").weak());
+        return;
+    };
+    let span = (end - origin).as_secs_f32().max(f32::EPSILON);
+
+    egui::Grid::new("timing_waterfall")
+        .num_columns(3)
+        .spacing([10.0, 4.0])
+        .show(ui, |ui| {
+            for (name, color, start, end) in phases {
+                ui.label(name);
+
+                let (rect, response) =
+                    ui.allocate_exact_size(egui::vec2(300.0, 12.0), egui::Sense::hover());
+                let painter = ui.painter();
+                painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+                let left = rect.left() + rect.width() * (start - origin).as_secs_f32() / span;
+                let right = rect.left() + rect.width() * (end - origin).as_secs_f32() / span;
+                let bar = egui::Rect::from_x_y_ranges(left..=right.max(left + 1.0), rect.y_range());
+                painter.rect_filled(bar, 2.0, color);
+                response.on_hover_text(format!("Starts at +{}", format_duration(start - origin)));
+
+                ui.label(egui::RichText::new(format_duration(end - start)).monospace());
+                ui.end_row();
+            }
+        });
+
+    if timings.accepted.is_none() && timings.first_byte.is_some() {
+        ui.label(
+            egui::RichText::new("Sent on a reused keep-alive connection")
+                .small()
+                .weak(),
+        );
+    }
+    if let Some(total) = timings.total() {
+        ui.label(egui::RichText::new(format!("Total: {}", format_duration(total))).strong());
+    }
+}
@@ -0,0 +1,262 @@
+use serde_json::Value;
+
+/// Bracket paths nest at most this deep; the rest of a longer key is kept as
+/// a literal name.
+const MAX_NESTING: usize = 32;
+
+/// A `name=value` pair with the text it was decoded from.
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: String,
+    pub value: String,
+    pub raw: String,
+    /// Set when an escape was invalid or the decoded bytes were not UTF-8.
+    pub error: Option<String>,
+}
+
+/// Splits `a=1&b=2` into decoded pairs, keeping order and duplicate keys.
+/// With `plus_as_space` a `+` decodes to a space as in HTML form bodies.
+pub fn parse_pairs(input: &str, plus_as_space: bool) -> Vec<(String, String)> {
+    parse_params(input, plus_as_space)
+        .into_iter()
+        .map(|param| (param.name, param.value))
+        .collect()
+}
+
+/// Like `parse_pairs`, keeping the raw text of each pair and what did not
+/// decode cleanly.
+pub fn parse_params(input: &str, plus_as_space: bool) -> Vec<Param> {
+    input
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let (name, name_error) = decode_component(name, plus_as_space);
+            let (value, value_error) = decode_component(value, plus_as_space);
+            Param {
+                name,
+                value,
+                raw: pair.to_string(),
+                error: name_error.or(value_error),
+            }
+        })
+        .collect()
+}
+
+/// Percent-decodes `input`. Invalid escapes are kept as written and bytes
+/// that are not UTF-8 are replaced; either is reported.
+fn decode_component(input: &str, plus_as_space: bool) -> (String, Option<String>) {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut error = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .filter(|digits| digits.iter().all(u8::is_ascii_hexdigit))
+                    .and_then(|digits| {
+                        u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()
+                    });
+                if let Some(byte) = hex {
+                    decoded.push(byte);
+                    i += 3;
+                    continue;
+                }
+                let escape: String = input[i..].chars().take(3).collect();
+                error.get_or_insert_with(|| format!("invalid percent-encoding `{}`", escape));
+                decoded.push(b'%');
+            }
+            b'+' if plus_as_space => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    match String::from_utf8(decoded) {
+        Ok(text) => (text, error),
+        Err(e) => (
+            String::from_utf8_lossy(e.as_bytes()).into_owned(),
+            error.or_else(|| Some(String::from("decoded bytes are not valid UTF-8"))),
+        ),
+    }
+}
+
+/// Builds a tree from bracketed keys: `a[b][c]=1` becomes `{"a":{"b":{"c":"1"}}}`,
+/// `ids[]=1&ids[]=2` an array and repeated plain keys an array of their values.
+pub fn nested_tree(pairs: &[(String, String)]) -> Value {
+    let mut root = Value::Object(Default::default());
+    for (key, value) in pairs {
+        insert_path(&mut root, &key_segments(key), value.clone());
+    }
+    root
+}
+
+/// Splits `a[b][]` into `["a", "b", ""]`. Keys that are not well-formed
+/// bracket paths are kept as a single segment, and brackets beyond
+/// `MAX_NESTING` levels as the last segment.
+fn key_segments(key: &str) -> Vec<String> {
+    let Some(open) = key.find('[') else {
+        return vec![key.to_string()];
+    };
+    if open == 0 || !key.ends_with(']') {
+        return vec![key.to_string()];
+    }
+
+    let mut segments = vec![key[..open].to_string()];
+    let mut rest = &key[open..];
+    while let Some(inner) = rest.strip_prefix('[') {
+        if segments.len() >= MAX_NESTING {
+            segments.push(rest.to_string());
+            return segments;
+        }
+        let Some(close) = inner.find(']') else {
+            return vec![key.to_string()];
+        };
+        segments.push(inner[..close].to_string());
+        rest = &inner[close + 1..];
+    }
+    if !rest.is_empty() {
+        return vec![key.to_string()];
+    }
+    segments
+}
+
+fn insert_path(node: &mut Value, segments: &[String], value: String) {
+    let Some((segment, rest)) = segments.split_first() else {
+        // Leaf: repeated keys collect into an array
+        match node {
+            Value::Null => *node = Value::String(value),
+            Value::Array(items) => items.push(Value::String(value)),
+            Value::Object(map) => {
+                map.insert(String::new(), Value::String(value));
+            }
+            other => {
+                let previous = other.take();
+                *other = Value::Array(vec![previous, Value::String(value)]);
+            }
+        }
+        return;
+    };
+
+    if segment.is_empty() {
+        // `[]` appends a new element
+        if !node.is_array() {
+            let previous = node.take();
+            *node = Value::Array(if previous.is_null() {
+                Vec::new()
+            } else {
+                vec![previous]
+            });
+        }
+        let Value::Array(items) = node else {
+            unreachable!()
+        };
+        items.push(Value::Null);
+        insert_path(items.last_mut().unwrap(), rest, value);
+    } else {
+        if !node.is_object() {
+            let previous = node.take();
+            let mut map = serde_json::Map::new();
+            match previous {
+                Value::Null => {}
+                Value::Array(items) => {
+                    for (index, item) in items.into_iter().enumerate() {
+                        map.insert(index.to_string(), item);
+                    }
+                }
+                other => {
+                    map.insert(String::new(), other);
+                }
+            }
+            *node = Value::Object(map);
+        }
+        let Value::Object(map) = node else {
+            unreachable!()
+        };
+        let child = map.entry(segment.clone()).or_insert(Value::Null);
+        insert_path(child, rest, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_escapes_decode() {
+        assert_eq!(
+            decode_component("caf%C3%A9+au%20lait", true),
+            ("café au lait".to_string(), None)
+        );
+        assert_eq!(decode_component("a+b", false), ("a+b".to_string(), None));
+    }
+
+    #[test]
+    fn invalid_escapes_are_kept_and_reported() {
+        for (input, kept, escape) in [
+            ("100%", "100%", "%"),
+            ("%4", "%4", "%4"),
+            ("%zz", "%zz", "%zz"),
+            // `from_str_radix` alone would accept a sign
+            ("%+1", "%+1", "%+1"),
+            ("%é", "%é", "%é"),
+        ] {
+            let (decoded, error) = decode_component(input, false);
+            assert_eq!(decoded, kept);
+            assert_eq!(
+                error.as_deref(),
+                Some(format!("invalid percent-encoding `{}`", escape).as_str())
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_is_replaced_and_reported() {
+        let (decoded, error) = decode_component("%FF%FE", false);
+        assert_eq!(decoded, "\u{fffd}\u{fffd}");
+        assert_eq!(error.as_deref(), Some("decoded bytes are not valid UTF-8"));
+    }
+
+    #[test]
+    fn params_keep_their_raw_text() {
+        let params = parse_params("q=a%2Gb&&flag&x=%41", true);
+        let fields: Vec<(&str, &str, &str, bool)> = params
+            .iter()
+            .map(|p| {
+                (
+                    p.name.as_str(),
+                    p.value.as_str(),
+                    p.raw.as_str(),
+                    p.error.is_some(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("q", "a%2Gb", "q=a%2Gb", true),
+                ("flag", "", "flag", false),
+                ("x", "A", "x=%41", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn deep_bracket_paths_keep_the_rest_as_a_literal() {
+        let key = format!("a{}", "[x]".repeat(100_000));
+        let segments = key_segments(&key);
+        assert_eq!(segments.len(), MAX_NESTING + 1);
+        assert_eq!(
+            segments[MAX_NESTING],
+            "[x]".repeat(100_000 - (MAX_NESTING - 1))
+        );
+
+        let tree = nested_tree(&[(key, "1".to_string())]);
+        let mut node = &tree["a"];
+        for _ in 1..MAX_NESTING {
+            node = &node["x"];
+        }
+        assert_eq!(node.as_object().map(|map| map.len()), Some(1));
+    }
+}
@@ -0,0 +1,361 @@
+use eframe::egui;
+use roxmltree::{Document, Node, NodeType};
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+const SOAP_11_NAMESPACE: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+const SOAP_12_NAMESPACE: &str = "http://www.w3.org/2003/05/soap-envelope";
+
+const ELEMENT_COLOR: egui::Color32 = egui::Color32::from_rgb(86, 156, 214);
+const ATTRIBUTE_COLOR: egui::Color32 = egui::Color32::from_rgb(156, 220, 254);
+const VALUE_COLOR: egui::Color32 = egui::Color32::from_rgb(206, 145, 120);
+
+/// Returns true if the content type names an XML media type, or if it does
+/// not name anything more specific and the body looks like an XML document.
+pub fn is_xml(content_type: &str, body: &[u8]) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+
+    if mime.ends_with("/xml") || mime.ends_with("+xml") {
+        return true;
+    }
+    if !(mime.is_empty() || mime == "text/plain" || mime == "application/octet-stream") {
+        return false;
+    }
+
+    let text = String::from_utf8_lossy(&body[..body.len().min(256)]);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    text.starts_with("<?xml")
+        || text
+            .strip_prefix('<')
+            .and_then(|rest| rest.chars().next())
+            .is_some_and(|c| c.is_alphabetic())
+}
+
+/// Parses an XML document, allowing a DTD so SOAP and RSS payloads that
+/// carry a doctype still load.
+pub fn parse(text: &str) -> Result<Document<'_>, roxmltree::Error> {
+    let options = roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    Document::parse_with_options(text.trim_start_matches('\u{feff}'), options)
+}
+
+/// Describes a SOAP envelope, e.g. `SOAP 1.1 · GetQuote`.
+fn soap_summary(doc: &Document) -> Option<String> {
+    let envelope = doc.root_element();
+    let version = match envelope.tag_name().namespace() {
+        Some(SOAP_11_NAMESPACE) => "SOAP 1.1",
+        Some(SOAP_12_NAMESPACE) => "SOAP 1.2",
+        _ => return None,
+    };
+    if envelope.tag_name().name() != "Envelope" {
+        return None;
+    }
+
+    let operation = envelope
+        .children()
+        .find(|n| n.is_element() && n.tag_name().name() == "Body")
+        .and_then(|body| body.children().find(|n| n.is_element()))
+        .map(|op| qualified_name(op));
+    Some(match operation {
+        Some(op) => format!("{} · {}", version, op),
+        None => version.to_string(),
+    })
+}
+
+/// A parsed document copied out of its source text, so it can be kept
+/// across frames instead of being parsed on each one.
+pub struct Tree {
+    nodes: Vec<TreeNode>,
+    /// SOAP version and operation, see [`soap_summary`].
+    pub soap_summary: Option<String>,
+}
+
+enum TreeNode {
+    Element(Element),
+    Text(String),
+    Comment(String),
+    Pi(String),
+}
+
+struct Element {
+    id: roxmltree::NodeId,
+    name: String,
+    namespace: Option<String>,
+    /// `xmlns` declarations followed by `@`-prefixed attributes.
+    attributes: Vec<(String, String)>,
+    children: Vec<TreeNode>,
+}
+
+impl Tree {
+    pub fn new(doc: &Document) -> Self {
+        Self {
+            nodes: doc.root().children().filter_map(tree_node).collect(),
+            soap_summary: soap_summary(doc),
+        }
+    }
+}
+
+/// Copies a node, dropping whitespace-only text.
+fn tree_node(node: Node) -> Option<TreeNode> {
+    match node.node_type() {
+        NodeType::Element => {
+            let mut attributes: Vec<(String, String)> = declared_namespaces(node)
+                .into_iter()
+                .map(|(prefix, uri)| {
+                    let attr = match prefix {
+                        Some(prefix) => format!("xmlns:{}", prefix),
+                        None => "xmlns".to_string(),
+                    };
+                    (attr, uri.to_string())
+                })
+                .collect();
+            for attr in node.attributes() {
+                let attr_name = match attr.namespace().and_then(|uri| prefix_for(node, uri)) {
+                    Some(prefix) => format!("{}:{}", prefix, attr.name()),
+                    None => attr.name().to_string(),
+                };
+                attributes.push((format!("@{}", attr_name), attr.value().to_string()));
+            }
+            Some(TreeNode::Element(Element {
+                id: node.id(),
+                name: qualified_name(node),
+                namespace: node.tag_name().namespace().map(str::to_string),
+                attributes,
+                children: node.children().filter_map(tree_node).collect(),
+            }))
+        }
+        NodeType::Text => {
+            let text = node.text().unwrap_or("").trim();
+            (!text.is_empty()).then(|| TreeNode::Text(text.to_string()))
+        }
+        NodeType::Comment => Some(TreeNode::Comment(node.text().unwrap_or("").to_string())),
+        NodeType::PI => node
+            .pi()
+            .map(|pi| TreeNode::Pi(format!("<?{} {}?>", pi.target, pi.value.unwrap_or("")))),
+        NodeType::Root => None,
+    }
+}
+
+/// Renders the document as a collapsible element tree. Elements start
+/// expanded unless `collapsed` is set.
+pub fn render_tree(ui: &mut egui::Ui, id: egui::Id, tree: &Tree, collapsed: bool) {
+    for node in &tree.nodes {
+        render_node(ui, id, node, collapsed);
+    }
+}
+
+fn render_node(ui: &mut egui::Ui, id: egui::Id, node: &TreeNode, collapsed: bool) {
+    match node {
+        TreeNode::Element(element) => render_element(ui, id, element, collapsed),
+        TreeNode::Text(text) => {
+            ui.label(egui::RichText::new(text).monospace());
+        }
+        TreeNode::Comment(text) => {
+            ui.label(
+                egui::RichText::new(format!("<!--{}-->", text))
+                    .monospace()
+                    .italics()
+                    .weak(),
+            );
+        }
+        TreeNode::Pi(pi) => {
+            ui.label(egui::RichText::new(pi).monospace().weak());
+        }
+    }
+}
+
+fn render_element(ui: &mut egui::Ui, id: egui::Id, element: &Element, collapsed: bool) {
+    let name = &element.name;
+
+    // Leaf elements with only text are shown inline as `name  value`
+    if element.attributes.is_empty()
+        && element
+            .children
+            .iter()
+            .all(|child| matches!(child, TreeNode::Text(_)))
+    {
+        ui.horizontal_wrapped(|ui| {
+            ui.label(egui::RichText::new(name).monospace().color(ELEMENT_COLOR));
+            match element.children.first() {
+                Some(TreeNode::Text(text)) => {
+                    ui.label(egui::RichText::new(text).monospace());
+                }
+                _ => {
+                    ui.label(egui::RichText::new("(empty)").italics().weak());
+                }
+            }
+        });
+        return;
+    }
+
+    egui::CollapsingHeader::new(egui::RichText::new(name).monospace().color(ELEMENT_COLOR))
+        .id_salt(id.with(element.id))
+        .default_open(!collapsed)
+        .show(ui, |ui| {
+            if let Some(uri) = &element.namespace {
+                ui.label(
+                    egui::RichText::new(format!("namespace: {}", uri))
+                        .small()
+                        .weak(),
+                );
+            }
+            for (name, value) in &element.attributes {
+                render_attribute(ui, name, value);
+            }
+            for child in &element.children {
+                render_node(ui, id, child, collapsed);
+            }
+        });
+}
+
+fn render_attribute(ui: &mut egui::Ui, name: &str, value: &str) {
+    ui.horizontal_wrapped(|ui| {
+        ui.label(egui::RichText::new(name).monospace().color(ATTRIBUTE_COLOR));
+        ui.label(
+            egui::RichText::new(format!("\"{}\"", value))
+                .monospace()
+                .color(VALUE_COLOR),
+        );
+    });
+}
+
+/// Re-serializes the document with two-space indentation. Whitespace-only
+/// text between elements is dropped; other text is trimmed.
+pub fn pretty_print(text: &str, doc: &Document) -> String {
+    let mut out = String::new();
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    if text.starts_with("<?xml")
+        && let Some(end) = text.find("?>")
+    {
+        out.push_str(&text[..end + 2]);
+        out.push('\n');
+    }
+    for node in doc.root().children() {
+        write_node(&mut out, node, 0);
+    }
+    out
+}
+
+fn write_node(out: &mut String, node: Node, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match node.node_type() {
+        NodeType::Element => {
+            let name = qualified_name(node);
+            out.push_str(&indent);
+            out.push('<');
+            out.push_str(&name);
+            for (prefix, uri) in declared_namespaces(node) {
+                match prefix {
+                    Some(prefix) => out.push_str(&format!(" xmlns:{}=\"", prefix)),
+                    None => out.push_str(" xmlns=\""),
+                }
+                out.push_str(&escape(uri, true));
+                out.push('"');
+            }
+            for attr in node.attributes() {
+                out.push(' ');
+                if let Some(prefix) = attr.namespace().and_then(|uri| prefix_for(node, uri)) {
+                    out.push_str(prefix);
+                    out.push(':');
+                }
+                out.push_str(attr.name());
+                out.push_str("=\"");
+                out.push_str(&escape(attr.value(), true));
+                out.push('"');
+            }
+
+            let children: Vec<Node> = node
+                .children()
+                .filter(|child| !(child.is_text() && child.text().unwrap_or("").trim().is_empty()))
+                .collect();
+            match children.as_slice() {
+                [] => out.push_str("/>\n"),
+                [only] if only.is_text() => {
+                    out.push('>');
+                    out.push_str(&escape(only.text().unwrap_or("").trim(), false));
+                    out.push_str(&format!("</{}>\n", name));
+                }
+                _ => {
+                    out.push_str(">\n");
+                    for child in children {
+                        write_node(out, child, depth + 1);
+                    }
+                    out.push_str(&format!("{}</{}>\n", indent, name));
+                }
+            }
+        }
+        NodeType::Text => {
+            let text = node.text().unwrap_or("").trim();
+            if !text.is_empty() {
+                out.push_str(&format!("{}{}\n", indent, escape(text, false)));
+            }
+        }
+        NodeType::Comment => {
+            out.push_str(&format!("{}<!--{}-->\n", indent, node.text().unwrap_or("")));
+        }
+        NodeType::PI => {
+            if let Some(pi) = node.pi() {
+                match pi.value {
+                    Some(value) => {
+                        out.push_str(&format!("{}<?{} {}?>\n", indent, pi.target, value))
+                    }
+                    None => out.push_str(&format!("{}<?{}?>\n", indent, pi.target)),
+                }
+            }
+        }
+        NodeType::Root => {}
+    }
+}
+
+/// Element name with the prefix it was written with, e.g. `soap:Envelope`.
+fn qualified_name(node: Node) -> String {
+    let tag = node.tag_name();
+    match tag.namespace().and_then(|uri| prefix_for(node, uri)) {
+        Some(prefix) => format!("{}:{}", prefix, tag.name()),
+        None => tag.name().to_string(),
+    }
+}
+
+fn prefix_for<'a>(node: Node<'a, '_>, uri: &str) -> Option<&'a str> {
+    if uri == XML_NAMESPACE {
+        return Some("xml");
+    }
+    node.lookup_prefix(uri)
+}
+
+/// Namespaces declared on this element itself rather than inherited.
+fn declared_namespaces<'a>(node: Node<'a, '_>) -> Vec<(Option<&'a str>, &'a str)> {
+    let inherited: Vec<(Option<&str>, &str)> = node
+        .parent_element()
+        .map(|parent| {
+            parent
+                .namespaces()
+                .map(|ns| (ns.name(), ns.uri()))
+                .collect()
+        })
+        .unwrap_or_default();
+    node.namespaces()
+        .map(|ns| (ns.name(), ns.uri()))
+        .filter(|ns| ns.1 != XML_NAMESPACE && !inherited.contains(ns))
+        .collect()
+}
+
+fn escape(text: &str, attribute: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' if attribute => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}